@@ -5,14 +5,14 @@ set -e
 export RUST_LOG="${RUST_LOG:-info}"
 
 # Validate required environment variables (DB or API mode)
-if [ -n "$DB_USERNAME" ] && [ -n "$DB_PASSWORD" ] && [ -n "$DB_DATABASE_NAME" ]; then
+if [ -n "$DB_USERNAME" ] && { [ -n "$DB_PASSWORD" ] || [ -n "$DB_PASSWORD_FILE" ]; } && [ -n "$DB_DATABASE_NAME" ]; then
     # Database mode
     :
 elif [ -n "$IMMICH_API_URL" ] && [ -n "$IMMICH_API_KEY" ]; then
     # API mode
     :
 else
-    echo "ERROR: Either (IMMICH_API_URL + IMMICH_API_KEY) OR (DB_USERNAME + DB_PASSWORD + DB_DATABASE_NAME) must be set"
+    echo "ERROR: Either (IMMICH_API_URL + IMMICH_API_KEY) OR (DB_USERNAME + DB_PASSWORD or DB_PASSWORD_FILE + DB_DATABASE_NAME) must be set"
     exit 1
 fi
 
@@ -42,9 +42,14 @@ case "$MODE" in
 esac
 
 # Add data access mode
-if [ -n "$DB_USERNAME" ] && [ -n "$DB_PASSWORD" ] && [ -n "$DB_DATABASE_NAME" ]; then
+if [ -n "$DB_USERNAME" ] && { [ -n "$DB_PASSWORD" ] || [ -n "$DB_PASSWORD_FILE" ]; } && [ -n "$DB_DATABASE_NAME" ]; then
     args+=("--data-access-mode" "database")
-    args+=("--postgres-url" "postgresql://$DB_USERNAME:$DB_PASSWORD@$DB_HOSTNAME:$DB_PORT/$DB_DATABASE_NAME")
+    if [ -n "$DB_PASSWORD_FILE" ]; then
+        # db_password_file is read from env by clap - keeps the password out of the process list
+        args+=("--postgres-url" "postgresql://$DB_USERNAME@$DB_HOSTNAME:$DB_PORT/$DB_DATABASE_NAME")
+    else
+        args+=("--postgres-url" "postgresql://$DB_USERNAME:$DB_PASSWORD@$DB_HOSTNAME:$DB_PORT/$DB_DATABASE_NAME")
+    fi
     args+=("--immich-root" "/data")
 else
     args+=("--data-access-mode" "immich-api")
@@ -63,6 +68,10 @@ elif [ -n "$IMMICH_ANALYZE_OLLAMA_HOSTS" ]; then
     args+=("--hosts" "$IMMICH_ANALYZE_OLLAMA_HOSTS")
 fi
 
+if [ -n "$IMMICH_ANALYZE_HOSTS_FILE" ]; then
+    args+=("--hosts-file" "$IMMICH_ANALYZE_HOSTS_FILE")
+fi
+
 # api_key are read from env by clap - no need to pass explicitly
 
 if [ -n "$IMMICH_ANALYZE_MODEL_NAME" ]; then
@@ -99,10 +108,247 @@ if [ "${IMMICH_ANALYZE_NO_FINAL_OUTPUT:-false}" = "true" ]; then
     args+=("--no-final-output")
 fi
 
+if [ -n "$IMMICH_ANALYZE_STRIP_THINKING" ]; then
+    args+=("--strip-thinking" "$IMMICH_ANALYZE_STRIP_THINKING")
+fi
+
+if [ "${IMMICH_ANALYZE_SKIP_MODEL_CHECK:-false}" = "true" ]; then
+    args+=("--skip-model-check")
+fi
+
+if [ "${IMMICH_ANALYZE_REQUIRE_ALL_HOSTS:-false}" = "true" ]; then
+    args+=("--require-all-hosts")
+fi
+
+if [ "${IMMICH_ANALYZE_FORMAT_ONLY:-false}" = "true" ]; then
+    args+=("--format-only")
+fi
+
+if [ "${IMMICH_ANALYZE_EXTRACT_FORMAT_TAG:-false}" = "true" ]; then
+    args+=("--extract-format-tag")
+fi
+
+if [ "${IMMICH_ANALYZE_HOST_RECOVERY_PROBE:-false}" = "true" ]; then
+    args+=("--host-recovery-probe")
+fi
+
+if [ "${IMMICH_ANALYZE_SKIP_CORRUPT_IMAGES:-false}" = "true" ]; then
+    args+=("--skip-corrupt-images")
+fi
+
+if [ "${IMMICH_ANALYZE_SHUFFLE:-false}" = "true" ]; then
+    args+=("--shuffle")
+fi
+
+if [ "${IMMICH_ANALYZE_RETRY_FAILED:-false}" = "true" ]; then
+    args+=("--retry-failed")
+fi
+
+if [ "${IMMICH_ANALYZE_USE_FILENAME_HINT:-false}" = "true" ]; then
+    args+=("--use-filename-hint")
+fi
+
+if [ "${IMMICH_ANALYZE_ONCE:-false}" = "true" ]; then
+    args+=("--once")
+fi
+
+if [ "${IMMICH_ANALYZE_PLAIN:-false}" = "true" ]; then
+    args+=("--plain")
+fi
+
+if [ "${IMMICH_ANALYZE_FAIL_FAST:-false}" = "true" ]; then
+    args+=("--fail-fast")
+fi
+
+if [ "${IMMICH_ANALYZE_USE_ORIGINAL_FALLBACK:-false}" = "true" ]; then
+    args+=("--use-original-fallback")
+fi
+
+if [ "${IMMICH_ANALYZE_WRITE_RUN_LOG:-false}" = "true" ]; then
+    args+=("--write-run-log")
+fi
+
+if [ "${IMMICH_ANALYZE_INCLUDE_PEOPLE:-false}" = "true" ]; then
+    args+=("--include-people")
+fi
+
+if [ "${IMMICH_ANALYZE_RESET_CONTEXT:-false}" = "true" ]; then
+    args+=("--reset-context")
+fi
+
+if [ "${IMMICH_ANALYZE_VERIFY_JPEG_EOF:-false}" = "true" ]; then
+    args+=("--verify-jpeg-eof")
+fi
+
+if [ "${IMMICH_ANALYZE_PRINT_PROMPT:-false}" = "true" ]; then
+    args+=("--print-prompt")
+fi
+
+if [ "${IMMICH_ANALYZE_DUMP_REQUEST:-false}" = "true" ]; then
+    args+=("--dump-request")
+fi
+
+if [ "${IMMICH_ANALYZE_CONCURRENCY_AUTO:-false}" = "true" ]; then
+    args+=("--concurrency-auto")
+fi
+
+if [ "${IMMICH_ANALYZE_DIFF:-false}" = "true" ]; then
+    args+=("--diff")
+fi
+
+if [ "${IMMICH_ANALYZE_APPLY:-false}" = "true" ]; then
+    args+=("--apply")
+fi
+
 if [[ "${IMMICH_ANALYZE_WAIT_FOR_IMMICH:-true}" = "false" ]]; then
     args+=("--no-wait-for-immich")
 fi
 
+if [ -n "$IMMICH_ANALYZE_DESCRIPTION_LANGUAGE" ]; then
+    args+=("--description-language" "$IMMICH_ANALYZE_DESCRIPTION_LANGUAGE")
+fi
+
+if [ -n "$IMMICH_ANALYZE_TAG_PREFIX" ]; then
+    args+=("--tag-prefix" "$IMMICH_ANALYZE_TAG_PREFIX")
+fi
+
+if [ -n "$IMMICH_ANALYZE_OUTPUT_CSV" ]; then
+    args+=("--output-csv" "$IMMICH_ANALYZE_OUTPUT_CSV")
+fi
+
+if [ -n "$IMMICH_ANALYZE_NO_PROXY_FOR" ]; then
+    args+=("--no-proxy-for" "$IMMICH_ANALYZE_NO_PROXY_FOR")
+fi
+
+# One "Name: Value" header per line
+if [ -n "$IMMICH_ANALYZE_HOST_HEADERS" ]; then
+    while IFS= read -r header; do
+        [ -n "$header" ] && args+=("--host-header" "$header")
+    done <<< "$IMMICH_ANALYZE_HOST_HEADERS"
+fi
+
+if [ -n "$IMMICH_ANALYZE_OLLAMA_OPTIONS" ]; then
+    while IFS= read -r option; do
+        [ -n "$option" ] && args+=("--ollama-option" "$option")
+    done <<< "$IMMICH_ANALYZE_OLLAMA_OPTIONS"
+fi
+
+if [ -n "$IMMICH_ANALYZE_USER_AGENT" ]; then
+    args+=("--user-agent" "$IMMICH_ANALYZE_USER_AGENT")
+fi
+
+if [ -n "$IMMICH_ANALYZE_FAILURE_CACHE" ]; then
+    args+=("--failure-cache" "$IMMICH_ANALYZE_FAILURE_CACHE")
+fi
+
+if [ -n "$IMMICH_ANALYZE_DESCRIPTION_COLUMN" ]; then
+    args+=("--description-column" "$IMMICH_ANALYZE_DESCRIPTION_COLUMN")
+fi
+
+if [ -n "$IMMICH_ANALYZE_REFUSAL_PHRASES" ]; then
+    args+=("--refusal-phrases" "$IMMICH_ANALYZE_REFUSAL_PHRASES")
+fi
+
+if [ -n "$IMMICH_ANALYZE_SCAN_ON_START" ]; then
+    args+=("--scan-on-start" "$IMMICH_ANALYZE_SCAN_ON_START")
+fi
+
+if [ -n "$IMMICH_ANALYZE_SKIP_ARCHIVED" ]; then
+    args+=("--skip-archived" "$IMMICH_ANALYZE_SKIP_ARCHIVED")
+fi
+
+if [ -n "$IMMICH_ANALYZE_SKIP_TRASHED" ]; then
+    args+=("--skip-trashed" "$IMMICH_ANALYZE_SKIP_TRASHED")
+fi
+
+if [ -n "$IMMICH_ANALYZE_LOCALE_DIR" ]; then
+    args+=("--locale-dir" "$IMMICH_ANALYZE_LOCALE_DIR")
+fi
+
+if [ -n "$IMMICH_ANALYZE_OUTPUT_FORMAT" ]; then
+    args+=("--output-format" "$IMMICH_ANALYZE_OUTPUT_FORMAT")
+fi
+
+if [ -n "$IMMICH_ANALYZE_FAIL_EXIT_THRESHOLD" ]; then
+    args+=("--fail-exit-threshold" "$IMMICH_ANALYZE_FAIL_EXIT_THRESHOLD")
+fi
+
+if [ -n "$IMMICH_ANALYZE_PREVIEW_EXTENSIONS" ]; then
+    args+=("--preview-extensions" "$IMMICH_ANALYZE_PREVIEW_EXTENSIONS")
+fi
+
+if [ -n "$IMMICH_ANALYZE_KEEP_ALIVE" ]; then
+    args+=("--keep-alive" "$IMMICH_ANALYZE_KEEP_ALIVE")
+fi
+
+if [ -n "$IMMICH_ANALYZE_MAX_RUNTIME" ]; then
+    args+=("--max-runtime" "$IMMICH_ANALYZE_MAX_RUNTIME")
+fi
+
+if [ -n "$IMMICH_ANALYZE_RAMP_DURATION" ]; then
+    args+=("--ramp-duration" "$IMMICH_ANALYZE_RAMP_DURATION")
+fi
+
+if [ -n "$IMMICH_ANALYZE_SORT_BY" ]; then
+    args+=("--sort-by" "$IMMICH_ANALYZE_SORT_BY")
+fi
+
+if [ -n "$IMMICH_ANALYZE_COVERAGE_TARGET" ]; then
+    args+=("--coverage-target" "$IMMICH_ANALYZE_COVERAGE_TARGET")
+fi
+
+if [ -n "$IMMICH_ANALYZE_DESCRIPTION_PREFIX" ]; then
+    args+=("--description-prefix" "$IMMICH_ANALYZE_DESCRIPTION_PREFIX")
+fi
+
+if [ -n "$IMMICH_ANALYZE_DESCRIPTION_SUFFIX" ]; then
+    args+=("--description-suffix" "$IMMICH_ANALYZE_DESCRIPTION_SUFFIX")
+fi
+
+if [ -n "$IMMICH_ANALYZE_OVERWRITE_OLDER_THAN" ]; then
+    args+=("--overwrite-older-than" "$IMMICH_ANALYZE_OVERWRITE_OLDER_THAN")
+fi
+
+if [ -n "$IMMICH_ANALYZE_THUMBS_SUBDIR" ]; then
+    args+=("--thumbs-subdir" "$IMMICH_ANALYZE_THUMBS_SUBDIR")
+fi
+
+if [ -n "$IMMICH_ANALYZE_IMAGE_DETAIL" ]; then
+    args+=("--image-detail" "$IMMICH_ANALYZE_IMAGE_DETAIL")
+fi
+
+if [ -n "$IMMICH_ANALYZE_RESULTS_DB" ]; then
+    args+=("--results-db" "$IMMICH_ANALYZE_RESULTS_DB")
+fi
+
+if [ -n "$IMMICH_ANALYZE_WRITE_TARGET" ]; then
+    args+=("--write-target" "$IMMICH_ANALYZE_WRITE_TARGET")
+fi
+
+if [ -n "$IMMICH_ANALYZE_POST_HOOK" ]; then
+    args+=("--post-hook" "$IMMICH_ANALYZE_POST_HOOK")
+fi
+
+if [ -n "$IMMICH_ANALYZE_ENDPOINT_PATH" ]; then
+    args+=("--endpoint-path" "$IMMICH_ANALYZE_ENDPOINT_PATH")
+fi
+
+if [ -n "$IMMICH_ANALYZE_RESUME_FROM_UUID" ]; then
+    args+=("--resume-from-uuid" "$IMMICH_ANALYZE_RESUME_FROM_UUID")
+fi
+
+if [ -n "$IMMICH_ANALYZE_PAUSE_FILE" ]; then
+    args+=("--pause-file" "$IMMICH_ANALYZE_PAUSE_FILE")
+fi
+
+if [ -n "$IMMICH_ANALYZE_THROTTLE_COOLDOWN" ]; then
+    args+=("--throttle-cooldown" "$IMMICH_ANALYZE_THROTTLE_COOLDOWN")
+fi
+
+if [ -n "$IMMICH_ANALYZE_STABILITY_MODE" ]; then
+    args+=("--stability-mode" "$IMMICH_ANALYZE_STABILITY_MODE")
+fi
+
 # Numeric validations
 if [[ "$IMMICH_ANALYZE_MAX_CONCURRENT" =~ ^[0-9]+$ ]]; then
     args+=("--max-concurrent" "$IMMICH_ANALYZE_MAX_CONCURRENT")
@@ -140,7 +386,119 @@ if [[ "$IMMICH_ANALYZE_WAIT_RETRY_INTERVAL" =~ ^[0-9]+$ ]]; then
     args+=("--wait-retry-interval" "$IMMICH_ANALYZE_WAIT_RETRY_INTERVAL")
 fi
 
-echo "Running immich-analyze with args: ${args[@]}"
+if [[ "$IMMICH_ANALYZE_CONCURRENCY_PER_HOST" =~ ^[0-9]+$ ]]; then
+    args+=("--concurrency-per-host" "$IMMICH_ANALYZE_CONCURRENCY_PER_HOST")
+fi
+
+if [[ "$IMMICH_ANALYZE_WEBHOOK_HOST_FAILURES" =~ ^[0-9]+$ ]]; then
+    args+=("--webhook-host-failures" "$IMMICH_ANALYZE_WEBHOOK_HOST_FAILURES")
+fi
+
+if [[ "$IMMICH_ANALYZE_WEBHOOK_HOST_FAILURE_WINDOW" =~ ^[0-9]+$ ]]; then
+    args+=("--webhook-host-failure-window" "$IMMICH_ANALYZE_WEBHOOK_HOST_FAILURE_WINDOW")
+fi
+
+if [[ "$IMMICH_ANALYZE_MAX_FILE_SIZE_MB" =~ ^[0-9]+$ ]]; then
+    args+=("--max-file-size-mb" "$IMMICH_ANALYZE_MAX_FILE_SIZE_MB")
+fi
+
+if [[ "$IMMICH_ANALYZE_MAX_RPM" =~ ^[0-9]+$ ]]; then
+    args+=("--max-rpm" "$IMMICH_ANALYZE_MAX_RPM")
+fi
+
+if [[ "$IMMICH_ANALYZE_CONNECT_TIMEOUT" =~ ^[0-9]+$ ]]; then
+    args+=("--connect-timeout" "$IMMICH_ANALYZE_CONNECT_TIMEOUT")
+fi
+
+if [[ "$IMMICH_ANALYZE_SHUFFLE_SEED" =~ ^[0-9]+$ ]]; then
+    args+=("--shuffle-seed" "$IMMICH_ANALYZE_SHUFFLE_SEED")
+fi
+
+if [[ "$IMMICH_ANALYZE_MAX_ATTEMPTS" =~ ^[0-9]+$ ]]; then
+    args+=("--max-attempts" "$IMMICH_ANALYZE_MAX_ATTEMPTS")
+fi
+
+if [[ "$IMMICH_ANALYZE_FAILURE_COOLDOWN_HOURS" =~ ^[0-9]+$ ]]; then
+    args+=("--failure-cooldown-hours" "$IMMICH_ANALYZE_FAILURE_COOLDOWN_HOURS")
+fi
+
+if [[ "$IMMICH_ANALYZE_MIN_DESCRIPTION_LENGTH" =~ ^[0-9]+$ ]]; then
+    args+=("--min-description-length" "$IMMICH_ANALYZE_MIN_DESCRIPTION_LENGTH")
+fi
+
+if [[ "$IMMICH_ANALYZE_TIMEOUT_PER_IMAGE_MB" =~ ^[0-9]+$ ]]; then
+    args+=("--timeout-per-image-mb" "$IMMICH_ANALYZE_TIMEOUT_PER_IMAGE_MB")
+fi
+
+if [[ "$IMMICH_ANALYZE_MAX_TIMEOUT" =~ ^[0-9]+$ ]]; then
+    args+=("--max-timeout" "$IMMICH_ANALYZE_MAX_TIMEOUT")
+fi
+
+if [[ "$IMMICH_ANALYZE_MAX_PEOPLE" =~ ^[0-9]+$ ]]; then
+    args+=("--max-people" "$IMMICH_ANALYZE_MAX_PEOPLE")
+fi
+
+if [[ "$IMMICH_ANALYZE_PROMPT_MAX_IMAGES" =~ ^[0-9]+$ ]]; then
+    args+=("--prompt-max-images" "$IMMICH_ANALYZE_PROMPT_MAX_IMAGES")
+fi
+
+if [[ "$IMMICH_ANALYZE_DB_CONNECT_TIMEOUT" =~ ^[0-9]+$ ]]; then
+    args+=("--db-connect-timeout" "$IMMICH_ANALYZE_DB_CONNECT_TIMEOUT")
+fi
+
+if [[ "$IMMICH_ANALYZE_HOST_STATUS_INTERVAL" =~ ^[0-9]+$ ]]; then
+    args+=("--host-status-interval" "$IMMICH_ANALYZE_HOST_STATUS_INTERVAL")
+fi
+
+if [[ "$IMMICH_ANALYZE_MOCK_DELAY_MS" =~ ^[0-9]+$ ]]; then
+    args+=("--mock-delay-ms" "$IMMICH_ANALYZE_MOCK_DELAY_MS")
+fi
+
+if [[ "$IMMICH_ANALYZE_MIN_CONCURRENT" =~ ^[0-9]+$ ]]; then
+    args+=("--min-concurrent" "$IMMICH_ANALYZE_MIN_CONCURRENT")
+fi
+
+if [[ "$IMMICH_ANALYZE_POST_HOOK_CONCURRENCY" =~ ^[0-9]+$ ]]; then
+    args+=("--post-hook-concurrency" "$IMMICH_ANALYZE_POST_HOOK_CONCURRENCY")
+fi
+
+if [[ "$IMMICH_ANALYZE_POST_HOOK_TIMEOUT" =~ ^[0-9]+$ ]]; then
+    args+=("--post-hook-timeout" "$IMMICH_ANALYZE_POST_HOOK_TIMEOUT")
+fi
+
+if [[ "$IMMICH_ANALYZE_MAX_IMAGE_DIMENSION" =~ ^[0-9]+$ ]]; then
+    args+=("--max-image-dimension" "$IMMICH_ANALYZE_MAX_IMAGE_DIMENSION")
+fi
+
+if [[ "$IMMICH_ANALYZE_SHUTDOWN_GRACE" =~ ^[0-9]+$ ]]; then
+    args+=("--shutdown-grace" "$IMMICH_ANALYZE_SHUTDOWN_GRACE")
+fi
+
+if [[ "$IMMICH_ANALYZE_REJECT_DUPLICATE_THRESHOLD" =~ ^[0-9]+$ ]]; then
+    args+=("--reject-duplicate-threshold" "$IMMICH_ANALYZE_REJECT_DUPLICATE_THRESHOLD")
+fi
+
+if [[ "$IMMICH_ANALYZE_RESPONSE_START_TIMEOUT" =~ ^[0-9]+$ ]]; then
+    args+=("--response-start-timeout" "$IMMICH_ANALYZE_RESPONSE_START_TIMEOUT")
+fi
+
+if [[ "$IMMICH_ANALYZE_MIN_IMAGE_DIMENSION" =~ ^[0-9]+$ ]]; then
+    args+=("--min-image-dimension" "$IMMICH_ANALYZE_MIN_IMAGE_DIMENSION")
+fi
+
+if [[ "$IMMICH_ANALYZE_ERROR_RATE_THRESHOLD" =~ ^[0-9]+$ ]]; then
+    args+=("--error-rate-threshold" "$IMMICH_ANALYZE_ERROR_RATE_THRESHOLD")
+fi
+
+if [[ "$IMMICH_ANALYZE_NUM_CTX" =~ ^[0-9]+$ ]]; then
+    args+=("--num-ctx" "$IMMICH_ANALYZE_NUM_CTX")
+fi
+
+if [[ "$IMMICH_ANALYZE_NUM_GPU" =~ ^[0-9]+$ ]]; then
+    args+=("--num-gpu" "$IMMICH_ANALYZE_NUM_GPU")
+fi
+
+echo "Running immich-analyze with args: ${args[*]}" | sed -E 's#(postgresql://[^:/@]+:)[^@]+@#\1****@#'
 
 # Execute with proper signal handling
 exec immich-analyze "${args[@]}"
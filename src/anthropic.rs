@@ -0,0 +1,62 @@
+use crate::utils::EncodedImage;
+use reqwest::RequestBuilder;
+use serde_json::Value;
+
+/// Anthropic API version sent with every request.
+const API_VERSION: &str = "2023-06-01";
+/// Response token limit, which Anthropic requires on every request.
+const MAX_TOKENS: u32 = 1024;
+
+/// Adds the `anthropic-version` header and, when given, the `x-api-key` header.
+pub fn authorize(request: RequestBuilder, api_key: Option<&str>) -> RequestBuilder {
+    let versioned = request.header("anthropic-version", API_VERSION);
+    match api_key {
+        Some(key) => versioned.header("x-api-key", key),
+        None => versioned,
+    }
+}
+
+/// Builds a `/v1/messages` body: the images as base64 `source` blocks, then the prompt.
+pub fn build_request_body(model_name: &str, prompt: &str, images: &[EncodedImage]) -> Value {
+    let mut content: Vec<Value> = images
+        .iter()
+        .map(|image| {
+            serde_json::json!({
+                "type": "image",
+                "source": {
+                    "type": "base64",
+                    "media_type": image.mime_type,
+                    "data": image.base64
+                }
+            })
+        })
+        .collect();
+    content.push(serde_json::json!({
+        "type": "text",
+        "text": prompt
+    }));
+    serde_json::json!({
+        "model": model_name,
+        "max_tokens": MAX_TOKENS,
+        "messages": [
+            {
+                "role": "user",
+                "content": content
+            }
+        ],
+    })
+}
+
+/// Text of the first `text` content block; extended thinking puts `thinking` blocks before it.
+pub fn parse_response(json_value: &Value) -> Option<&str> {
+    json_value
+        .get("content")
+        .and_then(|content| content.as_array())
+        .and_then(|blocks| {
+            blocks
+                .iter()
+                .find(|block| block.get("type").and_then(|kind| kind.as_str()) == Some("text"))
+        })
+        .and_then(|block| block.get("text"))
+        .and_then(|text| text.as_str())
+}
@@ -1,11 +1,23 @@
-use crate::data_access::DataAccessMode;
+use crate::data_access::{AssetFilter, DataAccessMode};
+use crate::results_db::WriteTarget;
+use base64::{Engine as _, engine::general_purpose::STANDARD};
 use clap::{Parser, ValueEnum};
+use regex::Regex;
+use reqwest::header::{AUTHORIZATION, HeaderMap, HeaderName, HeaderValue};
+use serde_json::Value;
+use std::{path::PathBuf, time::Duration};
+use uuid::Uuid;
 
 #[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum Interface {
     #[default]
     Ollama,
     Llamacpp,
+    /// Anthropic Messages API (Claude vision models)
+    Anthropic,
+    /// No model: return a canned description after --mock-delay-ms, for benchmarking the
+    /// pipeline (file reads, database writes, progress) on its own
+    Mock,
 }
 
 #[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -19,6 +31,83 @@ pub enum OverwritePolicy {
     MissingAi,
 }
 
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DescriptionColumn {
+    /// `asset_exif.description`: shown in the Immich UI and matched by metadata search
+    #[default]
+    Exif,
+    /// `asset_metadata` row with key `immich-analyze`: stored with the asset but not shown in the UI
+    AssetMetadata,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// Localized human-readable messages and progress lines
+    #[default]
+    Text,
+    /// One JSON object per lifecycle event on stdout; human-readable messages go to stderr
+    Json,
+}
+
+/// `detail` of OpenAI-style `image_url` parts: how closely the model looks at each image.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImageDetail {
+    /// Low-resolution pass: far fewer tokens and faster, but small details are missed
+    Low,
+    /// Full resolution: best descriptions, most tokens
+    High,
+    /// Let the server choose
+    #[default]
+    Auto,
+}
+
+impl ImageDetail {
+    /// Value sent in the request.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::High => "high",
+            Self::Auto => "auto",
+        }
+    }
+}
+
+/// Which assets a batch run treats as done (`--coverage-target`).
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CoverageTarget {
+    /// Assets with a description
+    #[default]
+    Description,
+    /// Assets with a smart search (CLIP) embedding, i.e. already findable by search
+    SmartSearch,
+}
+
+/// How monitor mode decides that a new preview is fully written (`--stability-mode`).
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StabilityMode {
+    /// Wait until size and modification time stop changing
+    #[default]
+    Size,
+    /// Previews are written atomically by renaming: a file appearing under its final name is
+    /// complete; data writes still wait for the size to settle
+    Rename,
+    /// Process every event at once
+    None,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortBy {
+    /// Smallest preview files first
+    Size,
+    /// Preview file name
+    Name,
+    /// Most recently modified preview files first
+    MtimeDesc,
+    /// Least recently modified preview files first
+    MtimeAsc,
+}
+
 #[derive(Parser, Debug, Clone)]
 #[command(author, version, about, long_about = None)]
 #[expect(clippy::struct_excessive_bools)]
@@ -29,6 +118,31 @@ pub struct Args {
     /// Enable combined mode: process existing images then monitor for new ones
     #[arg(short, long)]
     pub combined: bool,
+    /// With --monitor: process all existing previews once, with the same file stability
+    /// checks, then exit instead of watching (for cron-style scheduling)
+    #[arg(long, requires = "monitor", conflicts_with = "combined")]
+    pub once: bool,
+    /// Batch mode: stop at the first hard error (AI host HTTP 5xx, all hosts unavailable,
+    /// invalid API key or database error) and print the partial statistics
+    #[arg(long, default_value_t = false, conflicts_with_all = ["monitor", "combined"])]
+    pub fail_fast: bool,
+    /// Re-analyze only this asset (repeatable) and print the full result, e.g. to debug a
+    /// prompt on one photo. Implies --overwrite-existing and bypasses the failure cache
+    #[arg(long, value_name = "UUID", conflicts_with_all = ["monitor", "combined"])]
+    pub only_asset: Vec<Uuid>,
+    /// Batch mode: exit with status 1 when more than this percentage of the processed files
+    /// failed (skipped files are not counted), e.g. `0` or `5%`
+    #[arg(long, value_parser = parse_percent)]
+    pub fail_exit_threshold: Option<u8>,
+    /// Batch mode: stop starting new files after this long, e.g. `4h`, `90m` or `1h30m`
+    /// (a bare number is seconds). Files already being analyzed are finished and the
+    /// results reported; the next run picks up the remaining ones
+    #[arg(long, value_parser = parse_duration, conflicts_with = "monitor")]
+    pub max_runtime: Option<Duration>,
+    /// In monitor mode, also process previews that already exist when monitoring starts
+    /// [default: true, false in combined mode where batch processing covers them]
+    #[arg(long, num_args = 0..=1, default_missing_value = "true")]
+    pub scan_on_start: Option<bool>,
     /// Overwrite existing entries in database (process all files regardless of existing descriptions) (same as --overwrite-policy all)
     #[arg(short, long)]
     pub overwrite_existing: bool,
@@ -39,18 +153,66 @@ pub struct Args {
     /// Takes precedence over --overwrite-existing.
     #[arg(short = 'O', long, value_enum)]
     pub overwrite_policy: Option<OverwritePolicy>,
+    /// When overwriting, only replace descriptions written longer ago than this (e.g. `30d`,
+    /// `12h`), so repeated runs upgrade stale descriptions only (database access mode)
+    #[arg(long, value_parser = parse_duration)]
+    pub overwrite_older_than: Option<Duration>,
     /// When overwriting or adding, preserve human-entered text by only replacing the [AI]...[/AI] block
     #[arg(short, long, conflicts_with = "disable_ai_wrapper")]
     pub preserve_human: bool,
+    /// Print how each new description differs from the stored one instead of writing it;
+    /// combine with --overwrite-existing to preview a re-run with another model or prompt.
+    /// Previewed files are counted as skipped
+    #[arg(long, conflicts_with = "format_only")]
+    pub diff: bool,
+    /// With --diff, also store the new descriptions
+    #[arg(long, requires = "diff")]
+    pub apply: bool,
     /// Path to Immich root directory (containing upload/, thumbs/ folders)
     #[arg(long, default_value = "/var/lib/immich")]
     pub immich_root: String,
+    /// Immich's `.env` file: fill in --postgres-url from its `DB_*` variables and --immich-root
+    /// from `UPLOAD_LOCATION`; both flags still take precedence when given
+    #[arg(long)]
+    pub immich_env: Option<String>,
+    /// Directory with the preview images, relative to --immich-root (or absolute) for
+    /// non-standard layouts; `.` when --immich-root already points at it
+    #[arg(long, default_value = "thumbs")]
+    pub thumbs_subdir: String,
+    /// Database mode: for image assets without a preview in thumbs/, analyze a downscaled copy
+    /// of the original from `asset.originalPath` instead (JPEG, PNG and WebP originals only)
+    #[arg(long, default_value_t = false)]
+    pub use_original_fallback: bool,
+    /// Leave archived assets out of processing (`--skip-archived false` to include them)
+    #[arg(long, default_value_t = true, action = clap::ArgAction::Set, num_args = 0..=1, default_missing_value = "true")]
+    pub skip_archived: bool,
+    /// Leave assets in the trash out of processing (`--skip-trashed false` to include them)
+    #[arg(long, default_value_t = true, action = clap::ArgAction::Set, num_args = 0..=1, default_missing_value = "true")]
+    pub skip_trashed: bool,
     /// `PostgreSQL` connection string (used only in database mode)
     #[arg(
         long,
         default_value = "host=localhost user=postgres dbname=immich password=your_password"
     )]
     pub postgres_url: String,
+    /// Read the `PostgreSQL` password from this file (e.g. a Docker secret) instead of --postgres-url
+    #[arg(long, env = "DB_PASSWORD_FILE")]
+    pub db_password_file: Option<String>,
+    /// Seconds to wait for the `PostgreSQL` connection before giving up (used only in database mode)
+    #[arg(long, visible_alias = "postgres-connect-timeout", default_value_t = 10, value_parser = clap::value_parser!(u64).range(1..))]
+    pub db_connect_timeout: u64,
+    /// Database mode: record each batch run (start, end, counts, model, hosts) in an
+    /// `immich_analyze_runs` table, created if missing; not with --write-target results-db
+    #[arg(long, default_value_t = false)]
+    pub write_run_log: bool,
+    /// Local results database as `sqlite:<path>`, receiving descriptions (asset id, description,
+    /// model, timestamp) according to --write-target; created if missing
+    #[arg(long, value_parser = parse_results_db, conflicts_with = "format_only")]
+    pub results_db: Option<PathBuf>,
+    /// Where descriptions are written: immich, results-db (Immich is only read, for setups
+    /// without write access) or both; results-db and both require --results-db
+    #[arg(long, value_enum, default_value_t = WriteTarget::Immich)]
+    pub write_target: WriteTarget,
     /// Data access mode: database (direct `PostgreSQL`) or api (Immich REST API)
     #[arg(short, long, value_enum, default_value = "database")]
     pub data_access_mode: DataAccessMode,
@@ -75,31 +237,173 @@ pub struct Args {
     /// AI service interface type
     #[arg(long, value_enum, default_value = "ollama")]
     pub interface: Interface,
-    /// Host URLs (Ollama or llama.cpp server)
+    /// Host URLs (Ollama, llama.cpp server or Anthropic API), separated by commas, spaces or
+    /// newlines; duplicates are dropped. `${VAR}` is replaced with the environment variable
     #[arg(long, default_value = "http://localhost:11434", value_delimiter = ',')]
     pub hosts: Vec<String>,
-    /// API key for authentication (llama.cpp server)
+    /// File with one host URL per line (blank lines and `#` comments are ignored), read at
+    /// startup and added to --hosts; replaces the default host when --hosts is not given
+    #[arg(long)]
+    pub hosts_file: Option<String>,
+    /// API key for authentication (llama.cpp server, Anthropic)
     #[arg(long, env = "IMMICH_ANALYZE_API_KEY", hide_env_values = true)]
     pub api_key: Option<String>,
-    /// Maximum number of concurrent requests
+    /// Request path appended to each host instead of the interface default (`/api/chat`,
+    /// `/v1/chat/completions`, `/v1/messages`), e.g. `/proxy/v1/chat/completions` behind a
+    /// reverse proxy; a prefix in front of the default path is also used for the model check
+    #[arg(long, value_parser = parse_endpoint_path)]
+    pub endpoint_path: Option<String>,
+    /// Image `detail` for llama.cpp and other OpenAI-compatible servers: `low` is much faster
+    /// and cheaper on big batches but misses small details; ignored by Ollama and Anthropic
+    #[arg(long, value_enum, default_value_t = ImageDetail::Auto)]
+    pub image_detail: ImageDetail,
+    /// Extra header sent with every request to AI hosts, as `Name: Value` (repeatable),
+    /// e.g. for the auth scheme of a reverse proxy
+    #[arg(long, value_parser = parse_host_header)]
+    pub host_header: Vec<(HeaderName, HeaderValue)>,
+    /// HTTP basic auth credentials for AI hosts behind a reverse proxy, as `user:pass`.
+    /// A llama.cpp `--api-key` takes precedence, since both use the Authorization header
+    #[arg(long, env = "IMMICH_ANALYZE_BASIC_AUTH", hide_env_values = true, value_parser = parse_basic_auth)]
+    pub basic_auth: Option<String>,
+    /// Proxy URL for requests to AI hosts (http://, https:// or socks5://)
+    #[arg(long, env = "IMMICH_ANALYZE_PROXY", hide_env_values = true)]
+    pub proxy: Option<String>,
+    /// Hosts or domains that bypass --proxy (comma-separated, e.g. localhost,.internal)
+    #[arg(long, value_delimiter = ',', requires = "proxy")]
+    pub no_proxy_for: Vec<String>,
+    /// User-Agent header sent to AI hosts, for gateways that block unknown clients
+    #[arg(long, default_value = DEFAULT_USER_AGENT)]
+    pub user_agent: String,
+    /// Maximum number of concurrent requests, shared by batch and monitor in combined mode
+    /// (also caps files processed at once in monitor mode)
     #[arg(long, default_value_t = 4)]
     pub max_concurrent: usize,
+    /// Batch mode: start with 1 file at a time and add slots evenly until --max-concurrent
+    /// is reached after this long (e.g. `2m`), so a model that is still loading is not
+    /// flooded with requests
+    #[arg(long, visible_alias = "concurrency-ramp", value_parser = parse_duration)]
+    pub ramp_duration: Option<Duration>,
+    /// Batch mode: tune concurrency from observed latency, starting at --min-concurrent and
+    /// adding a slot while requests stay fast, halving it when latency or errors rise;
+    /// --max-concurrent is the upper bound
+    #[arg(long, default_value_t = false, conflicts_with = "ramp_duration")]
+    pub concurrency_auto: bool,
+    /// Lower bound and starting point for --concurrency-auto
+    #[arg(long, default_value_t = 1, requires = "concurrency_auto", value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    pub min_concurrent: usize,
+    /// Batch mode: when more than this percentage of the last 20 analyzed files failed, halve
+    /// the files in flight until the error rate stays below it for --throttle-cooldown
+    #[arg(long, conflicts_with = "concurrency_auto", value_parser = clap::value_parser!(u8).range(1..=100))]
+    pub error_rate_threshold: Option<u8>,
+    /// How long --error-rate-threshold keeps concurrency reduced before checking whether
+    /// the backends recovered (e.g. `30s`, `5m`)
+    #[arg(long, default_value = "1m", requires = "error_rate_threshold", value_parser = parse_duration)]
+    pub throttle_cooldown: Duration,
+    /// Maximum number of concurrent requests sent to a single host (0 = no per-host limit)
+    #[arg(long, default_value_t = 0)]
+    pub concurrency_per_host: usize,
+    /// Maximum AI requests per minute across all hosts and tasks (0 = unlimited)
+    #[arg(long, default_value_t = 0)]
+    pub max_rpm: u32,
+    /// Log every host and whether it is currently unavailable at this interval in seconds,
+    /// for diagnosing flaky hosts in long runs (0 = disabled)
+    #[arg(long, default_value_t = 0)]
+    pub host_status_interval: u64,
     /// Host availability check interval in seconds
     #[arg(long, default_value_t = 60)]
     pub unavailable_duration: u64,
+    /// Once a host's unavailable period expires, send it a single probe request and only
+    /// re-enable it for all tasks after that request succeeds
+    #[arg(long, default_value_t = false)]
+    pub host_recovery_probe: bool,
+    /// Skip preview files larger than this size in megabytes (0 = no limit)
+    #[arg(long, default_value_t = 0)]
+    pub max_file_size_mb: u64,
+    /// Preview file extensions to process; other files in thumbs/ are skipped
+    #[arg(long, value_delimiter = ',', default_value = "jpeg,jpg,webp,png")]
+    pub preview_extensions: Vec<String>,
+    /// Decode each preview before sending it and skip images that are truncated or corrupt
+    #[arg(long, default_value_t = false)]
+    pub skip_corrupt_images: bool,
     /// HTTP request timeout in seconds
     #[arg(long, default_value_t = 300)]
     pub timeout: u64,
+    /// Extra request timeout in seconds per megabyte of image, added to --timeout (0 = flat timeout)
+    #[arg(long, default_value_t = 0)]
+    pub timeout_per_image_mb: u64,
+    /// Upper limit in seconds for the request timeout scaled by --timeout-per-image-mb
+    #[arg(long, default_value_t = 3600)]
+    pub max_timeout: u64,
+    /// Ollama only: how long the model stays loaded after a request, e.g. `30m`, `1h`, or `-1`
+    /// to keep it loaded forever. Keeping the model resident trades VRAM for speed, avoiding a
+    /// reload between images [default: Ollama's own, 5m]
+    #[arg(long, value_parser = parse_keep_alive, allow_hyphen_values = true)]
+    pub keep_alive: Option<String>,
+    /// Ollama only: unload the model after every analyzed image so no server-side state carries
+    /// over to the next one. Much slower, as the model is reloaded for each image
+    #[arg(long, default_value_t = false, conflicts_with = "keep_alive")]
+    pub reset_context: bool,
+    /// Ollama only: context window in tokens (`num_ctx`). Pinning it stops the server from
+    /// resizing the KV cache between images; a needlessly large value slows every request and
+    /// can show up as latency growing over a run
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub num_ctx: Option<u32>,
+    /// Ollama only: number of model layers offloaded to the GPU (`num_gpu`), 0 to run on the CPU
+    #[arg(long)]
+    pub num_gpu: Option<u32>,
+    /// Ollama only: extra entry of the request's `options`, as `key=value` (repeatable). The
+    /// value is read as JSON when it parses (`temperature=0.2`), otherwise as a string;
+    /// takes precedence over --num-ctx and --num-gpu
+    #[arg(long, value_parser = parse_ollama_option)]
+    pub ollama_option: Vec<(String, Value)>,
+    /// Most images sent per request: with more than 1, other images stacked with the asset
+    /// in Immich (bursts, duplicates) are sent after it as context
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u8).range(1..=8))]
+    pub prompt_max_images: u8,
+    /// Shrink images larger than this many pixels on their longest side (re-encoded as JPEG)
+    /// before sending, e.g. when a proxy in front of the host rejects large requests (HTTP 413)
+    #[arg(long, value_parser = clap::value_parser!(u32).range(64..))]
+    pub max_image_dimension: Option<u32>,
+    /// Skip images whose width and height are both below this many pixels, such as
+    /// placeholder icons, instead of sending them to the model
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub min_image_dimension: Option<u32>,
+    /// Timeout in seconds for establishing a connection to a host, so unreachable hosts fail fast
+    #[arg(long, default_value_t = 10)]
+    pub connect_timeout: u64,
+    /// Seconds to wait for a host to start answering (response headers) before trying the
+    /// next one, separately from --timeout for the whole request. Servers that do not stream
+    /// only answer once inference is done, so keep it above the usual inference time
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    pub response_start_timeout: Option<u64>,
     /// File write timeout in seconds
     #[arg(long, default_value_t = 30)]
     pub file_write_timeout: u64,
     /// File stability check interval in milliseconds
     #[arg(long, default_value_t = 500)]
     pub file_check_interval: u64,
+    /// Number of consecutive checks with unchanged size and modification time before a file is considered fully written
+    #[arg(long, default_value_t = 3, value_parser = clap::value_parser!(u8).range(1..))]
+    pub file_stable_checks: u8,
+    /// Monitor mode: how a new preview is known to be fully written; `rename` skips the
+    /// stability polling for files created or moved into place, for setups that write
+    /// previews atomically
+    #[arg(long, value_enum, default_value_t = StabilityMode::Size)]
+    pub stability_mode: StabilityMode,
+    /// Monitor mode: only treat a JPEG preview as fully written once it ends with the
+    /// end-of-image marker, so a writer stalled mid-file is not mistaken for a finished one
+    #[arg(long, default_value_t = false)]
+    pub verify_jpeg_eof: bool,
     /// Minimum time between processing identical events in seconds
     #[arg(long, default_value_t = 2)]
     pub event_cooldown: u64,
-    /// Prompt for generating image description
+    /// Monitor mode: seconds to let files already being processed finish after SIGTERM or
+    /// SIGINT, so their descriptions are still written; tasks still running are then abandoned
+    #[arg(long, default_value_t = 30)]
+    pub shutdown_grace: u64,
+    /// Prompt for generating image description.
+    /// Supports per-asset placeholders: `{asset_date}`, `{album_names}`, `{original_filename}`,
+    /// and `${VAR}` for environment variables (`$$` for a literal `$`)
     #[arg(
         long,
         default_value = concat!(
@@ -110,12 +414,79 @@ pub struct Args {
         )
     )]
     pub prompt: String,
-    /// Interface language (ru, en)
+    /// Ask the model only for the image format (Screenshot, Selfie, etc.) and store it
+    /// as a tag instead of writing a description. Replaces --prompt
+    #[arg(long, default_value_t = false)]
+    pub format_only: bool,
+    /// Ask the model to end descriptions with a `Format: X` line, which is stored as a tag
+    /// under --tag-prefix instead of in the description
+    #[arg(long, default_value_t = false, conflicts_with = "format_only")]
+    pub extract_format_tag: bool,
+    /// Parent tag for format tags written with --format-only or --extract-format-tag
+    /// (e.g. Format/Screenshot)
+    #[arg(long, default_value = "Format")]
+    pub tag_prefix: String,
+    /// Where descriptions are written. Only exif is visible in the Immich UI and description
+    /// search; smart search uses CLIP image embeddings and never reads either column
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = DescriptionColumn::Exif,
+        conflicts_with = "format_only"
+    )]
+    pub description_column: DescriptionColumn,
+    /// Prepend the asset's original filename (e.g. `beach_trip_2019.jpg`) to the prompt as a hint;
+    /// camera-generated names like DSC01234 are not sent
+    #[arg(long, default_value_t = false)]
+    pub use_filename_hint: bool,
+    /// Tell the model who is in the photo ("This photo contains: Alice, Bob."), using the named
+    /// people recognized by Immich; unnamed and hidden people are never sent
+    #[arg(long, default_value_t = false)]
+    pub include_people: bool,
+    /// Maximum number of people named in the prompt with --include-people
+    #[arg(long, default_value_t = 5)]
+    pub max_people: usize,
+    /// Reject descriptions shorter than this many characters instead of storing them
+    /// (0 = no limit, ignored with --format-only)
+    #[arg(long, default_value_t = 0)]
+    pub min_description_length: usize,
+    /// Batch mode: skip instead of storing a description once the model has returned the same
+    /// text more than this many times in a row, which usually means it is stuck
+    #[arg(long, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    pub reject_duplicate_threshold: Option<usize>,
+    /// Reject descriptions containing any of these phrases, case-insensitive
+    /// (comma-separated, e.g. "I cannot see,I'm unable to")
+    #[arg(long, value_delimiter = ',')]
+    pub refusal_phrases: Vec<String>,
+    /// Language the model should write descriptions in (e.g. English); independent of --lang.
+    /// When unset, the prompt is sent unchanged
+    #[arg(long)]
+    pub description_language: Option<String>,
+    /// Increase log verbosity (-v info, -vv debug, -vvv trace); `RUST_LOG` takes precedence
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+    /// Interface language (en, ru, de, fr, es or a locale loaded from --locale-dir)
     #[arg(long, default_value = "")]
     pub lang: String,
+    /// Directory with extra translation files (e.g. de.yml) loaded at startup; they add
+    /// new locales or override built-in messages without recompiling
+    #[arg(long)]
+    pub locale_dir: Option<String>,
     /// Maximum number of retry attempts (0 = infinite)
     #[arg(long, default_value_t = 0)]
     pub max_retries: u32,
+    /// JSON file recording assets that failed in previous runs, so they are not retried forever
+    #[arg(long)]
+    pub failure_cache: Option<String>,
+    /// Skip assets from --failure-cache that already failed this many times (0 = never skip)
+    #[arg(long, default_value_t = 3, requires = "failure_cache")]
+    pub max_attempts: u32,
+    /// Hours after the last failed attempt before a skipped asset is tried again
+    #[arg(long, default_value_t = 24, requires = "failure_cache")]
+    pub failure_cooldown_hours: u64,
+    /// Clear --failure-cache on startup so previously failed assets are retried
+    #[arg(long, default_value_t = false, requires = "failure_cache")]
+    pub retry_failed: bool,
     /// Delay between retry cycles in seconds (fixed)
     #[arg(long, default_value_t = 5)]
     pub retry_delay_seconds: u64,
@@ -125,9 +496,45 @@ pub struct Args {
     /// Disable [AI]...[/AI] wrapper around AI-generated description
     #[arg(long, default_value_t = false, conflicts_with = "preserve_human")]
     pub disable_ai_wrapper: bool,
+    /// Text put before every generated description (e.g. "[AI] "), to find them later
+    #[arg(long)]
+    pub description_prefix: Option<String>,
+    /// Text put after every generated description
+    #[arg(long)]
+    pub description_suffix: Option<String>,
+    /// Process assets in random order (batch mode only; ignored in monitor mode).
+    /// Waits for discovery to finish before processing starts
+    #[arg(long, default_value_t = false)]
+    pub shuffle: bool,
+    /// Seed for --shuffle to get a reproducible order
+    #[arg(long, requires = "shuffle")]
+    pub shuffle_seed: Option<u64>,
+    /// Process assets in this order instead of discovery order (batch mode, database access
+    /// mode). Waits for discovery to finish before processing starts
+    #[arg(long, value_enum, conflicts_with_all = ["shuffle", "only_asset"])]
+    pub sort_by: Option<SortBy>,
+    /// Batch mode: assets left out up front. `description` drops described assets (with the
+    /// default overwrite policy), `smart-search` drops assets that have a `smart_search`
+    /// embedding, so only photos search cannot find yet are analyzed (database access mode)
+    #[arg(long, value_enum, default_value_t = CoverageTarget::Description)]
+    pub coverage_target: CoverageTarget,
+    /// With --sort-by: leave out every asset sorted before this one and start processing
+    /// there, e.g. after checking descriptions by hand up to it. If it is not found, all
+    /// assets are processed
+    #[arg(long, value_name = "UUID", requires = "sort_by")]
+    pub resume_from_uuid: Option<Uuid>,
+    /// Stdout format: localized text, or JSON lines with `started`, `file_processing`, `file_done`, `file_failed` and `summary` events
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub output_format: OutputFormat,
+    /// Plain-text console output: periodic progress summaries instead of per-file progress lines and ASCII status markers instead of icons. Enabled automatically when stdout is not a terminal
+    #[arg(long, visible_alias = "no-progress", default_value_t = false)]
+    pub plain: bool,
     /// Disable final output with analysis results and statistics after batch processing
     #[arg(long, default_value_t = false)]
     pub no_final_output: bool,
+    /// Write generated descriptions to this CSV file with columns asset ID, filename and description after batch processing
+    #[arg(long)]
+    pub output_csv: Option<String>,
     /// Disable waiting for Immich to become available on startup (API mode only)
     #[arg(long, default_value_t = false)]
     pub no_wait_for_immich: bool,
@@ -140,15 +547,257 @@ pub struct Args {
     /// Port for health check HTTP server (0 to disable)
     #[arg(long, default_value_t = 3000)]
     pub health_port: u16,
+    /// Strip <think>...</think> reasoning blocks from model output before storing
+    /// [default: true if the model name contains "thinking"]
+    #[arg(long, num_args = 0..=1, default_missing_value = "true")]
+    pub strip_thinking: Option<bool>,
+    /// Delay in milliseconds before `--interface mock` returns its canned description
+    #[arg(long, default_value_t = 0)]
+    pub mock_delay_ms: u64,
+    /// Print the resolved prompt (with --description-language applied) once at startup
+    #[arg(long, default_value_t = false)]
+    pub print_prompt: bool,
+    /// Log the JSON body of the first AI request, with images shortened to a placeholder,
+    /// at debug level (`-vv`)
+    #[arg(long, default_value_t = false)]
+    pub dump_request: bool,
+    /// Skip the startup check that the model is available on every host
+    #[arg(long, default_value_t = false)]
+    pub skip_model_check: bool,
+    /// At startup, query the model list of every host and exit with an error listing the
+    /// unreachable ones, instead of running on the hosts that are up
+    #[arg(long, default_value_t = false)]
+    pub require_all_hosts: bool,
+    /// Webhook URL to POST a JSON notification to on batch completion and repeated host failures
+    #[arg(long, env = "IMMICH_ANALYZE_WEBHOOK_URL", hide_env_values = true)]
+    pub webhook_url: Option<String>,
+    /// Notify the webhook when a host is marked unavailable more than this many times within the window
+    #[arg(long, default_value_t = 3)]
+    pub webhook_host_failures: usize,
+    /// Window in seconds for counting host failures before notifying the webhook
+    #[arg(long, default_value_t = 600)]
+    pub webhook_host_failure_window: u64,
+    /// Shell command run after each stored description, e.g. to refresh a search index.
+    /// `{asset_id}`, `{filename}` and `{description}` are substituted; the last two (and the
+    /// asset id) are also passed as `IMMICH_ANALYZE_*` environment variables
+    #[arg(long)]
+    pub post_hook: Option<String>,
+    /// Most post hooks running at once; further ones wait
+    #[arg(long, default_value_t = 2, requires = "post_hook")]
+    pub post_hook_concurrency: usize,
+    /// Pause processing while this file exists, e.g. while the GPU is needed elsewhere.
+    /// Sending SIGUSR1 toggles the same paused state without a file
+    #[arg(long)]
+    pub pause_file: Option<PathBuf>,
+    /// Seconds before a post hook is killed
+    #[arg(long, default_value_t = 30, requires = "post_hook")]
+    pub post_hook_timeout: u64,
+}
+
+/// User agent sent to AI hosts unless `--user-agent` is given.
+const DEFAULT_USER_AGENT: &str = concat!("immich-analyze/", env!("CARGO_PKG_VERSION"));
+
+/// Prompt used with `--format-only`, asking for a single category name.
+const FORMAT_ONLY_PROMPT: &str = concat!(
+    "Determine the format of the image (Wallpaper, Screenshot, Drawing, City photo, Selfie, etc.). ",
+    "Respond with only the format name in English, without punctuation or explanations. ",
+    "If in doubt, name the most likely option and don't think too long."
+);
+
+/// Appended to the prompt with `--extract-format-tag`, so the format can be split off reliably.
+const FORMAT_LINE_INSTRUCTION: &str =
+    "End the response with a separate last line of the form \"Format: <format name in English>\".";
+
+/// Parses a percentage from 0 to 100, with or without a trailing `%`.
+fn parse_percent(value: &str) -> Result<u8, String> {
+    value
+        .trim()
+        .trim_end_matches('%')
+        .parse::<u8>()
+        .ok()
+        .filter(|percent| *percent <= 100)
+        .ok_or_else(|| format!("expected a percentage from 0 to 100, got '{value}'"))
+}
+
+/// Parses a non-zero duration made of `<number><unit>` parts with units `d`, `h`, `m` and `s`,
+/// such as `4h` or `1h30m`; a bare number is seconds.
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    let invalid = || format!("expected a duration like '4h', '90m' or '1h30m', got '{value}'");
+    let trimmed = value.trim();
+    if let Ok(seconds) = trimmed.parse::<u64>() {
+        return (seconds > 0)
+            .then(|| Duration::from_secs(seconds))
+            .ok_or_else(invalid);
+    }
+    let mut total: u64 = 0;
+    let mut digits = String::new();
+    for character in trimmed.chars() {
+        if character.is_ascii_digit() {
+            digits.push(character);
+            continue;
+        }
+        let unit_seconds: u64 = match character {
+            'd' => 86_400,
+            'h' => 3_600,
+            'm' => 60,
+            's' => 1,
+            _ => return Err(invalid()),
+        };
+        let amount = digits.parse::<u64>().map_err(|_| invalid())?;
+        total = amount
+            .checked_mul(unit_seconds)
+            .and_then(|seconds| total.checked_add(seconds))
+            .ok_or_else(invalid)?;
+        digits.clear();
+    }
+    if !digits.is_empty() || total == 0 {
+        return Err(invalid());
+    }
+    Ok(Duration::from_secs(total))
+}
+
+/// Parses an Ollama `keep_alive` value: whole seconds (negative to never unload) or a
+/// duration such as `30m` or `1h30m`.
+fn parse_keep_alive(value: &str) -> Result<String, String> {
+    let trimmed = value.trim();
+    let is_duration = Regex::new(r"^([0-9]+(\.[0-9]+)?(ns|us|µs|ms|s|m|h))+$")
+        .is_ok_and(|duration| duration.is_match(trimmed));
+    if is_duration || trimmed.parse::<i64>().is_ok() {
+        Ok(trimmed.to_owned())
+    } else {
+        Err(format!(
+            "expected a duration like '30m' or '1h', or seconds like '-1', got '{value}'"
+        ))
+    }
+}
+
+/// Parses a `key=value` entry for `--ollama-option`.
+fn parse_ollama_option(value: &str) -> Result<(String, Value), String> {
+    let (key, raw_value) = value
+        .split_once('=')
+        .filter(|(key, _)| !key.trim().is_empty())
+        .ok_or_else(|| format!("expected an option like 'num_ctx=8192', got '{value}'"))?;
+    let trimmed = raw_value.trim();
+    let option_value =
+        serde_json::from_str(trimmed).unwrap_or_else(|_| Value::from(trimmed.to_owned()));
+    Ok((key.trim().to_owned(), option_value))
+}
+
+/// Parses a `Name: Value` header for `--host-header`.
+fn parse_host_header(value: &str) -> Result<(HeaderName, HeaderValue), String> {
+    let (name, raw_value) = value
+        .split_once(':')
+        .ok_or_else(|| format!("expected a header like 'Name: Value', got '{value}'"))?;
+    let header_name = HeaderName::from_bytes(name.trim().as_bytes())
+        .map_err(|err| format!("invalid header name '{}': {err}", name.trim()))?;
+    let mut header_value = HeaderValue::from_str(raw_value.trim())
+        .map_err(|err| format!("invalid value for header '{header_name}': {err}"))?;
+    // Headers added here are usually credentials, keep them out of debug output
+    header_value.set_sensitive(true);
+    Ok((header_name, header_value))
+}
+
+/// Parses `--endpoint-path`, adding the leading slash if it is missing.
+fn parse_endpoint_path(value: &str) -> Result<String, String> {
+    let trimmed = value.trim();
+    if trimmed.is_empty() || trimmed.contains("://") {
+        return Err(format!(
+            "expected a path like '/v1/chat/completions', got '{value}'"
+        ));
+    }
+    if trimmed.starts_with('/') {
+        Ok(trimmed.to_owned())
+    } else {
+        Ok(format!("/{trimmed}"))
+    }
+}
+
+/// Parses `--results-db`: `sqlite:` followed by the database file path.
+fn parse_results_db(value: &str) -> Result<PathBuf, String> {
+    value
+        .strip_prefix("sqlite://")
+        .or_else(|| value.strip_prefix("sqlite:"))
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+        .ok_or_else(|| format!("expected 'sqlite:<path>', got '{value}'"))
+}
+
+/// Checks that `--basic-auth` has the `user:pass` form.
+fn parse_basic_auth(value: &str) -> Result<String, String> {
+    if value.contains(':') {
+        Ok(value.to_owned())
+    } else {
+        Err("expected credentials like 'user:pass'".to_owned())
+    }
 }
 
 impl Args {
+    /// Default headers for requests to AI hosts: `--host-header` values plus the
+    /// `--basic-auth` Authorization header.
+    #[must_use]
+    pub fn host_headers(&self) -> HeaderMap {
+        let mut headers: HeaderMap = self.host_header.iter().cloned().collect();
+        if let Some(credentials) = &self.basic_auth
+            && let Ok(mut value) =
+                HeaderValue::from_str(&format!("Basic {}", STANDARD.encode(credentials)))
+        {
+            value.set_sensitive(true);
+            headers.insert(AUTHORIZATION, value);
+        }
+        headers
+    }
+
+    #[must_use]
+    pub const fn asset_filter(&self) -> AssetFilter {
+        AssetFilter {
+            skip_archived: self.skip_archived,
+            skip_trashed: self.skip_trashed,
+        }
+    }
+
     #[must_use]
     pub fn effective_overwrite_policy(&self) -> OverwritePolicy {
         match self.overwrite_policy {
             Some(policy) => policy,
-            None if self.overwrite_existing => OverwritePolicy::All,
+            None if self.overwrite_existing || !self.only_asset.is_empty() => OverwritePolicy::All,
             None => OverwritePolicy::default(),
         }
     }
+
+    /// Returns the base prompt with the description language instruction appended, if any,
+    /// and the `Format:` line instruction with `--extract-format-tag`.
+    ///
+    /// In `--format-only` mode the classification prompt is used instead.
+    #[must_use]
+    pub fn effective_prompt(&self) -> String {
+        if self.format_only {
+            return FORMAT_ONLY_PROMPT.to_owned();
+        }
+        let prompt = match self.description_language.as_deref().map(str::trim) {
+            Some(language) if !language.is_empty() => {
+                format!(
+                    "{}\nWrite the description in {language}.",
+                    self.prompt.trim_end()
+                )
+            }
+            _ => self.prompt.clone(),
+        };
+        if self.extract_format_tag {
+            format!("{}\n{FORMAT_LINE_INSTRUCTION}", prompt.trim_end())
+        } else {
+            prompt
+        }
+    }
+
+    #[must_use]
+    pub fn effective_strip_thinking(&self) -> bool {
+        self.strip_thinking
+            .unwrap_or_else(|| self.model_name.contains("thinking"))
+    }
+
+    /// Whether monitor mode scans existing previews on start, defaulting to off in combined mode.
+    #[must_use]
+    pub fn effective_scan_on_start(&self) -> bool {
+        self.scan_on_start.unwrap_or(!self.combined)
+    }
 }
@@ -1,25 +1,35 @@
 use crate::{
-    args::{Args, Interface, OverwritePolicy},
+    args::{Args, DescriptionColumn, ImageDetail, OverwritePolicy, StabilityMode},
     data_access::DataAccess,
-    host_manager::HostManager,
+    database::AnalysisOutput,
+    failure_cache::FailureCache,
+    host_manager::ImageAnalyzer,
+    pause::Pause,
+    post_hook::PostHook,
 };
-use std::num::NonZeroU32;
+use serde_json::{Map, Value};
+use std::{sync::Arc, time::Duration};
 
 #[derive(Debug, Clone)]
+#[expect(clippy::struct_excessive_bools)]
 pub struct MonitorConfig {
+    /// Process the existing backlog and return instead of watching (`--once`)
+    pub once: bool,
+    /// Process previews that already exist before watching for new ones
+    pub scan_on_start: bool,
     pub file_write_timeout: u64,
     pub file_check_interval: u64,
+    pub file_stable_checks: u8,
+    pub stability_mode: StabilityMode,
+    /// Also require JPEG previews to end with the end-of-image marker before processing
+    pub verify_jpeg_eof: bool,
     pub event_cooldown: u64,
-    pub timeout: u64,
+    /// Seconds spawned tasks may run after a stop signal before they are abandoned
+    pub shutdown_grace: u64,
     pub lang: String,
     pub overwrite_policy: OverwritePolicy,
-    pub hosts: Vec<String>,
-    pub interface: Interface,
-    pub api_key: Option<String>,
-    pub unavailable_duration: u64,
+    pub max_concurrent: usize,
     pub api_poll_interval: u32,
-    pub max_retries: Option<NonZeroU32>,
-    pub retry_delay_seconds: u64,
     pub enrich_prompt: bool,
     pub preserve_human: bool,
     pub disable_ai_wrapper: bool,
@@ -29,19 +39,19 @@ impl MonitorConfig {
     #[must_use]
     pub fn from_args(args: &Args, lang: &str) -> Self {
         Self {
+            once: args.once,
+            scan_on_start: args.effective_scan_on_start(),
             file_write_timeout: args.file_write_timeout,
             file_check_interval: args.file_check_interval,
+            file_stable_checks: args.file_stable_checks,
+            stability_mode: args.stability_mode,
+            verify_jpeg_eof: args.verify_jpeg_eof,
             event_cooldown: args.event_cooldown,
-            timeout: args.timeout,
+            shutdown_grace: args.shutdown_grace,
             lang: lang.to_owned(),
             overwrite_policy: args.effective_overwrite_policy(),
-            hosts: args.hosts.clone(),
-            interface: args.interface,
-            api_key: args.api_key.clone(),
-            unavailable_duration: args.unavailable_duration,
+            max_concurrent: args.max_concurrent,
             api_poll_interval: args.api_poll_interval,
-            max_retries: NonZeroU32::new(args.max_retries),
-            retry_delay_seconds: args.retry_delay_seconds,
             enrich_prompt: args.enrich_prompt,
             preserve_human: args.preserve_human,
             disable_ai_wrapper: args.disable_ai_wrapper,
@@ -49,15 +59,169 @@ impl MonitorConfig {
     }
 }
 
+pub const BYTES_PER_MB: u64 = 1024 * 1024;
+
+/// Per-request options applied by the host manager when analyzing an image.
+#[derive(Debug, Clone, Default)]
+#[expect(clippy::struct_excessive_bools)]
+pub struct AnalysisOptions {
+    pub strip_thinking: bool,
+    /// Maximum preview file size in bytes, `None` for no limit
+    pub max_file_size: Option<u64>,
+    /// Decode images before sending them and skip the ones that fail
+    pub skip_corrupt_images: bool,
+    pub output: AnalysisOutput,
+    /// Parent tag for formats split off descriptions (`--extract-format-tag`), `None` to keep
+    /// the format in the description
+    pub format_tag_prefix: Option<String>,
+    /// Prepend the asset's original filename to the prompt unless it is camera-generated
+    pub use_filename_hint: bool,
+    /// Prepend the names of up to this many recognized people to the prompt, `None` to disable
+    pub include_people: Option<usize>,
+    /// Minimum description length in characters, 0 for no limit
+    pub min_description_length: usize,
+    /// Lowercased phrases that mark a description as a refusal
+    pub refusal_phrases: Vec<String>,
+    /// Extra request timeout in seconds per megabyte of image, 0 for a flat timeout
+    pub timeout_per_mb: u64,
+    /// Upper limit in seconds for the scaled request timeout
+    pub max_timeout: u64,
+    /// Ollama `keep_alive`: a duration string or a number of seconds, `None` for the server default
+    pub keep_alive: Option<Value>,
+    /// Unload the Ollama model after every analyzed image
+    pub reset_context: bool,
+    /// Ollama `options` object of each request, empty to send none
+    pub ollama_options: Map<String, Value>,
+    /// Most images per request: the asset's own preview plus related stacked images
+    pub max_images: usize,
+    /// Time `--interface mock` takes to answer
+    pub mock_delay: Duration,
+    /// Longest side images are shrunk to before sending, `None` to send them as they are
+    pub max_image_dimension: Option<u32>,
+    /// Images with both sides below this are skipped, `None` to send any size
+    pub min_image_dimension: Option<u32>,
+    /// Path appended to each host instead of the interface's default endpoint
+    pub endpoint_path: Option<String>,
+    /// Longest wait for response headers, `None` to only apply the request timeout
+    pub response_start_timeout: Option<Duration>,
+    /// `detail` of image parts sent to OpenAI-compatible servers
+    pub image_detail: ImageDetail,
+    /// Log the first request body, with images elided, at debug level
+    pub dump_request: bool,
+    /// Overwriting policies keep descriptions written more recently than this
+    pub overwrite_older_than: Option<Duration>,
+    /// Added before and after every generated description, empty for none
+    pub description_prefix: String,
+    pub description_suffix: String,
+    /// Print how each description changes before storing it (`--diff`)
+    pub show_diff: bool,
+    /// Only print the change, leaving the stored description as it is
+    pub diff_only: bool,
+}
+
+impl AnalysisOptions {
+    #[must_use]
+    pub fn from_args(args: &Args) -> Self {
+        Self {
+            strip_thinking: args.effective_strip_thinking(),
+            max_file_size: (args.max_file_size_mb > 0)
+                .then(|| args.max_file_size_mb.saturating_mul(BYTES_PER_MB)),
+            skip_corrupt_images: args.skip_corrupt_images,
+            output: if args.format_only {
+                AnalysisOutput::Tag {
+                    prefix: args.tag_prefix.trim_matches('/').to_owned(),
+                }
+            } else {
+                match args.description_column {
+                    DescriptionColumn::Exif => AnalysisOutput::Description,
+                    DescriptionColumn::AssetMetadata => AnalysisOutput::Metadata,
+                }
+            },
+            format_tag_prefix: args
+                .extract_format_tag
+                .then(|| args.tag_prefix.trim_matches('/').to_owned()),
+            use_filename_hint: args.use_filename_hint,
+            include_people: (args.include_people && args.max_people > 0).then_some(args.max_people),
+            min_description_length: args.min_description_length,
+            refusal_phrases: args
+                .refusal_phrases
+                .iter()
+                .map(|phrase| phrase.trim().to_lowercase())
+                .filter(|phrase| !phrase.is_empty())
+                .collect(),
+            timeout_per_mb: args.timeout_per_image_mb,
+            max_timeout: args.max_timeout,
+            keep_alive: args.keep_alive.as_deref().map(|keep_alive| {
+                // Ollama only accepts bare numbers as seconds, "-1" would fail duration parsing
+                keep_alive
+                    .parse::<i64>()
+                    .map_or_else(|_| Value::from(keep_alive), Value::from)
+            }),
+            reset_context: args.reset_context,
+            ollama_options: args
+                .num_ctx
+                .map(|num_ctx| ("num_ctx".to_owned(), Value::from(num_ctx)))
+                .into_iter()
+                .chain(
+                    args.num_gpu
+                        .map(|num_gpu| ("num_gpu".to_owned(), Value::from(num_gpu))),
+                )
+                // Collected into a map, later `--ollama-option` entries win
+                .chain(args.ollama_option.iter().cloned())
+                .collect(),
+            max_images: usize::from(args.prompt_max_images),
+            mock_delay: Duration::from_millis(args.mock_delay_ms),
+            max_image_dimension: args.max_image_dimension,
+            min_image_dimension: args.min_image_dimension,
+            endpoint_path: args.endpoint_path.clone(),
+            response_start_timeout: args.response_start_timeout.map(Duration::from_secs),
+            image_detail: args.image_detail,
+            dump_request: args.dump_request,
+            overwrite_older_than: args.overwrite_older_than,
+            description_prefix: args.description_prefix.clone().unwrap_or_default(),
+            description_suffix: args.description_suffix.clone().unwrap_or_default(),
+            show_diff: args.diff,
+            diff_only: args.diff && !args.apply,
+        }
+    }
+}
+
+/// State shared by every file of a run, in batch and monitor processing alike.
+///
+/// Created once at startup; clones share the same state.
+#[derive(Debug, Clone, Default)]
+pub struct RunState {
+    /// Assets that failed in earlier runs (`--failure-cache`)
+    pub failure_cache: Option<Arc<FailureCache>>,
+    /// Holds back new files while paused by SIGUSR1 or `--pause-file`
+    pub pause: Option<Arc<Pause>>,
+    /// Command run after each stored description (`--post-hook`)
+    pub post_hook: Option<Arc<PostHook>>,
+}
+
+impl RunState {
+    /// Wait while processing is paused; returns at once without pause control.
+    pub async fn wait_while_paused(&self) {
+        if let Some(pause) = &self.pause {
+            pause.wait_while_paused().await;
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 pub struct ProcessingContext<'a> {
     pub data_access: &'a DataAccess,
     pub prompt: &'a str,
-    pub host_manager: &'a HostManager,
+    /// Produces the descriptions; `HostManager` outside of tests
+    pub analyzer: &'a dyn ImageAnalyzer,
     pub overwrite_policy: OverwritePolicy,
     pub enrich_prompt: bool,
     pub preserve_human: bool,
     pub disable_ai_wrapper: bool,
+    /// Skips assets that failed repeatedly and records new outcomes
+    pub failure_cache: Option<&'a FailureCache>,
+    /// Run after each stored description
+    pub post_hook: Option<&'a Arc<PostHook>>,
 }
 
 impl<'a> ProcessingContext<'a> {
@@ -65,7 +229,7 @@ impl<'a> ProcessingContext<'a> {
     pub const fn new(
         data_access: &'a DataAccess,
         prompt: &'a str,
-        host_manager: &'a HostManager,
+        analyzer: &'a dyn ImageAnalyzer,
         overwrite_policy: OverwritePolicy,
         enrich_prompt: bool,
         preserve_human: bool,
@@ -74,11 +238,22 @@ impl<'a> ProcessingContext<'a> {
         Self {
             data_access,
             prompt,
-            host_manager,
+            analyzer,
             overwrite_policy,
             enrich_prompt,
             preserve_human,
             disable_ai_wrapper,
+            failure_cache: None,
+            post_hook: None,
         }
     }
+
+    /// Track failures and run the post hook from `run_state`; without it every asset is
+    /// always tried and no hook runs.
+    #[must_use]
+    pub fn with_run_state(mut self, run_state: &'a RunState) -> Self {
+        self.failure_cache = run_state.failure_cache.as_deref();
+        self.post_hook = run_state.post_hook.as_ref();
+        self
+    }
 }
@@ -1,12 +1,83 @@
+use crate::database::{AnalysisOutput, RunLogEntry};
 use crate::error::ImageAnalysisError;
 use crate::immich_api::{AssetMetadata, AssetRef, ImmichApiProvider};
-use crate::utils::{extract_uuid_from_preview_filename, filename_from_path, is_preview_filename};
+use crate::results_db::ResultsDb;
+use crate::utils::{
+    downscale_image, extract_asset_id_from_preview_path, is_preview_filename,
+    normalize_preview_extensions,
+};
 use clap::ValueEnum;
+use futures::channel::mpsc::{TrySendError, UnboundedSender};
+use log::{debug, info};
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::SystemTime;
 use tokio_postgres::Client as PgClient;
 use uuid::Uuid;
 
+/// Longest side of the downscaled copy sent in place of a missing preview (Immich's preview size)
+const ORIGINAL_FALLBACK_MAX_SIZE: u32 = 1440;
+
+/// Original file extensions that can be decoded for the preview fallback
+const ORIGINAL_FALLBACK_EXTENSIONS: [&str; 4] = ["jpg", "jpeg", "png", "webp"];
+
+/// Media locations Immich stores in `asset."originalPath"`, mapped onto `immich_root`
+pub const IMMICH_MEDIA_LOCATIONS: [&str; 2] = ["/usr/src/app/upload", "/data"];
+
+/// Which assets discovery leaves out because of their Immich status.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AssetFilter {
+    /// Leave out archived assets (`--skip-archived`)
+    pub skip_archived: bool,
+    /// Leave out assets in the trash (`--skip-trashed`)
+    pub skip_trashed: bool,
+}
+
+impl AssetFilter {
+    #[must_use]
+    pub const fn is_active(self) -> bool {
+        self.skip_archived || self.skip_trashed
+    }
+
+    /// Why an asset with this status is left out, `None` if it is kept.
+    ///
+    /// An archived asset in the trash counts as trashed.
+    #[must_use]
+    pub const fn exclusion(self, archived: bool, trashed: bool) -> Option<Exclusion> {
+        if trashed && self.skip_trashed {
+            Some(Exclusion::Trashed)
+        } else if archived && self.skip_archived {
+            Some(Exclusion::Archived)
+        } else {
+            None
+        }
+    }
+}
+
+/// Reason an asset was left out by an [`AssetFilter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exclusion {
+    Archived,
+    Trashed,
+}
+
+/// Number of assets left out by an [`AssetFilter`] during discovery.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ExcludedAssets {
+    pub archived: u64,
+    pub trashed: u64,
+}
+
+impl ExcludedAssets {
+    pub const fn count(&mut self, exclusion: Exclusion) {
+        match exclusion {
+            Exclusion::Archived => self.archived = self.archived.saturating_add(1),
+            Exclusion::Trashed => self.trashed = self.trashed.saturating_add(1),
+        }
+    }
+}
+
 /// Mode of data access.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
 pub enum DataAccessMode {
@@ -26,14 +97,67 @@ pub enum DataAccess {
     Database {
         /// `PostgreSQL` client for direct database queries
         client: Arc<PgClient>,
-        /// Root path to Immich data directory (for filesystem access to thumbs/)
+        /// Root path to Immich data directory (for filesystem access to originals)
         immich_root: PathBuf,
+        /// Directory holding the previews, `thumbs/` under `immich_root` by default
+        thumbs_dir: PathBuf,
+        /// Normalized file extensions of the previews in `thumbs_dir`
+        preview_extensions: Vec<String>,
+        /// Analyze a downscaled copy of the original for image assets without a preview
+        use_original_fallback: bool,
+        /// Archived and trashed assets left out of discovery
+        asset_filter: AssetFilter,
+        /// Local database receiving descriptions next to or instead of Immich
+        results_db: Option<ResultsDb>,
     },
     /// API-backed access using Immich REST API
     ImmichApi {
         /// Immich API provider for HTTP-based operations
         provider: Arc<ImmichApiProvider>,
+        /// Local database receiving descriptions next to or instead of Immich
+        results_db: Option<ResultsDb>,
     },
+    /// In-memory assets and descriptions for unit tests
+    #[cfg(test)]
+    Memory(Arc<MemoryStore>),
+}
+
+/// Assets and descriptions held by [`DataAccess::Memory`].
+#[cfg(test)]
+#[derive(Default)]
+pub struct MemoryStore {
+    /// Assets that exist
+    pub assets: Vec<Uuid>,
+    /// Asset descriptions, as written by `update_description`
+    pub descriptions: std::sync::Mutex<HashMap<Uuid, String>>,
+    /// Descriptions in the `immich-analyze` metadata entry
+    pub metadata_descriptions: std::sync::Mutex<HashMap<Uuid, String>>,
+    /// Local database receiving descriptions next to or instead of the store
+    pub results_db: Option<ResultsDb>,
+}
+
+#[cfg(test)]
+impl MemoryStore {
+    /// Creates a store holding `assets`, none of them described yet.
+    pub fn with_assets(assets: &[Uuid]) -> Self {
+        Self {
+            assets: assets.to_vec(),
+            ..Self::default()
+        }
+    }
+
+    /// The asset descriptions, surviving a panicked test holding the lock.
+    pub fn descriptions(&self) -> std::sync::MutexGuard<'_, HashMap<Uuid, String>> {
+        self.descriptions
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    fn metadata_descriptions(&self) -> std::sync::MutexGuard<'_, HashMap<Uuid, String>> {
+        self.metadata_descriptions
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
 }
 
 impl DataAccess {
@@ -42,10 +166,28 @@ impl DataAccess {
     /// # Arguments
     /// * `client` - Arc-wrapped `PostgreSQL` client
     /// * `immich_root` - Path to Immich root directory (containing thumbs/)
-    pub const fn new_database(client: Arc<PgClient>, immich_root: PathBuf) -> Self {
+    /// * `thumbs_subdir` - Preview directory relative to `immich_root` (`--thumbs-subdir`)
+    /// * `preview_extensions` - Preview file extensions to process (`--preview-extensions`)
+    /// * `use_original_fallback` - Fall back to the original file for assets without a preview
+    /// * `asset_filter` - Archived and trashed assets to leave out of discovery
+    /// * `results_db` - Local database receiving descriptions (`--results-db`)
+    pub fn new_database(
+        client: Arc<PgClient>,
+        immich_root: PathBuf,
+        thumbs_subdir: &str,
+        preview_extensions: &[String],
+        use_original_fallback: bool,
+        asset_filter: AssetFilter,
+        results_db: Option<ResultsDb>,
+    ) -> Self {
         Self::Database {
             client,
+            thumbs_dir: immich_root.join(thumbs_subdir),
+            preview_extensions: normalize_preview_extensions(preview_extensions),
             immich_root,
+            use_original_fallback,
+            asset_filter,
+            results_db,
         }
     }
 
@@ -53,48 +195,166 @@ impl DataAccess {
     ///
     /// # Arguments
     /// * `provider` - Arc-wrapped Immich API provider
-    pub const fn new_api(provider: Arc<ImmichApiProvider>) -> Self {
-        Self::ImmichApi { provider }
+    /// * `results_db` - Local database receiving descriptions (`--results-db`)
+    pub const fn new_api(provider: Arc<ImmichApiProvider>, results_db: Option<ResultsDb>) -> Self {
+        Self::ImmichApi {
+            provider,
+            results_db,
+        }
+    }
+
+    /// The local database receiving descriptions, if `--results-db` is set.
+    // Only the test-only `Memory` arm keeps this from being `const`
+    #[cfg_attr(not(test), expect(clippy::missing_const_for_fn))]
+    fn results_db(&self) -> Option<&ResultsDb> {
+        match self {
+            Self::Database { results_db, .. } | Self::ImmichApi { results_db, .. } => {
+                results_db.as_ref()
+            }
+            #[cfg(test)]
+            Self::Memory(store) => store.results_db.as_ref(),
+        }
+    }
+
+    /// Whether descriptions and tags are written to Immich, i.e. the write target is not
+    /// `results-db`.
+    #[must_use]
+    pub fn writes_immich(&self) -> bool {
+        self.results_db().is_none_or(ResultsDb::writes_immich)
+    }
+
+    /// Stores a description in the results database, if one is open.
+    async fn store_result(
+        &self,
+        asset_id: &Uuid,
+        description: &str,
+    ) -> Result<(), ImageAnalysisError> {
+        match self.results_db() {
+            Some(results_db) => results_db.store(*asset_id, description).await,
+            None => Ok(()),
+        }
     }
 
-    /// Gets a list of assets that need processing (no description yet).
+    /// Sends every asset that may need processing to `sender` as it is discovered.
     ///
     /// # Database mode
-    /// Uses `crate::file_processing::get_immich_preview_files` to scan the filesystem,
+    /// Uses `crate::file_processing::walk_immich_preview_files` to scan the filesystem,
+    /// so assets are sent while the walk is still in progress.
+    /// With `use_original_fallback`, image assets without a preview on disk are sent
+    /// after the walk. Archived and trashed assets are looked up before the walk and
+    /// left out according to `asset_filter`.
     ///
     /// # API mode
-    /// Fetches from Immich API `/api/search/metadata` endpoint, returning all assets.
+    /// Fetches from Immich API `/api/search/metadata` endpoint, then sends all assets
+    /// the provider's asset filter keeps.
+    ///
+    /// # Arguments
+    /// * `sender` - Channel receiving `AssetRef`s; dropped once discovery finishes
     ///
     /// # Returns
-    /// Vector of `AssetRef` structs for assets awaiting description generation.
-    pub async fn get_assets_to_process(&self) -> Result<Vec<AssetRef>, ImageAnalysisError> {
+    /// How many assets were left out as archived or trashed.
+    pub async fn discover_assets(
+        &self,
+        sender: UnboundedSender<AssetRef>,
+    ) -> Result<ExcludedAssets, ImageAnalysisError> {
         match self {
             Self::Database {
-                client: _,
-                immich_root,
+                client,
+                thumbs_dir,
+                preview_extensions,
+                use_original_fallback,
+                asset_filter,
+                ..
             } => {
-                let preview_files =
-                    crate::file_processing::get_immich_preview_files(immich_root).await?;
-
-                let mut assets = Vec::new();
-                for file_path in preview_files {
-                    let filename = filename_from_path(&file_path);
-
-                    if let Ok(asset_id) = extract_uuid_from_preview_filename(&filename) {
-                        assets.push(AssetRef { id: asset_id });
+                let exclusions: HashMap<Uuid, Exclusion> = if asset_filter.is_active() {
+                    crate::database::get_archived_or_trashed_assets(client)
+                        .await?
+                        .into_iter()
+                        .filter_map(|(asset_id, archived, trashed)| {
+                            asset_filter
+                                .exclusion(archived, trashed)
+                                .map(|exclusion| (asset_id, exclusion))
+                        })
+                        .collect()
+                } else {
+                    HashMap::new()
+                };
+                let mut excluded = ExcludedAssets::default();
+                let mut with_preview = HashSet::new();
+                crate::file_processing::walk_immich_preview_files(
+                    thumbs_dir,
+                    preview_extensions,
+                    |file_path| {
+                        if let Ok(asset_id) = extract_asset_id_from_preview_path(&file_path) {
+                            with_preview.insert(asset_id);
+                            if let Some(exclusion) = exclusions.get(&asset_id) {
+                                excluded.count(*exclusion);
+                            } else {
+                                let _: Result<(), TrySendError<AssetRef>> =
+                                    sender.unbounded_send(AssetRef {
+                                        id: asset_id,
+                                        preview_path: Some(file_path),
+                                    });
+                            }
+                        }
+                    },
+                )
+                .await?;
+                if *use_original_fallback {
+                    let missing: Vec<Uuid> = crate::database::get_image_asset_ids(client)
+                        .await?
+                        .into_iter()
+                        .filter(|asset_id| !with_preview.contains(asset_id))
+                        .filter(|asset_id| {
+                            exclusions.get(asset_id).is_none_or(|exclusion| {
+                                excluded.count(*exclusion);
+                                false
+                            })
+                        })
+                        .collect();
+                    if !missing.is_empty() {
+                        info!(
+                            "{} image assets have no preview, using their originals",
+                            missing.len()
+                        );
                     }
+                    for asset_id in missing {
+                        let _: Result<(), TrySendError<AssetRef>> =
+                            sender.unbounded_send(AssetRef {
+                                id: asset_id,
+                                preview_path: None,
+                            });
+                    }
+                }
+                Ok(excluded)
+            }
+            Self::ImmichApi { provider, .. } => {
+                let (assets, excluded) = provider.get_assets().await?;
+                for asset in assets {
+                    let _: Result<(), TrySendError<AssetRef>> = sender.unbounded_send(asset);
+                }
+                Ok(excluded)
+            }
+            #[cfg(test)]
+            Self::Memory(store) => {
+                for asset_id in &store.assets {
+                    let _: Result<(), TrySendError<AssetRef>> = sender.unbounded_send(AssetRef {
+                        id: *asset_id,
+                        preview_path: None,
+                    });
                 }
-                Ok(assets)
+                Ok(ExcludedAssets::default())
             }
-            Self::ImmichApi { provider } => provider.get_assets().await,
         }
     }
 
     /// Gets the filesystem path to the preview image for an asset.
     ///
     /// # Database mode
-    /// Scans the `thumbs/` directory tree under `immich_root` to locate
+    /// Scans the `thumbs_dir` directory tree to locate
     /// the preview file matching the asset UUID, then returns its path.
+    /// With `use_original_fallback`, an image asset without a preview gets a downscaled
+    /// JPEG copy of its original in a temporary file instead.
     ///
     /// # API mode
     /// Downloads from Immich API `/api/assets/{id}/thumbnail?size=preview` endpoint
@@ -108,20 +368,34 @@ impl DataAccess {
     /// `PathBuf` to the preview image file suitable for AI analysis.
     pub async fn get_preview_path(&self, asset_id: &Uuid) -> Result<PathBuf, ImageAnalysisError> {
         match self {
-            Self::Database { immich_root, .. } => {
-                Self::find_preview_file_in_thumbs(immich_root, asset_id).await
-            }
-            Self::ImmichApi { provider } => provider.get_preview_path(asset_id).await,
+            Self::Database {
+                client,
+                immich_root,
+                thumbs_dir,
+                preview_extensions,
+                use_original_fallback,
+                ..
+            } => match Self::find_preview_file_in_thumbs(thumbs_dir, preview_extensions, asset_id)
+                .await
+            {
+                Err(_) if *use_original_fallback => {
+                    Self::downscaled_original(client, immich_root, asset_id).await
+                }
+                found => found,
+            },
+            Self::ImmichApi { provider, .. } => provider.get_preview_path(asset_id).await,
+            #[cfg(test)]
+            Self::Memory(_) => Ok(PathBuf::from(format!("{asset_id}_preview.jpeg"))),
         }
     }
 
     /// Helper: find preview file in thumbs directory tree for database mode.
     async fn find_preview_file_in_thumbs(
-        immich_root: &Path,
+        thumbs_dir: &Path,
+        preview_extensions: &[String],
         asset_id: &Uuid,
     ) -> Result<PathBuf, ImageAnalysisError> {
-        let thumbs_dir = immich_root.join("thumbs");
-        let mut stack = vec![thumbs_dir];
+        let mut stack = vec![thumbs_dir.to_path_buf()];
 
         while let Some(current_dir) = stack.pop() {
             match tokio::fs::read_dir(&current_dir).await {
@@ -133,11 +407,11 @@ impl DataAccess {
                         } else if path.is_file() {
                             let filename = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
 
-                            if !is_preview_filename(filename) {
+                            if !is_preview_filename(filename, preview_extensions) {
                                 continue;
                             }
 
-                            if let Ok(found_id) = extract_uuid_from_preview_filename(filename)
+                            if let Ok(found_id) = extract_asset_id_from_preview_path(&path)
                                 && found_id == *asset_id
                             {
                                 return Ok(path);
@@ -157,6 +431,39 @@ impl DataAccess {
         })
     }
 
+    /// Helper: write a downscaled copy of an image asset's original for database mode.
+    ///
+    /// The copy is named like a preview so the asset UUID can be recovered from it.
+    async fn downscaled_original(
+        client: &PgClient,
+        immich_root: &Path,
+        asset_id: &Uuid,
+    ) -> Result<PathBuf, ImageAnalysisError> {
+        let Some(original_path) =
+            crate::database::get_original_image_path(client, *asset_id).await?
+        else {
+            return Err(ImageAnalysisError::UnsupportedOriginal {
+                filename: asset_id.to_string(),
+            });
+        };
+        let original = resolve_original_path(immich_root, &original_path);
+        let decodable = original
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| {
+                ORIGINAL_FALLBACK_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str())
+            });
+        if !decodable {
+            return Err(ImageAnalysisError::UnsupportedOriginal {
+                filename: original_path,
+            });
+        }
+        debug!("Using original {} for asset {asset_id}", original.display());
+        let target = std::env::temp_dir().join(format!("{asset_id}_preview.jpeg"));
+        downscale_image(&original, &target, ORIGINAL_FALLBACK_MAX_SIZE).await?;
+        Ok(target)
+    }
+
     /// Checks if an asset exists in the database.
     ///
     /// # Database mode
@@ -175,7 +482,9 @@ impl DataAccess {
             Self::Database { client, .. } => {
                 crate::database::check_asset_exists(client, *asset_id).await
             }
-            Self::ImmichApi { provider } => provider.asset_exists(asset_id).await,
+            Self::ImmichApi { provider, .. } => provider.asset_exists(asset_id).await,
+            #[cfg(test)]
+            Self::Memory(store) => Ok(store.assets.contains(asset_id)),
         }
     }
 
@@ -201,8 +510,147 @@ impl DataAccess {
             Self::Database { client, .. } => {
                 crate::database::get_asset_metadata(client, *asset_id).await
             }
-            Self::ImmichApi { provider } => provider.get_asset_metadata(asset_id).await,
+            Self::ImmichApi { provider, .. } => provider.get_asset_metadata(asset_id).await,
+            #[cfg(test)]
+            Self::Memory(_) => Err(ImageAnalysisError::AssetNotFound {
+                asset_id: *asset_id,
+            }),
+        }
+    }
+
+    /// Gets the original filename of an asset, used for the `--use-filename-hint` prompt hint.
+    ///
+    /// # Database mode
+    /// Queries the `asset` table.
+    ///
+    /// # API mode
+    /// Uses the metadata from `GET /api/assets/{id}`.
+    ///
+    /// # Arguments
+    /// * `asset_id` - UUID of the target asset
+    pub async fn get_original_file_name(
+        &self,
+        asset_id: &Uuid,
+    ) -> Result<Option<String>, ImageAnalysisError> {
+        match self {
+            Self::Database { client, .. } => {
+                crate::database::get_original_file_name(client, *asset_id).await
+            }
+            Self::ImmichApi { provider, .. } => provider
+                .get_asset_metadata(asset_id)
+                .await
+                .map(|metadata| metadata.original_file_name),
+            #[cfg(test)]
+            Self::Memory(_) => Ok(None),
+        }
+    }
+
+    /// Gets the names of up to `limit` people recognized in an asset (`--include-people`).
+    ///
+    /// Unnamed and hidden people are left out.
+    ///
+    /// # Database mode
+    /// Queries the `person` and `asset_face` tables.
+    ///
+    /// # API mode
+    /// Uses the people listed by Immich API `GET /api/assets/{id}`.
+    ///
+    /// # Arguments
+    /// * `asset_id` - UUID of the target asset
+    /// * `limit` - Maximum number of names returned
+    pub async fn get_asset_people_names(
+        &self,
+        asset_id: &Uuid,
+        limit: usize,
+    ) -> Result<Vec<String>, ImageAnalysisError> {
+        match self {
+            Self::Database { client, .. } => {
+                crate::database::get_asset_people_names(client, *asset_id, limit).await
+            }
+            Self::ImmichApi { provider, .. } => {
+                let metadata = provider.get_asset_metadata(asset_id).await?;
+                let mut names: Vec<String> = Vec::new();
+                for person in metadata.people {
+                    if !person.is_hidden && !person.name.is_empty() && !names.contains(&person.name)
+                    {
+                        names.push(person.name);
+                    }
+                }
+                names.sort();
+                names.truncate(limit);
+                Ok(names)
+            }
+            #[cfg(test)]
+            Self::Memory(_) => Ok(Vec::new()),
+        }
+    }
+
+    /// Gets the names of the albums containing an asset, used for prompt placeholders.
+    ///
+    /// # Database mode
+    /// Queries the `album` and `album_asset` tables.
+    ///
+    /// # API mode
+    /// Fetches from Immich API `GET /api/albums?assetId={id}` endpoint.
+    ///
+    /// # Arguments
+    /// * `asset_id` - UUID of the target asset
+    pub async fn get_asset_album_names(
+        &self,
+        asset_id: &Uuid,
+    ) -> Result<Vec<String>, ImageAnalysisError> {
+        match self {
+            Self::Database { client, .. } => {
+                crate::database::get_asset_album_names(client, *asset_id).await
+            }
+            Self::ImmichApi { provider, .. } => provider.get_asset_album_names(asset_id).await,
+            #[cfg(test)]
+            Self::Memory(_) => Ok(Vec::new()),
+        }
+    }
+
+    /// Gets preview paths of up to `limit` other images stacked with an asset, to send
+    /// alongside it (`--prompt-max-images`).
+    ///
+    /// Related images are optional context, so a member whose preview cannot be found
+    /// is left out rather than failing the asset. Callers clean up the returned paths
+    /// with `cleanup_preview`.
+    ///
+    /// # Database mode
+    /// Reads the stack members from the `asset` table.
+    ///
+    /// # API mode
+    /// Reads the stack through `/api/assets/{id}` and `/api/stacks/{id}`.
+    ///
+    /// # Arguments
+    /// * `asset_id` - UUID of the asset being analyzed
+    /// * `limit` - Maximum number of related previews
+    pub async fn get_related_preview_paths(
+        &self,
+        asset_id: &Uuid,
+        limit: usize,
+    ) -> Result<Vec<PathBuf>, ImageAnalysisError> {
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
+        let member_ids = match self {
+            Self::Database { client, .. } => {
+                crate::database::get_stack_member_ids(client, *asset_id, limit).await?
+            }
+            Self::ImmichApi { provider, .. } => {
+                provider.get_stack_member_ids(asset_id, limit).await?
+            }
+            #[cfg(test)]
+            Self::Memory(_) => Vec::new(),
+        };
+        let mut paths = Vec::with_capacity(member_ids.len());
+        for member_id in member_ids {
+            match self.get_preview_path(&member_id).await {
+                Ok(path) => paths.push(path),
+                Err(err) => debug!("No preview for stacked asset {member_id}: {err}"),
+            }
         }
+        Ok(paths)
     }
 
     /// Updates or creates a description for an asset.
@@ -214,6 +662,10 @@ impl DataAccess {
     /// # API mode
     /// Sends PUT request to Immich API `/api/assets/{id}` with description payload.
     ///
+    /// # Results database
+    /// The description is stored in the results database as well, and only there
+    /// with `--write-target results-db`.
+    ///
     /// # Arguments
     /// * `asset_id` - UUID of the target asset
     /// * `description` - Generated description text to store
@@ -222,17 +674,83 @@ impl DataAccess {
         asset_id: &Uuid,
         description: &str,
     ) -> Result<(), ImageAnalysisError> {
+        if self.writes_immich() {
+            match self {
+                Self::Database { client, .. } => {
+                    crate::database::update_or_create_asset_description(
+                        client,
+                        *asset_id,
+                        description,
+                    )
+                    .await?;
+                }
+                Self::ImmichApi { provider, .. } => {
+                    provider.update_description(asset_id, description).await?;
+                }
+                #[cfg(test)]
+                Self::Memory(store) => {
+                    store
+                        .descriptions()
+                        .insert(*asset_id, description.to_owned());
+                }
+            }
+        }
+        self.store_result(asset_id, description).await
+    }
+
+    /// Creates the tag if needed and links it to an asset.
+    ///
+    /// # Database mode
+    /// Upserts the tag (and parent tags) into the `tag` table and links it via `tag_asset`.
+    ///
+    /// # API mode
+    /// Upserts the tag via `PUT /api/tags`, then links it via `PUT /api/tags/{id}/assets`.
+    ///
+    /// # Results database
+    /// Tags are not stored there, so with `--write-target results-db` nothing is written.
+    ///
+    /// # Arguments
+    /// * `asset_id` - UUID of the target asset
+    /// * `tag_value` - Full tag path, e.g. `Format/Screenshot`
+    pub async fn tag_asset(
+        &self,
+        asset_id: &Uuid,
+        tag_value: &str,
+    ) -> Result<(), ImageAnalysisError> {
+        if !self.writes_immich() {
+            debug!("Not tagging asset {asset_id} with {tag_value}, Immich is only read");
+            return Ok(());
+        }
         match self {
             Self::Database { client, .. } => {
-                crate::database::update_or_create_asset_description(client, *asset_id, description)
-                    .await
-            }
-            Self::ImmichApi { provider } => {
-                provider.update_description(asset_id, description).await
+                crate::database::upsert_asset_tag(client, *asset_id, tag_value).await
             }
+            Self::ImmichApi { provider, .. } => provider.tag_asset(asset_id, tag_value).await,
+            #[cfg(test)]
+            Self::Memory(_) => Ok(()),
         }
     }
 
+    /// Checks if an asset already has a tag nested under `prefix`.
+    ///
+    /// Uses the tags returned by `get_asset_metadata` in both modes.
+    ///
+    /// # Arguments
+    /// * `asset_id` - UUID of the target asset
+    /// * `prefix` - Parent tag path without trailing slash
+    pub async fn has_tag_under(
+        &self,
+        asset_id: &Uuid,
+        prefix: &str,
+    ) -> Result<bool, ImageAnalysisError> {
+        let metadata = self.get_asset_metadata(asset_id).await?;
+        Ok(metadata.tags.iter().any(|tag| {
+            tag.value
+                .strip_prefix(prefix)
+                .is_some_and(|rest| rest.starts_with('/'))
+        }))
+    }
+
     /// Gets the existing description for an asset, if any.
     ///
     /// # Database mode
@@ -254,13 +772,127 @@ impl DataAccess {
             Self::Database { client, .. } => {
                 crate::database::get_asset_description(client, *asset_id).await
             }
-            Self::ImmichApi { provider } => match provider.get_asset_metadata(asset_id).await {
+            Self::ImmichApi { provider, .. } => match provider.get_asset_metadata(asset_id).await {
                 Ok(metadata) => Ok(metadata
                     .exif_info
                     .and_then(|exif| exif.description)
                     .filter(|desc| !desc.is_empty())),
                 Err(err) => Err(err),
             },
+            #[cfg(test)]
+            Self::Memory(store) => Ok(store
+                .descriptions()
+                .get(asset_id)
+                .filter(|desc| !desc.is_empty())
+                .cloned()),
+        }
+    }
+
+    /// When the description in `output` was last written, `None` if there is none.
+    ///
+    /// # Database mode
+    /// Reads `updatedAt` of the row holding the description.
+    ///
+    /// # API mode
+    /// Not available; always `None` (`--overwrite-older-than` requires database mode).
+    ///
+    /// # Results database
+    /// With `--write-target results-db`, when the results database row was stored.
+    pub async fn get_description_updated_at(
+        &self,
+        asset_id: &Uuid,
+        output: &AnalysisOutput,
+    ) -> Result<Option<SystemTime>, ImageAnalysisError> {
+        if let Some(results_db) = self.results_db()
+            && !results_db.writes_immich()
+        {
+            return results_db.updated_at(asset_id).await;
+        }
+        match self {
+            Self::Database { client, .. } => {
+                crate::database::get_description_updated_at(client, *asset_id, output).await
+            }
+            Self::ImmichApi { .. } => Ok(None),
+            #[cfg(test)]
+            Self::Memory(_) => Ok(None),
+        }
+    }
+
+    /// Gets the description stored in the asset's `immich-analyze` metadata entry, if any.
+    ///
+    /// # Database mode
+    /// Reads the `description` field of the `asset_metadata` row.
+    ///
+    /// # API mode
+    /// Fetches `/api/assets/{id}/metadata` and extracts the same field.
+    pub async fn get_metadata_description(
+        &self,
+        asset_id: &Uuid,
+    ) -> Result<Option<String>, ImageAnalysisError> {
+        match self {
+            Self::Database { client, .. } => {
+                crate::database::get_asset_metadata_description(client, *asset_id).await
+            }
+            Self::ImmichApi { provider, .. } => provider.get_metadata_description(asset_id).await,
+            #[cfg(test)]
+            Self::Memory(store) => Ok(store.metadata_descriptions().get(asset_id).cloned()),
+        }
+    }
+
+    /// Stores a description in the asset's `immich-analyze` metadata entry.
+    ///
+    /// # Database mode
+    /// Upserts the row into the `asset_metadata` table.
+    ///
+    /// # API mode
+    /// Sends PUT request to `/api/assets/{id}/metadata`.
+    ///
+    /// # Results database
+    /// Stored as in `update_description`.
+    pub async fn update_metadata_description(
+        &self,
+        asset_id: &Uuid,
+        description: &str,
+    ) -> Result<(), ImageAnalysisError> {
+        if self.writes_immich() {
+            match self {
+                Self::Database { client, .. } => {
+                    crate::database::update_or_create_asset_metadata_description(
+                        client,
+                        *asset_id,
+                        description,
+                    )
+                    .await?;
+                }
+                Self::ImmichApi { provider, .. } => {
+                    provider
+                        .update_metadata_description(asset_id, description)
+                        .await?;
+                }
+                #[cfg(test)]
+                Self::Memory(store) => {
+                    store
+                        .metadata_descriptions()
+                        .insert(*asset_id, description.to_owned());
+                }
+            }
+        }
+        self.store_result(asset_id, description).await
+    }
+
+    /// Checks if an asset has a description in the results database while Immich is only
+    /// read (`--write-target results-db`).
+    ///
+    /// Always `false` when descriptions are written to Immich, since Immich then holds them.
+    pub async fn has_results_db_description(
+        &self,
+        asset_id: &Uuid,
+    ) -> Result<bool, ImageAnalysisError> {
+        match self.results_db() {
+            Some(results_db) if !results_db.writes_immich() => {
+                Ok(results_db.updated_at(asset_id).await?.is_some())
+            }
+            _ => Ok(false),
         }
     }
 
@@ -282,12 +914,103 @@ impl DataAccess {
             Self::Database { client, .. } => {
                 crate::database::asset_has_description(client, *asset_id).await
             }
-            Self::ImmichApi { provider } => provider.has_description(asset_id).await,
+            Self::ImmichApi { provider, .. } => provider.has_description(asset_id).await,
+            #[cfg(test)]
+            Self::Memory(store) => Ok(store
+                .descriptions()
+                .get(asset_id)
+                .is_some_and(|desc| !desc.is_empty())),
+        }
+    }
+
+    /// Returns the subset of `asset_ids` that already have a non-empty description.
+    ///
+    /// # Database mode
+    /// Checks all assets with a single `asset_exif` query.
+    ///
+    /// # API mode
+    /// Falls back to one `has_description` call per asset.
+    pub async fn get_assets_with_descriptions(
+        &self,
+        asset_ids: &[Uuid],
+    ) -> Result<HashSet<Uuid>, ImageAnalysisError> {
+        match self {
+            Self::Database { client, .. } => {
+                crate::database::get_assets_with_descriptions(client, asset_ids).await
+            }
+            Self::ImmichApi { provider, .. } => {
+                let mut described = HashSet::new();
+                for asset_id in asset_ids {
+                    if provider.has_description(asset_id).await? {
+                        described.insert(*asset_id);
+                    }
+                }
+                Ok(described)
+            }
+            #[cfg(test)]
+            Self::Memory(store) => {
+                let descriptions = store.descriptions();
+                Ok(asset_ids
+                    .iter()
+                    .filter(|asset_id| {
+                        descriptions
+                            .get(asset_id)
+                            .is_some_and(|desc| !desc.is_empty())
+                    })
+                    .copied()
+                    .collect())
+            }
+        }
+    }
+
+    /// Returns the subset of `asset_ids` that have a smart search embedding.
+    ///
+    /// # Database mode
+    /// Checks all assets with a single `smart_search` query.
+    ///
+    /// # API mode
+    /// Not supported, since the API does not expose embeddings; returns an error.
+    pub async fn get_assets_with_smart_search(
+        &self,
+        asset_ids: &[Uuid],
+    ) -> Result<HashSet<Uuid>, ImageAnalysisError> {
+        match self {
+            Self::Database { client, .. } => {
+                crate::database::get_assets_with_smart_search(client, asset_ids).await
+            }
+            Self::ImmichApi { .. } => Err(ImageAnalysisError::InvalidConfig {
+                error: "smart search coverage requires database access mode".to_owned(),
+            }),
+            #[cfg(test)]
+            Self::Memory(_) => Ok(HashSet::new()),
+        }
+    }
+
+    /// Records a finished batch run in the run log table.
+    ///
+    /// # Database mode
+    /// Inserts a row into `immich_analyze_runs` (`--write-run-log`).
+    ///
+    /// # API mode
+    /// Does nothing; `--write-run-log` is rejected at startup in this mode.
+    pub async fn record_run(&self, run: &RunLogEntry) -> Result<(), ImageAnalysisError> {
+        match self {
+            Self::Database { client, .. } => crate::database::insert_run_log(client, run).await,
+            Self::ImmichApi { .. } => Ok(()),
+            #[cfg(test)]
+            Self::Memory(_) => Ok(()),
         }
     }
 
     pub async fn cleanup_preview(&self, path: &PathBuf) -> Result<(), ImageAnalysisError> {
-        if matches!(self, Self::ImmichApi { .. }) {
+        // Previews are downloaded in API mode; in database mode only original fallbacks are copies
+        let temporary = match self {
+            Self::ImmichApi { .. } => true,
+            Self::Database { .. } => path.starts_with(std::env::temp_dir()),
+            #[cfg(test)]
+            Self::Memory(_) => false,
+        };
+        if temporary {
             match tokio::fs::remove_file(path).await {
                 Ok(()) => Ok(()),
                 Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
@@ -301,3 +1024,15 @@ impl DataAccess {
         }
     }
 }
+
+/// Map an `originalPath` from the Immich container onto the mounted `immich_root`.
+fn resolve_original_path(immich_root: &Path, original_path: &str) -> PathBuf {
+    let path = Path::new(original_path);
+    IMMICH_MEDIA_LOCATIONS
+        .iter()
+        .find_map(|location| path.strip_prefix(location).ok())
+        .map_or_else(
+            || immich_root.join(path),
+            |relative| immich_root.join(relative),
+        )
+}
@@ -1,16 +1,37 @@
 use crate::{
     error::ImageAnalysisError,
+    events::outln,
     immich_api::{AssetMetadata, ExifInfo, PersonInfo, TagInfo},
 };
 use log::{debug, warn};
 use serde::Serialize;
-use tokio_postgres::Client as PgClient;
+use std::{collections::HashSet, time::SystemTime};
+use tokio_postgres::{Client as PgClient, error::SqlState};
 use uuid::Uuid;
 
+/// Where the model output for an asset is stored.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub enum AnalysisOutput {
+    /// Written to the asset description
+    #[default]
+    Description,
+    /// Written as a format tag nested under `prefix` (`--format-only`)
+    Tag { prefix: String },
+    /// Written to the `asset_metadata` table under [`METADATA_KEY`] (`--description-column asset-metadata`)
+    Metadata,
+}
+
+/// `asset_metadata` key under which descriptions are stored
+pub const METADATA_KEY: &str = "immich-analyze";
+
 #[derive(Debug, Serialize)]
 pub struct ImageAnalysisResult {
     pub description: String,
     pub asset_id: Uuid,
+    pub output: AnalysisOutput,
+    /// Format from the trailing `Format:` line, split off with `--extract-format-tag`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
 }
 
 /// Gets the existing description for an asset from database
@@ -72,6 +93,48 @@ pub async fn asset_has_description(
     }
 }
 
+/// Returns the assets from `asset_ids` that already have a description, in one query
+pub async fn get_assets_with_descriptions(
+    client: &PgClient,
+    asset_ids: &[Uuid],
+) -> Result<HashSet<Uuid>, ImageAnalysisError> {
+    let query = "
+        SELECT \"assetId\" FROM asset_exif
+        WHERE \"assetId\" = ANY($1)
+        AND description IS NOT NULL
+        AND description != ''
+    ";
+    match client.query(query, &[&asset_ids]).await {
+        Ok(rows) => Ok(rows.iter().map(|row| row.get(0)).collect()),
+        Err(err) => {
+            eprintln!(
+                "{}",
+                rust_i18n::t!(
+                    "database.error_checking_description",
+                    error = err.to_string()
+                )
+            );
+            Err(ImageAnalysisError::DatabaseError {
+                error: err.to_string(),
+            })
+        }
+    }
+}
+
+/// Returns the assets from `asset_ids` that have a smart search embedding, in one query
+pub async fn get_assets_with_smart_search(
+    client: &PgClient,
+    asset_ids: &[Uuid],
+) -> Result<HashSet<Uuid>, ImageAnalysisError> {
+    let query = "SELECT \"assetId\" FROM smart_search WHERE \"assetId\" = ANY($1)";
+    let rows = client.query(query, &[&asset_ids]).await.map_err(|err| {
+        ImageAnalysisError::DatabaseError {
+            error: format!("Failed to query smart search embeddings: {err}"),
+        }
+    })?;
+    Ok(rows.iter().map(|row| row.get(0)).collect())
+}
+
 /// Check if an asset exists in the asset table
 pub async fn check_asset_exists(
     client: &PgClient,
@@ -95,18 +158,21 @@ pub async fn check_asset_exists(
     }
 }
 
-/// Update or create asset description in database
+/// Update or create asset description in database.
+///
+/// Assets without an `asset_exif` row get one. An asset id that does not exist at all
+/// fails with a `DatabaseError` saying so instead of the raw foreign key violation.
 pub async fn update_or_create_asset_description(
     client: &PgClient,
     asset_id: Uuid,
     description: &str,
 ) -> Result<(), ImageAnalysisError> {
-    println!(
+    outln!(
         "{}",
         rust_i18n::t!("database.updating_asset", asset_id = asset_id)
     );
     let preview: String = description.chars().take(100).collect();
-    println!(
+    outln!(
         "{}",
         rust_i18n::t!(
             "database.description_length",
@@ -131,13 +197,19 @@ pub async fn update_or_create_asset_description(
         .execute(upsert_query, &[&asset_id, &description])
         .await
     {
+        // The upsert either inserts or updates, so a successful call always wrote the row
         Ok(_) => {
-            println!(
+            outln!(
                 "{}",
                 rust_i18n::t!("database.insert_success", asset_id = asset_id)
             );
             Ok(())
         }
+        Err(err) if err.code() == Some(&SqlState::FOREIGN_KEY_VIOLATION) => {
+            Err(ImageAnalysisError::DatabaseError {
+                error: format!("Cannot write description: asset {asset_id} does not exist ({err})"),
+            })
+        }
         Err(err) => {
             eprintln!(
                 "{}\n{}",
@@ -155,6 +227,150 @@ pub async fn update_or_create_asset_description(
     }
 }
 
+/// When the stored description of an asset was last written, `None` if it has none.
+///
+/// Uses `updatedAt` of the `asset_exif` row, which any EXIF change bumps, or of the
+/// `asset_metadata` entry for [`AnalysisOutput::Metadata`].
+pub async fn get_description_updated_at(
+    client: &PgClient,
+    asset_id: Uuid,
+    output: &AnalysisOutput,
+) -> Result<Option<SystemTime>, ImageAnalysisError> {
+    let row = if *output == AnalysisOutput::Metadata {
+        let query = r#"
+            SELECT "updatedAt" FROM asset_metadata
+            WHERE "assetId" = $1 AND key = $2
+            AND COALESCE(value->>'description', '') != ''
+        "#;
+        client.query_opt(query, &[&asset_id, &METADATA_KEY]).await
+    } else {
+        let query = r#"
+            SELECT "updatedAt" FROM asset_exif
+            WHERE "assetId" = $1
+            AND description IS NOT NULL
+            AND description != ''
+        "#;
+        client.query_opt(query, &[&asset_id]).await
+    }
+    .map_err(|err| ImageAnalysisError::DatabaseError {
+        error: format!("Failed to query description timestamp for asset {asset_id}: {err}"),
+    })?;
+    Ok(row.map(|found| found.get::<_, SystemTime>(0)))
+}
+
+/// Gets the description stored in the `asset_metadata` table for an asset
+pub async fn get_asset_metadata_description(
+    client: &PgClient,
+    asset_id: Uuid,
+) -> Result<Option<String>, ImageAnalysisError> {
+    let query = r#"
+        SELECT value->>'description' AS description FROM asset_metadata
+        WHERE "assetId" = $1 AND key = $2
+    "#;
+    let row = client
+        .query_opt(query, &[&asset_id, &METADATA_KEY])
+        .await
+        .map_err(|err| ImageAnalysisError::DatabaseError {
+            error: format!("Failed to query asset_metadata for asset {asset_id}: {err}"),
+        })?;
+    Ok(row
+        .and_then(|found| found.get::<_, Option<String>>("description"))
+        .filter(|desc| !desc.is_empty()))
+}
+
+/// Update or create the description row in the `asset_metadata` table
+pub async fn update_or_create_asset_metadata_description(
+    client: &PgClient,
+    asset_id: Uuid,
+    description: &str,
+) -> Result<(), ImageAnalysisError> {
+    outln!(
+        "{}",
+        rust_i18n::t!("database.updating_asset", asset_id = asset_id)
+    );
+    let upsert_query = r#"
+        INSERT INTO asset_metadata (
+            "assetId", key, value, "updatedAt", "updateId"
+        ) VALUES (
+            $1, $2, jsonb_build_object('description', $3::text), NOW(), immich_uuid_v7()
+        )
+        ON CONFLICT ("assetId", key) DO UPDATE
+        SET value = EXCLUDED.value,
+            "updatedAt" = NOW(),
+            "updateId" = immich_uuid_v7()
+    "#;
+    client
+        .execute(upsert_query, &[&asset_id, &METADATA_KEY, &description])
+        .await
+        .map_err(|err| ImageAnalysisError::DatabaseError {
+            error: format!("Failed to write asset_metadata for asset {asset_id}: {err}"),
+        })?;
+    outln!(
+        "{}",
+        rust_i18n::t!("database.insert_success", asset_id = asset_id)
+    );
+    Ok(())
+}
+
+/// Create a tag (and its missing parent tags) for the asset owner and link it to the asset
+pub async fn upsert_asset_tag(
+    client: &PgClient,
+    asset_id: Uuid,
+    tag_value: &str,
+) -> Result<(), ImageAnalysisError> {
+    outln!(
+        "{}",
+        rust_i18n::t!(
+            "database.tagging_asset",
+            asset_id = asset_id,
+            tag = tag_value
+        )
+    );
+
+    let upsert_tag_query = r#"
+        INSERT INTO tag ("userId", value, "parentId")
+        SELECT "ownerId", $2, $3 FROM asset WHERE id = $1
+        ON CONFLICT ("userId", value) DO UPDATE
+        SET "updatedAt" = NOW()
+        RETURNING id
+    "#;
+
+    // Immich stores hierarchical tags as one row per level, each pointing at its parent
+    let mut tag_id: Option<Uuid> = None;
+    let mut tag_path = String::new();
+    for segment in tag_value.split('/') {
+        if !tag_path.is_empty() {
+            tag_path.push('/');
+        }
+        tag_path.push_str(segment);
+        let row = client
+            .query_opt(upsert_tag_query, &[&asset_id, &tag_path, &tag_id])
+            .await
+            .map_err(|err| ImageAnalysisError::DatabaseError {
+                error: format!("Failed to upsert tag {tag_path}: {err}"),
+            })?
+            .ok_or(ImageAnalysisError::AssetNotFound { asset_id })?;
+        tag_id = Some(row.get("id"));
+    }
+
+    let link_query = r#"
+        INSERT INTO tag_asset ("assetId", "tagId")
+        VALUES ($1, $2)
+        ON CONFLICT DO NOTHING
+    "#;
+    client
+        .execute(link_query, &[&asset_id, &tag_id])
+        .await
+        .map_err(|err| ImageAnalysisError::DatabaseError {
+            error: format!("Failed to link tag {tag_value} to asset {asset_id}: {err}"),
+        })?;
+    outln!(
+        "{}",
+        rust_i18n::t!("database.tag_success", asset_id = asset_id, tag = tag_value)
+    );
+    Ok(())
+}
+
 /// Gets full metadata for an asset from the database for prompt enrichment.
 pub async fn get_asset_metadata(
     client: &PgClient,
@@ -264,7 +480,7 @@ pub async fn get_asset_metadata(
 
     // Query people linked to this asset via asset_face
     let people_query = r#"
-        SELECT p.name, to_char(p."birthDate", 'YYYY-MM-DD') AS birth_date
+        SELECT p.name, to_char(p."birthDate", 'YYYY-MM-DD') AS birth_date, p."isHidden"
         FROM person p
         JOIN asset_face af ON af."personId" = p.id
         WHERE af."assetId" = $1
@@ -276,7 +492,12 @@ pub async fn get_asset_metadata(
             .map(|row| {
                 let name: String = row.get("name");
                 let birth_date: Option<String> = row.get("birth_date");
-                PersonInfo { name, birth_date }
+                let is_hidden: bool = row.get("isHidden");
+                PersonInfo {
+                    name,
+                    birth_date,
+                    is_hidden,
+                }
             })
             .collect(),
         Err(err) => {
@@ -322,11 +543,147 @@ pub async fn get_asset_metadata(
     })
 }
 
+/// Gets the original filename of the asset as uploaded to Immich
+pub async fn get_original_file_name(
+    client: &PgClient,
+    asset_id: Uuid,
+) -> Result<Option<String>, ImageAnalysisError> {
+    let query = r#"SELECT "originalFileName" FROM asset WHERE id = $1"#;
+    let row = client.query_opt(query, &[&asset_id]).await.map_err(|err| {
+        ImageAnalysisError::DatabaseError {
+            error: format!("Failed to query original filename for asset {asset_id}: {err}"),
+        }
+    })?;
+    Ok(row.and_then(|found| found.get("originalFileName")))
+}
+
+/// Gets the original file path of an image asset, `None` for videos and other non-image assets
+pub async fn get_original_image_path(
+    client: &PgClient,
+    asset_id: Uuid,
+) -> Result<Option<String>, ImageAnalysisError> {
+    let query = r#"SELECT "originalPath" FROM asset WHERE id = $1 AND type::text = 'IMAGE'"#;
+    let row = client.query_opt(query, &[&asset_id]).await.map_err(|err| {
+        ImageAnalysisError::DatabaseError {
+            error: format!("Failed to query original path for asset {asset_id}: {err}"),
+        }
+    })?;
+    Ok(row.map(|found| found.get("originalPath")))
+}
+
+/// Gets the IDs of all image assets that are not in the trash
+pub async fn get_image_asset_ids(client: &PgClient) -> Result<Vec<Uuid>, ImageAnalysisError> {
+    let query = r#"SELECT id FROM asset WHERE type::text = 'IMAGE' AND "deletedAt" IS NULL"#;
+    let rows = client
+        .query(query, &[])
+        .await
+        .map_err(|err| ImageAnalysisError::DatabaseError {
+            error: format!("Failed to query image assets: {err}"),
+        })?;
+    Ok(rows.iter().map(|row| row.get("id")).collect())
+}
+
+/// Gets the IDs of archived or trashed assets with their `(archived, trashed)` status
+pub async fn get_archived_or_trashed_assets(
+    client: &PgClient,
+) -> Result<Vec<(Uuid, bool, bool)>, ImageAnalysisError> {
+    let query = r#"
+        SELECT id,
+            visibility::text = 'archive' AS archived,
+            ("deletedAt" IS NOT NULL OR status::text <> 'active') AS trashed
+        FROM asset
+        WHERE visibility::text = 'archive' OR "deletedAt" IS NOT NULL OR status::text <> 'active'
+    "#;
+    let rows = client
+        .query(query, &[])
+        .await
+        .map_err(|err| ImageAnalysisError::DatabaseError {
+            error: format!("Failed to query archived and trashed assets: {err}"),
+        })?;
+    Ok(rows
+        .iter()
+        .map(|row| (row.get("id"), row.get("archived"), row.get("trashed")))
+        .collect())
+}
+
+/// Gets the IDs of up to `limit` other images in the same stack as the asset, oldest first
+pub async fn get_stack_member_ids(
+    client: &PgClient,
+    asset_id: Uuid,
+    limit: usize,
+) -> Result<Vec<Uuid>, ImageAnalysisError> {
+    let query = r#"
+        SELECT other.id
+        FROM asset a
+        JOIN asset other ON other."stackId" = a."stackId" AND other.id <> a.id
+        WHERE a.id = $1
+        AND other.type::text = 'IMAGE'
+        AND other."deletedAt" IS NULL
+        ORDER BY other."fileCreatedAt"
+        LIMIT $2
+    "#;
+    let row_limit = i64::try_from(limit).unwrap_or(i64::MAX);
+    let rows = client
+        .query(query, &[&asset_id, &row_limit])
+        .await
+        .map_err(|err| ImageAnalysisError::DatabaseError {
+            error: format!("Failed to query stack of asset {asset_id}: {err}"),
+        })?;
+    Ok(rows.iter().map(|row| row.get("id")).collect())
+}
+
+/// Gets the names of up to `limit` named, visible people recognized in the asset
+pub async fn get_asset_people_names(
+    client: &PgClient,
+    asset_id: Uuid,
+    limit: usize,
+) -> Result<Vec<String>, ImageAnalysisError> {
+    let query = r#"
+        SELECT DISTINCT p.name
+        FROM person p
+        JOIN asset_face af ON af."personId" = p.id
+        WHERE af."assetId" = $1
+        AND p.name <> ''
+        AND NOT p."isHidden"
+        ORDER BY p.name
+        LIMIT $2
+    "#;
+    let row_limit = i64::try_from(limit).unwrap_or(i64::MAX);
+    let rows = client
+        .query(query, &[&asset_id, &row_limit])
+        .await
+        .map_err(|err| ImageAnalysisError::DatabaseError {
+            error: format!("Failed to query people for asset {asset_id}: {err}"),
+        })?;
+    Ok(rows.iter().map(|row| row.get("name")).collect())
+}
+
+/// Gets the names of albums containing the asset
+pub async fn get_asset_album_names(
+    client: &PgClient,
+    asset_id: Uuid,
+) -> Result<Vec<String>, ImageAnalysisError> {
+    let query = r#"
+        SELECT al."albumName"
+        FROM album al
+        JOIN album_asset aa ON aa."albumId" = al.id
+        WHERE aa."assetId" = $1
+        AND al."deletedAt" IS NULL
+        ORDER BY al."albumName"
+    "#;
+    let rows = client.query(query, &[&asset_id]).await.map_err(|err| {
+        ImageAnalysisError::DatabaseError {
+            error: format!("Failed to query albums for asset {asset_id}: {err}"),
+        }
+    })?;
+    Ok(rows.iter().map(|row| row.get("albumName")).collect())
+}
+
 pub async fn check_database_connection(client: &PgClient) -> Result<bool, ImageAnalysisError> {
     let timeout_duration = std::time::Duration::from_secs(5);
     match tokio::time::timeout(timeout_duration, client.query("SELECT 1", &[])).await {
         Ok(Ok(_)) => {
-            println!("{}", rust_i18n::t!("database.connection_success"));
+            outln!("{}", rust_i18n::t!("database.connection_success"));
             Ok(true)
         }
         Ok(Err(err)) => {
@@ -349,3 +706,219 @@ pub async fn check_database_connection(client: &PgClient) -> Result<bool, ImageA
         }
     }
 }
+
+/// Columns and privileges the connected role needs to store descriptions
+const DESCRIPTION_PRIVILEGES: [(&str, &str, &str); 4] = [
+    ("asset", "id", "SELECT"),
+    ("asset_exif", "description", "SELECT"),
+    ("asset_exif", "description", "INSERT"),
+    ("asset_exif", "description", "UPDATE"),
+];
+
+/// Columns and privileges the connected role needs to store format tags
+const TAG_PRIVILEGES: [(&str, &str, &str); 4] = [
+    ("asset", "ownerId", "SELECT"),
+    ("tag", "value", "INSERT"),
+    ("tag", "updatedAt", "UPDATE"),
+    ("tag_asset", "tagId", "INSERT"),
+];
+
+/// Columns and privileges the connected role needs to store descriptions in `asset_metadata`
+const METADATA_PRIVILEGES: [(&str, &str, &str); 4] = [
+    ("asset", "id", "SELECT"),
+    ("asset_metadata", "value", "SELECT"),
+    ("asset_metadata", "value", "INSERT"),
+    ("asset_metadata", "value", "UPDATE"),
+];
+
+/// Verifies that the connected role can read assets and write the configured output,
+/// and format tags as well with `with_format_tags` (`--extract-format-tag`).
+///
+/// Missing tables or columns and missing privileges are reported before any processing starts.
+pub async fn check_write_permissions(
+    client: &PgClient,
+    output: &AnalysisOutput,
+    with_format_tags: bool,
+) -> Result<(), ImageAnalysisError> {
+    let output_privileges = match output {
+        AnalysisOutput::Description => DESCRIPTION_PRIVILEGES,
+        AnalysisOutput::Tag { .. } => TAG_PRIVILEGES,
+        AnalysisOutput::Metadata => METADATA_PRIVILEGES,
+    };
+    let tag_privileges: &[(&str, &str, &str)] = if with_format_tags {
+        &TAG_PRIVILEGES
+    } else {
+        &[]
+    };
+    let required = output_privileges.iter().chain(tag_privileges);
+    let privilege_query = "
+        SELECT CASE
+            WHEN to_regclass($1::text) IS NULL THEN NULL
+            ELSE has_column_privilege($1::text, $2::text, $3::text)
+        END AS granted
+    ";
+    let mut missing = Vec::new();
+    for (table, column, privilege) in required {
+        let row = client
+            .query_one(privilege_query, &[&table, &column, &privilege])
+            .await
+            .map_err(|err| ImageAnalysisError::DatabaseError {
+                error: rust_i18n::t!(
+                    "error.database_schema_mismatch",
+                    table = table,
+                    column = column,
+                    error = err.to_string()
+                )
+                .to_string(),
+            })?;
+        match row.get::<_, Option<bool>>("granted") {
+            Some(true) => {}
+            Some(false) => missing.push(format!("{privilege} ({column}) ON {table}")),
+            None => {
+                return Err(ImageAnalysisError::DatabaseError {
+                    error: rust_i18n::t!(
+                        "error.database_schema_mismatch",
+                        table = table,
+                        column = column,
+                        error = "table does not exist"
+                    )
+                    .to_string(),
+                });
+            }
+        }
+    }
+    if missing.is_empty() {
+        outln!("{}", rust_i18n::t!("database.write_permissions_ok"));
+        Ok(())
+    } else {
+        Err(ImageAnalysisError::DatabaseError {
+            error: rust_i18n::t!(
+                "error.database_missing_privileges",
+                privileges = missing.join(", ")
+            )
+            .to_string(),
+        })
+    }
+}
+
+/// One batch run recorded by `--write-run-log`
+#[derive(Debug)]
+pub struct RunLogEntry {
+    pub started_at: SystemTime,
+    pub finished_at: SystemTime,
+    pub processed: u32,
+    pub failed: u32,
+    pub skipped: u32,
+    pub model: String,
+    pub hosts: Vec<String>,
+}
+
+/// Create the `immich_analyze_runs` table unless it already exists
+pub async fn ensure_run_log_table(client: &PgClient) -> Result<(), ImageAnalysisError> {
+    let query = "
+        CREATE TABLE IF NOT EXISTS immich_analyze_runs (
+            id BIGSERIAL PRIMARY KEY,
+            started_at TIMESTAMPTZ NOT NULL,
+            finished_at TIMESTAMPTZ NOT NULL,
+            processed INTEGER NOT NULL,
+            failed INTEGER NOT NULL,
+            skipped INTEGER NOT NULL,
+            model TEXT NOT NULL,
+            hosts TEXT[] NOT NULL
+        )
+    ";
+    client
+        .batch_execute(query)
+        .await
+        .map_err(|err| ImageAnalysisError::DatabaseError {
+            error: rust_i18n::t!("error.run_log_table_failed", error = err.to_string()).to_string(),
+        })
+}
+
+/// Insert one row into `immich_analyze_runs`
+pub async fn insert_run_log(
+    client: &PgClient,
+    run: &RunLogEntry,
+) -> Result<(), ImageAnalysisError> {
+    let query = "
+        INSERT INTO immich_analyze_runs
+            (started_at, finished_at, processed, failed, skipped, model, hosts)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+    ";
+    let to_int = |count: u32| i32::try_from(count).unwrap_or(i32::MAX);
+    client
+        .execute(
+            query,
+            &[
+                &run.started_at,
+                &run.finished_at,
+                &to_int(run.processed),
+                &to_int(run.failed),
+                &to_int(run.skipped),
+                &run.model,
+                &run.hosts,
+            ],
+        )
+        .await
+        .map_err(|err| ImageAnalysisError::DatabaseError {
+            error: format!("Failed to record run in immich_analyze_runs: {err}"),
+        })?;
+    debug!("Run recorded in immich_analyze_runs");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_postgres::NoTls;
+
+    /// Writes descriptions against the Immich database in `DATABASE_URL`.
+    ///
+    /// Only session-local temporary tables shadowing `asset` and `asset_exif` are touched,
+    /// so the real data is left alone; `immich_uuid_v7()` must exist in the database.
+    #[tokio::test]
+    #[ignore = "needs an Immich database in DATABASE_URL"]
+    async fn writes_description_without_exif_row() -> Result<(), Box<dyn std::error::Error>> {
+        let Ok(url) = std::env::var("DATABASE_URL") else {
+            eprintln!("DATABASE_URL is not set, skipping");
+            return Ok(());
+        };
+        let (client, connection) = tokio_postgres::connect(&url, NoTls).await?;
+        tokio::spawn(connection);
+        client
+            .batch_execute(
+                r#"
+                CREATE TEMP TABLE asset (id uuid PRIMARY KEY);
+                CREATE TEMP TABLE asset_exif (
+                    "assetId" uuid PRIMARY KEY REFERENCES asset (id),
+                    description text NOT NULL DEFAULT '',
+                    "updatedAt" timestamptz NOT NULL DEFAULT NOW(),
+                    "updateId" uuid NOT NULL
+                );
+                "#,
+            )
+            .await?;
+        let asset_id = Uuid::new_v4();
+        client
+            .execute("INSERT INTO asset (id) VALUES ($1)", &[&asset_id])
+            .await?;
+
+        update_or_create_asset_description(&client, asset_id, "A lighthouse at dusk").await?;
+        assert_eq!(
+            get_asset_description(&client, asset_id).await?.as_deref(),
+            Some("A lighthouse at dusk")
+        );
+        update_or_create_asset_description(&client, asset_id, "A lighthouse at night").await?;
+        assert_eq!(
+            get_asset_description(&client, asset_id).await?.as_deref(),
+            Some("A lighthouse at night")
+        );
+
+        let unknown = update_or_create_asset_description(&client, Uuid::new_v4(), "Nothing").await;
+        assert!(matches!(
+            unknown,
+            Err(ImageAnalysisError::DatabaseError { .. })
+        ));
+        Ok(())
+    }
+}
@@ -5,22 +5,55 @@ use uuid::Uuid;
 pub enum ImageAnalysisError {
     #[error("Empty file: {filename}")]
     EmptyFile { filename: String },
+    #[error("File too large ({size} bytes): {filename}")]
+    FileTooLarge { filename: String, size: u64 },
+    #[error("Image too small ({width}x{height}): {filename}")]
+    TooSmall {
+        filename: String,
+        width: u32,
+        height: u32,
+    },
+    #[error("Corrupt image: {filename}")]
+    CorruptImage { filename: String },
+    #[error("Not a JPEG, PNG or WebP image: {filename}")]
+    UnsupportedFormat { filename: String },
+    #[error("No preview and original is not a decodable image: {filename}")]
+    UnsupportedOriginal { filename: String },
     #[error("HTTP error {status} for {filename}: {response}")]
     HttpError {
         status: u16,
         filename: String,
         response: String,
     },
+    #[error("Model context length exceeded for {filename}: {response}")]
+    ContextLengthExceeded { filename: String, response: String },
+    #[error("Request too large (HTTP 413) for {filename}: {response}")]
+    PayloadTooLarge { filename: String, response: String },
     #[error("Empty response for {filename}")]
     EmptyResponse { filename: String },
-    #[error("JSON parsing error for {filename}: {error}")]
-    JsonParsing { filename: String, error: String },
+    #[error("Response too short ({length} chars) for {filename}")]
+    ShortResponse { filename: String, length: usize },
+    #[error("Model refused to describe {filename} (matched \"{phrase}\")")]
+    RefusalResponse { filename: String, phrase: String },
+    #[error("JSON parsing error for {filename}: {error} (response: \"{snippet}\")")]
+    JsonParsing {
+        filename: String,
+        error: String,
+        /// Start of the offending response body, see `utils::response_snippet`
+        snippet: String,
+    },
     #[error("File write timeout {timeout}s for {filename}")]
     FileWriteTimeout { timeout: u64, filename: String },
     #[error("Processing error for {filename}: {error}")]
     ProcessingError { filename: String, error: String },
+    #[error("Failed {attempts} times before, skipping: {filename}")]
+    RepeatedFailure { filename: String, attempts: u32 },
+    #[error("Same description returned more than {threshold} times in a row: {filename}")]
+    SuspiciousDuplicate { filename: String, threshold: usize },
     #[error("Already processed: {filename}")]
     AlreadyProcessed { filename: String },
+    #[error("Only previewed, nothing written: {filename}")]
+    Previewed { filename: String },
     #[error("Database error: {error}")]
     DatabaseError { error: String },
     #[error("Invalid UUID in filename: {filename}")]
@@ -31,6 +64,8 @@ pub enum ImageAnalysisError {
     AllHostsUnavailable,
     #[error("AI service request timeout")]
     AiRequestTimeout,
+    #[error("No response from AI service within {timeout}s")]
+    ResponseStartTimeout { timeout: u64 },
     #[error("Invalid API key")]
     InvalidApiKey,
     #[error("Invalid configuration: {error}")]
@@ -41,6 +76,14 @@ pub enum ImageAnalysisError {
     IoError { path: String, error: String },
     #[error("Asset not found: {asset_id}")]
     AssetNotFound { asset_id: Uuid },
+    #[error("Hosts unreachable: {hosts}")]
+    HostsUnreachable { hosts: String },
+    #[error("Model {model} not found on {host} (available: {available})")]
+    ModelNotFound {
+        model: String,
+        host: String,
+        available: String,
+    },
 }
 
 impl ImageAnalysisError {
@@ -51,6 +94,32 @@ impl ImageAnalysisError {
             Self::EmptyFile { filename } => {
                 rust_i18n::t!("error.empty_file", filename = filename).to_string()
             }
+            Self::CorruptImage { filename } => {
+                rust_i18n::t!("error.corrupt_image", filename = filename).to_string()
+            }
+            Self::UnsupportedFormat { filename } => {
+                rust_i18n::t!("error.unsupported_format", filename = filename).to_string()
+            }
+            Self::UnsupportedOriginal { filename } => {
+                rust_i18n::t!("error.unsupported_original", filename = filename).to_string()
+            }
+            Self::FileTooLarge { filename, size } => rust_i18n::t!(
+                "error.file_too_large",
+                filename = filename,
+                size = size.to_string()
+            )
+            .to_string(),
+            Self::TooSmall {
+                filename,
+                width,
+                height,
+            } => rust_i18n::t!(
+                "error.too_small",
+                filename = filename,
+                width = width.to_string(),
+                height = height.to_string()
+            )
+            .to_string(),
             Self::HttpError {
                 status,
                 filename,
@@ -62,13 +131,36 @@ impl ImageAnalysisError {
                 response = response
             )
             .to_string(),
+            Self::ContextLengthExceeded { filename, .. } => {
+                rust_i18n::t!("error.context_length_exceeded", filename = filename).to_string()
+            }
+            Self::PayloadTooLarge { filename, .. } => {
+                rust_i18n::t!("error.payload_too_large", filename = filename).to_string()
+            }
             Self::EmptyResponse { filename } => {
                 rust_i18n::t!("error.empty_response", filename = filename).to_string()
             }
-            Self::JsonParsing { filename, error } => rust_i18n::t!(
+            Self::ShortResponse { filename, length } => rust_i18n::t!(
+                "error.short_response",
+                filename = filename,
+                length = length.to_string()
+            )
+            .to_string(),
+            Self::RefusalResponse { filename, phrase } => rust_i18n::t!(
+                "error.refusal_response",
+                filename = filename,
+                phrase = phrase
+            )
+            .to_string(),
+            Self::JsonParsing {
+                filename,
+                error,
+                snippet,
+            } => rust_i18n::t!(
                 "error.json_parsing_with_details",
                 filename = filename,
-                error = error
+                error = error,
+                snippet = snippet
             )
             .to_string(),
             Self::FileWriteTimeout { filename, timeout } => rust_i18n::t!(
@@ -77,11 +169,31 @@ impl ImageAnalysisError {
                 timeout = timeout.to_string()
             )
             .to_string(),
+            Self::RepeatedFailure { filename, attempts } => rust_i18n::t!(
+                "error.repeated_failure",
+                filename = filename,
+                attempts = attempts.to_string()
+            )
+            .to_string(),
+            Self::SuspiciousDuplicate {
+                filename,
+                threshold,
+            } => rust_i18n::t!(
+                "error.suspicious_duplicate",
+                filename = filename,
+                threshold = threshold.to_string()
+            )
+            .to_string(),
             Self::DatabaseError { error } => {
                 rust_i18n::t!("error.database_error", error = error).to_string()
             }
             Self::AllHostsUnavailable => rust_i18n::t!("error.all_hosts_unavailable").to_string(),
             Self::AiRequestTimeout => rust_i18n::t!("error.ai_request_timeout").to_string(),
+            Self::ResponseStartTimeout { timeout } => rust_i18n::t!(
+                "error.response_start_timeout",
+                timeout = timeout.to_string()
+            )
+            .to_string(),
             Self::ProcessingError { filename, error } => format!(
                 "{}\n{}",
                 error,
@@ -90,6 +202,9 @@ impl ImageAnalysisError {
             Self::AlreadyProcessed { filename } => {
                 rust_i18n::t!("main.file_already_in_database", filename = filename).to_string()
             }
+            Self::Previewed { filename } => {
+                rust_i18n::t!("main.file_previewed", filename = filename).to_string()
+            }
             Self::InvalidUuid { filename } => format!(
                 "{}\n{}",
                 rust_i18n::t!("error.critical_processing_error", filename = filename),
@@ -113,6 +228,115 @@ impl ImageAnalysisError {
             Self::AssetNotFound { asset_id } => {
                 rust_i18n::t!("database.asset_not_in_table", asset_id = asset_id).to_string()
             }
+            Self::HostsUnreachable { hosts } => {
+                rust_i18n::t!("error.hosts_unreachable", hosts = hosts).to_string()
+            }
+            Self::ModelNotFound {
+                model,
+                host,
+                available,
+            } => rust_i18n::t!(
+                "error.model_not_found",
+                model = model,
+                host = host,
+                available = available
+            )
+            .to_string(),
+        }
+    }
+
+    /// Check if this error means the file was intentionally skipped rather than failed
+    #[must_use]
+    pub const fn is_skipped(&self) -> bool {
+        matches!(
+            self,
+            Self::AlreadyProcessed { .. }
+                | Self::Previewed { .. }
+                | Self::InvalidUuid { .. }
+                | Self::AssetNotFound { .. }
+                | Self::FileTooLarge { .. }
+                | Self::TooSmall { .. }
+                | Self::CorruptImage { .. }
+                | Self::UnsupportedFormat { .. }
+                | Self::UnsupportedOriginal { .. }
+                | Self::RepeatedFailure { .. }
+                | Self::SuspiciousDuplicate { .. }
+        )
+    }
+
+    /// Check if this error points at a broken setup rather than a bad file (`--fail-fast`)
+    #[must_use]
+    pub const fn is_fatal(&self) -> bool {
+        match self {
+            Self::HttpError { status, .. } => *status >= 500 && *status <= 599,
+            Self::AllHostsUnavailable | Self::InvalidApiKey | Self::DatabaseError { .. } => true,
+            Self::EmptyFile { .. }
+            | Self::FileTooLarge { .. }
+            | Self::TooSmall { .. }
+            | Self::CorruptImage { .. }
+            | Self::UnsupportedFormat { .. }
+            | Self::UnsupportedOriginal { .. }
+            | Self::ContextLengthExceeded { .. }
+            | Self::PayloadTooLarge { .. }
+            | Self::EmptyResponse { .. }
+            | Self::ShortResponse { .. }
+            | Self::RefusalResponse { .. }
+            | Self::JsonParsing { .. }
+            | Self::FileWriteTimeout { .. }
+            | Self::ProcessingError { .. }
+            | Self::RepeatedFailure { .. }
+            | Self::SuspiciousDuplicate { .. }
+            | Self::AlreadyProcessed { .. }
+            | Self::Previewed { .. }
+            | Self::InvalidUuid { .. }
+            | Self::InvalidImmichStructure { .. }
+            | Self::AiRequestTimeout
+            | Self::ResponseStartTimeout { .. }
+            | Self::InvalidConfig { .. }
+            | Self::HttpClientError { .. }
+            | Self::IoError { .. }
+            | Self::AssetNotFound { .. }
+            | Self::HostsUnreachable { .. }
+            | Self::ModelNotFound { .. } => false,
+        }
+    }
+
+    /// Returns the variant name, used to group failures in the final statistics
+    #[must_use]
+    pub const fn variant_name(&self) -> &'static str {
+        match self {
+            Self::EmptyFile { .. } => "EmptyFile",
+            Self::FileTooLarge { .. } => "FileTooLarge",
+            Self::TooSmall { .. } => "TooSmall",
+            Self::CorruptImage { .. } => "CorruptImage",
+            Self::UnsupportedFormat { .. } => "UnsupportedFormat",
+            Self::UnsupportedOriginal { .. } => "UnsupportedOriginal",
+            Self::HttpError { .. } => "HttpError",
+            Self::ContextLengthExceeded { .. } => "ContextLengthExceeded",
+            Self::PayloadTooLarge { .. } => "PayloadTooLarge",
+            Self::EmptyResponse { .. } => "EmptyResponse",
+            Self::ShortResponse { .. } => "ShortResponse",
+            Self::RefusalResponse { .. } => "RefusalResponse",
+            Self::JsonParsing { .. } => "JsonParsing",
+            Self::FileWriteTimeout { .. } => "FileWriteTimeout",
+            Self::ProcessingError { .. } => "ProcessingError",
+            Self::RepeatedFailure { .. } => "RepeatedFailure",
+            Self::SuspiciousDuplicate { .. } => "SuspiciousDuplicate",
+            Self::AlreadyProcessed { .. } => "AlreadyProcessed",
+            Self::Previewed { .. } => "Previewed",
+            Self::DatabaseError { .. } => "DatabaseError",
+            Self::InvalidUuid { .. } => "InvalidUuid",
+            Self::InvalidImmichStructure { .. } => "InvalidImmichStructure",
+            Self::AllHostsUnavailable => "AllHostsUnavailable",
+            Self::AiRequestTimeout => "AiRequestTimeout",
+            Self::ResponseStartTimeout { .. } => "ResponseStartTimeout",
+            Self::InvalidApiKey => "InvalidApiKey",
+            Self::InvalidConfig { .. } => "InvalidConfig",
+            Self::HttpClientError { .. } => "HttpClientError",
+            Self::IoError { .. } => "IoError",
+            Self::AssetNotFound { .. } => "AssetNotFound",
+            Self::HostsUnreachable { .. } => "HostsUnreachable",
+            Self::ModelNotFound { .. } => "ModelNotFound",
         }
     }
 
@@ -124,24 +348,39 @@ impl ImageAnalysisError {
             Self::HttpError { status, .. } => {
                 *status == 0 || (*status >= 500 && *status <= 599) || *status == 429
             }
-            Self::AllHostsUnavailable | Self::AiRequestTimeout | Self::HttpClientError { .. } => {
-                true
-            }
+            Self::AllHostsUnavailable
+            | Self::AiRequestTimeout
+            | Self::ResponseStartTimeout { .. }
+            | Self::HttpClientError { .. } => true,
 
             // Non-retryable errors
             Self::EmptyFile { .. }
+            | Self::FileTooLarge { .. }
+            | Self::TooSmall { .. }
+            | Self::CorruptImage { .. }
+            | Self::UnsupportedFormat { .. }
+            | Self::UnsupportedOriginal { .. }
             | Self::InvalidUuid { .. }
             | Self::InvalidImmichStructure { .. }
             | Self::InvalidApiKey
             | Self::InvalidConfig { .. }
+            | Self::ContextLengthExceeded { .. }
+            | Self::PayloadTooLarge { .. }
             | Self::EmptyResponse { .. }
+            | Self::ShortResponse { .. }
+            | Self::RefusalResponse { .. }
             | Self::JsonParsing { .. }
             | Self::AlreadyProcessed { .. }
+            | Self::Previewed { .. }
+            | Self::RepeatedFailure { .. }
+            | Self::SuspiciousDuplicate { .. }
             | Self::DatabaseError { .. }
             | Self::ProcessingError { .. }
             | Self::FileWriteTimeout { .. }
             | Self::IoError { .. }
-            | Self::AssetNotFound { .. } => false,
+            | Self::AssetNotFound { .. }
+            | Self::HostsUnreachable { .. }
+            | Self::ModelNotFound { .. } => false,
         }
     }
 }
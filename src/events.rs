@@ -0,0 +1,97 @@
+use crate::{database::ImageAnalysisResult, error::ImageAnalysisError};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use uuid::Uuid;
+
+static JSON_OUTPUT: AtomicBool = AtomicBool::new(false);
+
+/// Emit lifecycle events as JSON lines on stdout (set by `--output-format json`).
+pub fn set_json_output(json: bool) {
+    JSON_OUTPUT.store(json, Ordering::Relaxed);
+}
+
+pub fn json_output() -> bool {
+    JSON_OUTPUT.load(Ordering::Relaxed)
+}
+
+/// `println!` for human-readable messages.
+///
+/// Goes to stderr while stdout carries JSON events, so consumers only ever see event lines there.
+macro_rules! outln {
+    ($($arg:tt)*) => {
+        if $crate::events::json_output() {
+            eprintln!($($arg)*);
+        } else {
+            println!($($arg)*);
+        }
+    };
+}
+pub(crate) use outln;
+
+/// One line of the JSON event stream; `event` carries the snake case variant name.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    Started {
+        mode: &'a str,
+    },
+    FileProcessing {
+        filename: &'a str,
+    },
+    FileDone {
+        filename: &'a str,
+        asset_id: Uuid,
+        description: &'a str,
+    },
+    FileFailed {
+        filename: &'a str,
+        /// Stable error kind, see `ImageAnalysisError::variant_name`
+        error: &'static str,
+        /// Localized error message
+        message: String,
+        /// Whether the file was skipped rather than failed
+        skipped: bool,
+    },
+    Summary {
+        successful: u32,
+        failed: u32,
+        skipped: u32,
+    },
+}
+
+impl Event<'_> {
+    /// Print the event as a single JSON line; does nothing unless JSON output is enabled.
+    pub fn emit(&self) {
+        if !json_output() {
+            return;
+        }
+        match serde_json::to_string(self) {
+            Ok(line) => println!("{line}"),
+            Err(err) => log::error!("Failed to serialize event: {err}"),
+        }
+    }
+}
+
+/// Emit `file_done` or `file_failed` for a finished file.
+pub fn emit_result(filename: &str, result: &Result<ImageAnalysisResult, ImageAnalysisError>) {
+    match result {
+        Ok(analysis) => Event::FileDone {
+            filename,
+            asset_id: analysis.asset_id,
+            description: &analysis.description,
+        }
+        .emit(),
+        Err(err) => emit_failure(filename, err),
+    }
+}
+
+/// Emit `file_failed` for a file that was skipped or could not be processed.
+pub fn emit_failure(filename: &str, error: &ImageAnalysisError) {
+    Event::FileFailed {
+        filename,
+        error: error.variant_name(),
+        message: error.user_message(),
+        skipped: error.is_skipped(),
+    }
+    .emit();
+}
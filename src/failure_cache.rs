@@ -0,0 +1,148 @@
+use crate::error::ImageAnalysisError;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+/// Failed assets persisted as JSON so they are not retried forever across runs.
+#[derive(Debug)]
+pub struct FailureCache {
+    path: PathBuf,
+    max_attempts: u32,
+    cooldown: Duration,
+    entries: Mutex<HashMap<Uuid, FailureEntry>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FailureEntry {
+    attempts: u32,
+    last_error: String,
+    /// Unix timestamp in seconds
+    last_attempt: u64,
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+impl FailureCache {
+    /// Load the failure cache from `path`, or start empty when `clear` is set or the file is missing.
+    pub async fn load(
+        path: &str,
+        max_attempts: u32,
+        cooldown: Duration,
+        clear: bool,
+    ) -> Result<Self, ImageAnalysisError> {
+        let entries = if clear {
+            HashMap::new()
+        } else {
+            match tokio::fs::read_to_string(path).await {
+                Ok(content) => {
+                    serde_json::from_str(&content).map_err(|err| ImageAnalysisError::IoError {
+                        path: path.to_owned(),
+                        error: err.to_string(),
+                    })?
+                }
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+                Err(err) => {
+                    return Err(ImageAnalysisError::IoError {
+                        path: path.to_owned(),
+                        error: err.to_string(),
+                    });
+                }
+            }
+        };
+        let cache = Self {
+            path: PathBuf::from(path),
+            max_attempts,
+            cooldown,
+            entries: Mutex::new(entries),
+        };
+        if clear {
+            cache.persist(&HashMap::new()).await;
+        }
+        Ok(cache)
+    }
+
+    /// Number of assets currently recorded as failed.
+    pub async fn len(&self) -> usize {
+        self.entries.lock().await.len()
+    }
+
+    /// Skip an asset that already failed `--max-attempts` times within the cooldown.
+    pub async fn check(&self, asset_id: &Uuid, filename: &str) -> Result<(), ImageAnalysisError> {
+        if self.max_attempts == 0 {
+            return Ok(());
+        }
+        let entries = self.entries.lock().await;
+        let Some(entry) = entries.get(asset_id) else {
+            return Ok(());
+        };
+        let since_last = unix_now().saturating_sub(entry.last_attempt);
+        if entry.attempts >= self.max_attempts && since_last < self.cooldown.as_secs() {
+            return Err(ImageAnalysisError::RepeatedFailure {
+                filename: filename.to_owned(),
+                attempts: entry.attempts,
+            });
+        }
+        drop(entries);
+        Ok(())
+    }
+
+    /// Record the outcome of processing an asset, where `error` is `None` on success.
+    ///
+    /// Successes clear the asset's entry; failures caused by the asset itself increment it.
+    /// Skips and errors where no host could be reached are not counted.
+    pub async fn record(&self, asset_id: Uuid, error: Option<&ImageAnalysisError>) {
+        let mut entries = self.entries.lock().await;
+        match error {
+            None => {
+                if entries.remove(&asset_id).is_none() {
+                    return;
+                }
+            }
+            Some(err)
+                if err.is_skipped() || matches!(err, ImageAnalysisError::AllHostsUnavailable) =>
+            {
+                return;
+            }
+            Some(err) => {
+                let entry = entries.entry(asset_id).or_insert_with(|| FailureEntry {
+                    attempts: 0,
+                    last_error: String::new(),
+                    last_attempt: 0,
+                });
+                entry.attempts = entry.attempts.saturating_add(1);
+                entry.last_error = err.to_string();
+                entry.last_attempt = unix_now();
+                debug!(
+                    "Recorded failure {} for asset {asset_id}: {err}",
+                    entry.attempts
+                );
+            }
+        }
+        // Written while holding the lock so concurrent updates can't overwrite newer state
+        self.persist(&entries).await;
+        drop(entries);
+    }
+
+    async fn persist(&self, entries: &HashMap<Uuid, FailureEntry>) {
+        let result = match serde_json::to_string_pretty(entries) {
+            Ok(content) => tokio::fs::write(&self.path, content).await,
+            Err(err) => Err(std::io::Error::other(err)),
+        };
+        if let Err(err) = result {
+            warn!(
+                "Failed to write failure cache {}: {err}",
+                self.path.display()
+            );
+        }
+    }
+}
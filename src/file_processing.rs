@@ -1,36 +1,56 @@
 use crate::{
-    config::ProcessingContext,
-    data_access::DataAccess,
+    args::{CoverageTarget, SortBy},
+    config::{ProcessingContext, RunState},
+    data_access::{DataAccess, ExcludedAssets},
     database::ImageAnalysisResult,
     error::ImageAnalysisError,
+    events::{self, Event, outln},
     health::mark_activity,
     host_manager::HostManager,
     immich_api::AssetRef,
-    progress::SimpleProgress,
-    prompt_enricher::enrich_prompt_if_needed,
+    progress::{SimpleProgress, Status},
+    prompt_enricher::prepare_prompt,
     utils::{
-        OverwriteDecision, build_final_description, check_overwrite_policy,
-        extract_uuid_from_preview_filename, filename_from_path, is_preview_filename,
+        OverwriteDecision, check_overwrite_policy, cleanup_related_previews,
+        extract_asset_id_from_preview_path, filename_from_path, is_preview_filename,
+        related_previews, store_analysis_result,
     },
 };
-use futures::stream::{self, StreamExt as _};
-use log::{error, warn};
-use reqwest::Client;
+use futures::{
+    channel::mpsc::{self, UnboundedReceiver},
+    stream::{self, Stream, StreamExt as _},
+};
+use log::{debug, error, info, warn};
+use rand::{rngs::StdRng, seq::SliceRandom as _};
 use std::{
-    num::NonZeroU32,
+    cmp::Reverse,
+    collections::{HashMap, HashSet, VecDeque},
     path::{Path, PathBuf},
+    pin::pin,
     sync::Arc,
     time::Duration,
 };
-use tokio::sync::Mutex;
+use tokio::{
+    sync::{Mutex, Semaphore},
+    task::JoinHandle,
+};
+use uuid::Uuid;
+
+/// Number of directories read concurrently while scanning the thumbs tree.
+const DIR_WALK_CONCURRENCY: usize = 32;
 
-/// Get all preview image files from Immich thumbs directory.
+/// Maximum number of assets checked for existing descriptions in one query.
+const DESCRIBED_CHECK_CHUNK_SIZE: usize = 1000;
+
+/// Walk the Immich thumbs directory `thumbs_dir`, passing each preview image file with one
+/// of the `preview_extensions` to `on_found` as soon as it is discovered.
 ///
 /// This function is used in database mode to scan the filesystem for preview files.
-pub async fn get_immich_preview_files(
-    immich_root: &Path,
-) -> Result<Vec<PathBuf>, ImageAnalysisError> {
-    let thumbs_dir = immich_root.join("thumbs");
+pub async fn walk_immich_preview_files(
+    thumbs_dir: &Path,
+    preview_extensions: &[String],
+    mut on_found: impl FnMut(PathBuf),
+) -> Result<(), ImageAnalysisError> {
     if !thumbs_dir.exists() {
         return Err(ImageAnalysisError::InvalidImmichStructure {
             error: rust_i18n::t!(
@@ -49,195 +69,881 @@ pub async fn get_immich_preview_files(
             .to_string(),
         });
     }
-    let mut preview_files = Vec::new();
-    let mut stack = vec![thumbs_dir];
-    while let Some(current_dir) = stack.pop() {
-        match tokio::fs::read_dir(&current_dir).await {
-            Ok(mut entries) => {
-                while let Ok(Some(entry)) = entries.next_entry().await {
-                    let path = entry.path();
-                    if path.is_dir() {
-                        stack.push(path);
-                    } else if path.is_file()
-                        && let Some(filename) = path.file_name().and_then(|name| name.to_str())
-                        && is_preview_filename(filename)
-                    {
-                        preview_files.push(path);
+    let mut pending_dirs = vec![thumbs_dir.to_path_buf()];
+    // Walk the tree level by level, reading several directories at once so
+    // large libraries are not bottlenecked on one sequential read_dir at a time
+    while !pending_dirs.is_empty() {
+        let mut listings = stream::iter(
+            std::mem::take(&mut pending_dirs)
+                .into_iter()
+                .map(|dir| read_preview_dir(dir, preview_extensions)),
+        )
+        .buffer_unordered(DIR_WALK_CONCURRENCY);
+        let mut next_dirs = Vec::new();
+        while let Some((subdirs, files)) = listings.next().await {
+            next_dirs.extend(subdirs);
+            files.into_iter().for_each(&mut on_found);
+        }
+        pending_dirs = next_dirs;
+    }
+    Ok(())
+}
+
+/// Discover assets in the background, growing the progress total as they are found.
+///
+/// Returns the receiving end to feed into `process_files_concurrently` and a handle
+/// resolving to the discovery counts once the scan has finished.
+/// With `shuffle_rng`, assets are held back until the scan finishes and then sent in
+/// random order, and with `sort_by` in that order, starting at `resume_from` if it is among
+/// them. With `covered_by`, assets that already have a description or a smart search
+/// embedding are dropped in batches before they reach processing; with `resume_from` only
+/// after the resume position is found, since the resume asset itself is usually described.
+pub fn spawn_asset_discovery(
+    data_access: DataAccess,
+    progress: Arc<Mutex<SimpleProgress>>,
+    shuffle_rng: Option<StdRng>,
+    sort_by: Option<SortBy>,
+    resume_from: Option<Uuid>,
+    covered_by: Option<CoverageTarget>,
+) -> (
+    UnboundedReceiver<AssetRef>,
+    JoinHandle<Result<DiscoveryStats, ImageAnalysisError>>,
+) {
+    let (asset_sender, asset_receiver) = mpsc::unbounded();
+    let handle = tokio::spawn(async move {
+        let (found_sender, found_receiver) = mpsc::unbounded();
+        let forward = async {
+            let mut stats = DiscoveryStats::default();
+            let mut held_back = Vec::new();
+            // The resume asset is usually described already, so it has to be found first
+            let filter_during_walk = resume_from.is_none() || sort_by.is_none();
+            let mut chunks = found_receiver.ready_chunks(DESCRIBED_CHECK_CHUNK_SIZE);
+            while let Some(found) = chunks.next().await {
+                let chunk = if filter_during_walk {
+                    drop_covered(&data_access, found, covered_by, &mut stats).await
+                } else {
+                    found
+                };
+                for asset in chunk {
+                    progress.lock().await.inc_total();
+                    stats.queued = stats.queued.saturating_add(1);
+                    if shuffle_rng.is_some() || sort_by.is_some() {
+                        held_back.push(asset);
+                    } else if asset_sender.unbounded_send(asset).is_err() {
+                        break;
                     }
                 }
             }
-            Err(err) => {
-                error!("Error reading directory {}: {}", current_dir.display(), err);
+            if let Some(mut rng) = shuffle_rng {
+                held_back.shuffle(&mut rng);
+            } else if let Some(order) = sort_by {
+                held_back = sort_assets(held_back, order).await;
+                if let Some(resume_id) = resume_from {
+                    let left_out = resume_at(&mut held_back, resume_id);
+                    let sorted = std::mem::take(&mut held_back);
+                    let resumed = sorted.len();
+                    let mut sorted_chunks = stream::iter(sorted).chunks(DESCRIBED_CHECK_CHUNK_SIZE);
+                    while let Some(chunk) = sorted_chunks.next().await {
+                        held_back.extend(
+                            drop_covered(&data_access, chunk, covered_by, &mut stats).await,
+                        );
+                    }
+                    let dropped =
+                        u64::try_from(resumed.saturating_sub(held_back.len())).unwrap_or(u64::MAX);
+                    let removed = left_out.saturating_add(dropped);
+                    stats.queued = stats.queued.saturating_sub(removed);
+                    progress.lock().await.sub_total(removed);
+                }
+            }
+            for asset in held_back {
+                if asset_sender.unbounded_send(asset).is_err() {
+                    break;
+                }
             }
+            stats
+        };
+        let (result, stats) = tokio::join!(data_access.discover_assets(found_sender), forward);
+        progress.lock().await.finish_discovery();
+        result.map(|excluded| DiscoveryStats { excluded, ..stats })
+    });
+    (asset_receiver, handle)
+}
+
+/// Order discovered assets for `--sort-by`.
+///
+/// Size and modification time come from the preview files found by discovery; assets
+/// without one (originals used as fallback, unreadable files) go last.
+async fn sort_assets(assets: Vec<AssetRef>, sort_by: SortBy) -> Vec<AssetRef> {
+    if sort_by == SortBy::Name {
+        let mut sorted = assets;
+        sorted.sort_by_cached_key(|asset| {
+            asset
+                .preview_path
+                .as_deref()
+                .map(filename_from_path)
+                .ok_or(asset.id)
+        });
+        return sorted;
+    }
+    let mut with_metadata: Vec<(AssetRef, Option<std::fs::Metadata>)> = stream::iter(assets)
+        .map(|asset| async move {
+            let metadata = match &asset.preview_path {
+                Some(path) => tokio::fs::metadata(path).await.ok(),
+                None => None,
+            };
+            (asset, metadata)
+        })
+        .buffered(DIR_WALK_CONCURRENCY)
+        .collect()
+        .await;
+    match sort_by {
+        SortBy::Size => {
+            with_metadata.sort_by_key(|(_, metadata)| {
+                metadata.as_ref().map_or(u64::MAX, std::fs::Metadata::len)
+            });
         }
+        SortBy::MtimeAsc => {
+            with_metadata.sort_by_key(|(_, metadata)| {
+                let modified = metadata.as_ref().and_then(|meta| meta.modified().ok());
+                (modified.is_none(), modified)
+            });
+        }
+        SortBy::MtimeDesc => {
+            with_metadata.sort_by_key(|(_, metadata)| {
+                let modified = metadata.as_ref().and_then(|meta| meta.modified().ok());
+                (modified.is_none(), Reverse(modified))
+            });
+        }
+        // Sorted above without reading metadata
+        SortBy::Name => {}
     }
-    Ok(preview_files)
+    with_metadata.into_iter().map(|(asset, _)| asset).collect()
+}
+
+/// Drop the sorted assets before `resume_id` (`--resume-from-uuid`), returning how many.
+///
+/// Keeps every asset when `resume_id` is not among them.
+fn resume_at(sorted: &mut Vec<AssetRef>, resume_id: Uuid) -> u64 {
+    let Some(position) = sorted.iter().position(|asset| asset.id == resume_id) else {
+        outln!(
+            "{}",
+            rust_i18n::t!("main.resume_uuid_not_found", uuid = resume_id.to_string())
+        );
+        return 0;
+    };
+    sorted.drain(..position);
+    outln!(
+        "{}",
+        rust_i18n::t!(
+            "main.resume_from",
+            uuid = resume_id.to_string(),
+            count = position.to_string()
+        )
+    );
+    u64::try_from(position).unwrap_or(u64::MAX)
+}
+
+/// Queue exactly the given assets (`--only-asset`) instead of discovering them.
+///
+/// Returns the same pair as `spawn_asset_discovery`.
+pub fn spawn_listed_assets(
+    asset_ids: &[Uuid],
+    progress: Arc<Mutex<SimpleProgress>>,
+) -> (
+    UnboundedReceiver<AssetRef>,
+    JoinHandle<Result<DiscoveryStats, ImageAnalysisError>>,
+) {
+    let (asset_sender, asset_receiver) = mpsc::unbounded();
+    let listed = asset_ids.to_vec();
+    let handle = tokio::spawn(async move {
+        let mut stats = DiscoveryStats::default();
+        for asset_id in listed {
+            progress.lock().await.inc_total();
+            stats.queued = stats.queued.saturating_add(1);
+            if asset_sender
+                .unbounded_send(AssetRef {
+                    id: asset_id,
+                    preview_path: None,
+                })
+                .is_err()
+            {
+                break;
+            }
+        }
+        progress.lock().await.finish_discovery();
+        Ok(stats)
+    });
+    (asset_receiver, handle)
+}
+
+/// Asset counts reported once discovery has finished.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DiscoveryStats {
+    /// Assets sent on for processing
+    pub queued: u64,
+    /// Assets dropped up front because they already have a description
+    pub already_described: u64,
+    /// Assets dropped up front because they already have a smart search embedding
+    pub already_indexed: u64,
+    /// Archived and trashed assets left out by `--skip-archived` and `--skip-trashed`
+    pub excluded: ExcludedAssets,
+}
+
+/// Drop the assets in `chunk` already covered by `covered_by`, counting them in `stats`.
+async fn drop_covered(
+    data_access: &DataAccess,
+    chunk: Vec<AssetRef>,
+    covered_by: Option<CoverageTarget>,
+    stats: &mut DiscoveryStats,
+) -> Vec<AssetRef> {
+    match covered_by {
+        Some(CoverageTarget::Description) => {
+            let (undescribed, described) = drop_described(data_access, chunk).await;
+            stats.already_described = stats.already_described.saturating_add(described);
+            undescribed
+        }
+        Some(CoverageTarget::SmartSearch) => {
+            let (unindexed, indexed) = drop_indexed(data_access, chunk).await;
+            stats.already_indexed = stats.already_indexed.saturating_add(indexed);
+            unindexed
+        }
+        None => chunk,
+    }
+}
+
+/// Drop assets that already have a description, checking the whole chunk in one query.
+///
+/// On error the chunk is returned unchanged and the per-asset check decides instead.
+async fn drop_described(data_access: &DataAccess, chunk: Vec<AssetRef>) -> (Vec<AssetRef>, u64) {
+    let ids: Vec<Uuid> = chunk.iter().map(|asset| asset.id).collect();
+    match data_access.get_assets_with_descriptions(&ids).await {
+        Ok(described) => drop_listed(chunk, &described),
+        Err(err) => {
+            warn!("Batched description check failed, checking assets one by one: {err}");
+            (chunk, 0)
+        }
+    }
+}
+
+/// Drop assets that already have a smart search embedding (`--coverage-target smart-search`).
+///
+/// On error the chunk is returned unchanged, so no asset is left out by mistake.
+async fn drop_indexed(data_access: &DataAccess, chunk: Vec<AssetRef>) -> (Vec<AssetRef>, u64) {
+    let ids: Vec<Uuid> = chunk.iter().map(|asset| asset.id).collect();
+    match data_access.get_assets_with_smart_search(&ids).await {
+        Ok(indexed) => drop_listed(chunk, &indexed),
+        Err(err) => {
+            warn!("Smart search check failed, keeping the whole chunk: {err}");
+            (chunk, 0)
+        }
+    }
+}
+
+/// Remove the assets in `listed` from `chunk`, returning the rest and how many were removed.
+fn drop_listed(chunk: Vec<AssetRef>, listed: &HashSet<Uuid>) -> (Vec<AssetRef>, u64) {
+    let before = chunk.len();
+    let kept: Vec<AssetRef> = chunk
+        .into_iter()
+        .filter(|asset| !listed.contains(&asset.id))
+        .collect();
+    let dropped = before.saturating_sub(kept.len());
+    (kept, u64::try_from(dropped).unwrap_or(u64::MAX))
+}
+
+/// Read a single directory, returning its subdirectories and preview files.
+async fn read_preview_dir(
+    dir: PathBuf,
+    preview_extensions: &[String],
+) -> (Vec<PathBuf>, Vec<PathBuf>) {
+    let mut subdirs = Vec::new();
+    let mut preview_files = Vec::new();
+    let mut entries = match tokio::fs::read_dir(&dir).await {
+        Ok(entries) => entries,
+        Err(err) => {
+            error!("Error reading directory {}: {}", dir.display(), err);
+            return (subdirs, preview_files);
+        }
+    };
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let Ok(file_type) = entry.file_type().await else {
+            continue;
+        };
+        let path = entry.path();
+        if file_type.is_dir() {
+            subdirs.push(path);
+        } else if file_type.is_file()
+            && let Some(filename) = path.file_name().and_then(|name| name.to_str())
+            && is_preview_filename(filename, preview_extensions)
+        {
+            preview_files.push(path);
+        }
+    }
+    (subdirs, preview_files)
 }
 
 async fn process_file_with_existing_check(
     ctx: &ProcessingContext<'_>,
     path: &Path,
+    duplicates: Option<&DuplicateGuard>,
 ) -> Result<ImageAnalysisResult, ImageAnalysisError> {
     let filename = filename_from_path(path);
-    let asset_id = extract_uuid_from_preview_filename(&filename)?;
+    let asset_id = extract_asset_id_from_preview_path(path)?;
+    if let Some(failure_cache) = ctx.failure_cache {
+        failure_cache.check(&asset_id, &filename).await?;
+    }
 
-    match check_overwrite_policy(ctx.data_access, &asset_id, ctx.overwrite_policy).await? {
+    let result = match check_overwrite_policy(
+        ctx.data_access,
+        &asset_id,
+        ctx.overwrite_policy,
+        ctx.analyzer.options(),
+    )
+    .await?
+    {
         OverwriteDecision::Skip => Err(ImageAnalysisError::AlreadyProcessed { filename }),
-        OverwriteDecision::AnalyzeFresh => process_file(ctx, path, None).await,
-        OverwriteDecision::PreserveExisting(desc) => process_file(ctx, path, Some(desc)).await,
+        OverwriteDecision::AnalyzeFresh => process_file(ctx, path, None, duplicates).await,
+        OverwriteDecision::PreserveExisting(desc) => {
+            process_file(ctx, path, Some(desc), duplicates).await
+        }
+    };
+    if let Some(failure_cache) = ctx.failure_cache {
+        failure_cache.record(asset_id, result.as_ref().err()).await;
     }
+    result
 }
 
 async fn process_file(
     ctx: &ProcessingContext<'_>,
     path: &Path,
     existing_description: Option<String>,
+    duplicates: Option<&DuplicateGuard>,
 ) -> Result<ImageAnalysisResult, ImageAnalysisError> {
     let data_access = ctx.data_access;
 
-    let filename = filename_from_path(path);
-
-    let asset_id = extract_uuid_from_preview_filename(&filename)?;
+    let asset_id = extract_asset_id_from_preview_path(path)?;
 
     let preview_path = data_access.get_preview_path(&asset_id).await?;
-    let final_prompt = enrich_prompt_if_needed(ctx, &asset_id)
-        .await
-        .unwrap_or_else(|| ctx.prompt.to_owned());
+    let final_prompt = prepare_prompt(ctx, &asset_id).await;
+    let related_paths = related_previews(ctx, &asset_id).await;
 
-    let analysis = ctx
-        .host_manager
-        .analyze_image(&preview_path, &final_prompt)
-        .await?;
+    let result = ctx
+        .analyzer
+        .analyze(&preview_path, &related_paths, &final_prompt)
+        .await;
+    cleanup_related_previews(data_access, &related_paths).await;
+    let analysis = result?;
 
     if let Err(err) = data_access.cleanup_preview(&preview_path).await {
         warn!("Failed to cleanup preview: {err}");
     }
 
-    let final_description = build_final_description(
-        &analysis,
-        data_access,
-        ctx.preserve_human,
-        existing_description,
-        ctx.disable_ai_wrapper,
-    )
-    .await?;
-
-    data_access
-        .update_description(&analysis.asset_id, &final_description)
-        .await?;
+    if let Some(guard) = duplicates {
+        guard.check(&analysis.description, &filename_from_path(path))?;
+    }
+    let stored = store_analysis_result(ctx, &analysis, existing_description).await?;
+    if let Some(post_hook) = ctx.post_hook {
+        post_hook.run(analysis.asset_id, &filename_from_path(path), &stored);
+    }
 
     Ok(analysis)
 }
 
 pub async fn process_files_concurrently(
-    assets: Vec<AssetRef>,
-    http_client: &Client,
+    assets: impl Stream<Item = AssetRef>,
+    host_manager: &Arc<HostManager>,
     data_access: &DataAccess,
+    run_state: &RunState,
     args: &crate::args::Args,
     locale: &str,
     progress: Arc<Mutex<SimpleProgress>>,
 ) -> Vec<(String, Result<ImageAnalysisResult, ImageAnalysisError>)> {
-    // Create host manager once for all files to preserve unavailable host state
-    let unavailable_duration = Duration::from_secs(args.unavailable_duration);
-
-    let host_manager = Arc::new(HostManager::new(
-        args.hosts.clone(),
-        args.interface,
-        http_client.clone(),
-        args.model_name.clone(),
-        args.timeout,
-        NonZeroU32::new(args.max_retries),
-        Duration::from_secs(args.retry_delay_seconds),
-        unavailable_duration,
-        args.api_key.clone(),
-    ));
-
-    stream::iter(assets.into_iter().map(|asset| {
-        let prompt = args.prompt.clone();
-        let progress_clone = Arc::clone(&progress);
-        let lang = locale.to_owned();
-        let overwrite_policy = args.effective_overwrite_policy();
-        let asset_id = asset.id;
-        let host_manager_clone = Arc::clone(&host_manager);
-
-        async move {
-            rust_i18n::set_locale(&lang);
-            mark_activity();
-            let preview_path = match data_access.get_preview_path(&asset_id).await {
-                Ok(preview_path) => preview_path,
-                Err(err) => {
-                    let filename = asset_id.to_string();
-                    progress_clone
-                        .lock()
-                        .await
-                        .set_message(&rust_i18n::t!("progress.error", filename = filename));
-
-                    progress_clone
-                        .lock()
-                        .await
-                        .set_message_and_inc(&rust_i18n::t!("progress.error", filename = filename));
-
-                    return (filename, Err(err));
-                }
-            };
-            let filename = filename_from_path(&preview_path);
-            progress_clone
-                .lock()
-                .await
-                .set_message(&rust_i18n::t!("progress.processing", filename = filename));
-
-            let ctx = ProcessingContext::new(
-                data_access,
-                &prompt,
-                &host_manager_clone,
-                overwrite_policy,
-                args.enrich_prompt,
-                args.preserve_human,
-                args.disable_ai_wrapper,
-            );
+    let base_prompt = args.effective_prompt();
+    let ramp = args
+        .ramp_duration
+        .filter(|_| args.max_concurrent > 1)
+        .map(|duration| spawn_concurrency_ramp(args.max_concurrent, duration));
+    let adaptive = (args.concurrency_auto && args.max_concurrent > 1).then(|| {
+        Arc::new(AdaptiveConcurrency::new(
+            args.min_concurrent,
+            args.max_concurrent,
+        ))
+    });
+    let duplicates = args.reject_duplicate_threshold.map(DuplicateGuard::new);
+    let throttle = args
+        .error_rate_threshold
+        .filter(|_| args.max_concurrent > 1)
+        .map(|threshold| {
+            Arc::new(ErrorThrottle::new(
+                args.max_concurrent,
+                threshold,
+                args.throttle_cooldown,
+            ))
+        });
+    let ramp_gate = ramp.as_ref().map(|(gate, _)| Arc::clone(gate)).or_else(|| {
+        adaptive
+            .as_ref()
+            .map(|controller| Arc::clone(&controller.gate))
+    });
 
-            let result = process_file_with_existing_check(&ctx, &preview_path).await;
-            match &result {
-                Err(
-                    ImageAnalysisError::AlreadyProcessed { .. }
-                    | ImageAnalysisError::InvalidUuid { .. }
-                    | ImageAnalysisError::AssetNotFound { .. },
-                ) => {
-                    progress_clone
-                        .lock()
-                        .await
-                        .set_message_and_dec_total(&rust_i18n::t!(
-                            "progress.skipped",
-                            filename = filename
-                        ));
-                }
-                _ => {
-                    progress_clone
-                        .lock()
-                        .await
-                        .set_message_and_inc(&rust_i18n::t!(
-                            "progress.finished",
+    let mut processed =
+        pin!(
+            assets
+                .map(|asset| {
+                    let ramp_gate_clone = ramp_gate.clone();
+                    let duplicates_ref = duplicates.as_ref();
+                    let adaptive_clone = adaptive.clone();
+                    let throttle_clone = throttle.clone();
+                    let prompt = base_prompt.clone();
+                    let progress_clone = Arc::clone(&progress);
+                    let lang = locale.to_owned();
+                    let overwrite_policy = args.effective_overwrite_policy();
+                    let asset_id = asset.id;
+                    let host_manager_clone = Arc::clone(host_manager);
+
+                    async move {
+                        // Held for the whole file, so the ramp or controller bounds files in flight
+                        let _gate_permit = match &ramp_gate_clone {
+                            Some(gate) => gate.acquire().await.ok(),
+                            None => None,
+                        };
+                        let _throttle_permit = match &throttle_clone {
+                            Some(throttle) => throttle.gate.acquire().await.ok(),
+                            None => None,
+                        };
+                        run_state.wait_while_paused().await;
+                        let started = tokio::time::Instant::now();
+                        rust_i18n::set_locale(&lang);
+                        mark_activity();
+                        let preview_path = match data_access.get_preview_path(&asset_id).await {
+                            Ok(preview_path) => preview_path,
+                            Err(err) => {
+                                let filename = asset_id.to_string();
+                                // An asset gone since discovery is skipped, not failed
+                                if err.is_skipped() {
+                                    progress_clone.lock().await.set_message_and_dec_total(
+                                        &rust_i18n::t!("progress.skipped", filename = filename),
+                                    );
+                                } else {
+                                    progress_clone.lock().await.set_message_and_inc(
+                                        &rust_i18n::t!("progress.error", filename = filename),
+                                    );
+                                }
+
+                                events::emit_failure(&filename, &err);
+                                return (filename, Err(err));
+                            }
+                        };
+                        let filename = filename_from_path(&preview_path);
+                        progress_clone.lock().await.set_message(&rust_i18n::t!(
+                            "progress.processing",
                             filename = filename
                         ));
+                        Event::FileProcessing {
+                            filename: &filename,
+                        }
+                        .emit();
+
+                        let ctx = ProcessingContext::new(
+                            data_access,
+                            &prompt,
+                            &*host_manager_clone,
+                            overwrite_policy,
+                            args.enrich_prompt,
+                            args.preserve_human,
+                            args.disable_ai_wrapper,
+                        )
+                        .with_run_state(run_state);
+
+                        let result =
+                            process_file_with_existing_check(&ctx, &preview_path, duplicates_ref)
+                                .await;
+                        if let Some(controller) = &adaptive_clone {
+                            controller.record(started.elapsed(), &result);
+                        }
+                        if let Some(throttle) = &throttle_clone {
+                            throttle.record(&result);
+                        }
+                        match &result {
+                            Err(err) if err.is_skipped() => {
+                                progress_clone.lock().await.set_message_and_dec_total(
+                                    &rust_i18n::t!("progress.skipped", filename = filename),
+                                );
+                            }
+                            _ => {
+                                progress_clone
+                                    .lock()
+                                    .await
+                                    .set_message_and_inc(&rust_i18n::t!(
+                                        "progress.finished",
+                                        filename = filename
+                                    ));
+                            }
+                        }
+                        events::emit_result(&filename, &result);
+                        (filename, result)
+                    }
+                })
+                .buffer_unordered(args.max_concurrent)
+        );
+    let mut results = Vec::new();
+    while let Some((filename, result)) = processed.next().await {
+        let fatal = args.fail_fast && result.as_ref().is_err_and(ImageAnalysisError::is_fatal);
+        if fatal {
+            eprintln!(
+                "{}",
+                rust_i18n::t!("error.fail_fast_stopped", filename = filename)
+            );
+        }
+        results.push((filename, result));
+        if fatal {
+            // Dropping the stream cancels in-flight files and stops discovery
+            break;
+        }
+    }
+    if let Some((_, ramp_handle)) = ramp {
+        ramp_handle.abort();
+    }
+    results
+}
+
+/// Gate that lets one file through at first and adds a slot at even intervals until
+/// `max_concurrent` files may run at once after `duration` (`--ramp-duration`).
+fn spawn_concurrency_ramp(
+    max_concurrent: usize,
+    duration: Duration,
+) -> (Arc<Semaphore>, JoinHandle<()>) {
+    outln!(
+        "{}",
+        rust_i18n::t!(
+            "main.concurrency_ramp",
+            count = max_concurrent.to_string(),
+            seconds = duration.as_secs().to_string()
+        )
+    );
+    let gate = Arc::new(Semaphore::new(1));
+    let steps = max_concurrent.saturating_sub(1);
+    let interval = duration
+        .checked_div(u32::try_from(steps).unwrap_or(u32::MAX))
+        .unwrap_or_default();
+    let ramp_gate = Arc::clone(&gate);
+    let handle = tokio::spawn(async move {
+        for slots in 2..=max_concurrent {
+            tokio::time::sleep(interval).await;
+            ramp_gate.add_permits(1);
+            debug!("Concurrency ramp: {slots}/{max_concurrent} files at once");
+        }
+    });
+    (gate, handle)
+}
+
+/// Files that must complete before `--concurrency-auto` re-evaluates, at least.
+const ADAPTIVE_MIN_WINDOW: usize = 4;
+/// More than one overload error in this many files halves the concurrency.
+const ADAPTIVE_FAILURE_RATIO: usize = 10;
+
+/// AIMD controller for `--concurrency-auto`: adds one slot after every healthy window of files
+/// and halves the slots when the median latency exceeds 1.5 times the best median seen, or
+/// when too many requests time out or hit unavailable or overloaded hosts.
+struct AdaptiveConcurrency {
+    gate: Arc<Semaphore>,
+    min: usize,
+    max: usize,
+    window: std::sync::Mutex<AdaptiveWindow>,
+}
+
+#[derive(Default)]
+struct AdaptiveWindow {
+    limit: usize,
+    latencies: Vec<Duration>,
+    failures: usize,
+    /// Lowest median latency of any window, the reference for "healthy"
+    baseline: Option<Duration>,
+    /// Slots still to remove after a decrease, taken as files in flight return their permits
+    excess: usize,
+}
+
+impl AdaptiveConcurrency {
+    fn new(min_concurrent: usize, max_concurrent: usize) -> Self {
+        let min = min_concurrent.clamp(1, max_concurrent);
+        outln!(
+            "{}",
+            rust_i18n::t!(
+                "main.concurrency_auto",
+                min = min.to_string(),
+                max = max_concurrent.to_string()
+            )
+        );
+        Self {
+            gate: Arc::new(Semaphore::new(min)),
+            min,
+            max: max_concurrent,
+            window: std::sync::Mutex::new(AdaptiveWindow {
+                limit: min,
+                ..AdaptiveWindow::default()
+            }),
+        }
+    }
+
+    /// Add a finished file to the window and adjust the slots once it is full.
+    ///
+    /// Only successes and overload errors count; skipped files never reached the model.
+    fn record(&self, latency: Duration, result: &Result<ImageAnalysisResult, ImageAnalysisError>) {
+        let mut window = self
+            .window
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        match result {
+            Ok(_) => window.latencies.push(latency),
+            Err(err) if err.is_retryable() => window.failures = window.failures.saturating_add(1),
+            Err(_) => {}
+        }
+        let samples = window.latencies.len().saturating_add(window.failures);
+        if samples >= window.limit.max(ADAPTIVE_MIN_WINDOW) {
+            self.adjust(&mut window, samples);
+        }
+        if window.excess > 0 {
+            let forgotten = self.gate.forget_permits(window.excess);
+            window.excess = window.excess.saturating_sub(forgotten);
+        }
+    }
+
+    fn adjust(&self, window: &mut AdaptiveWindow, samples: usize) {
+        window.latencies.sort_unstable();
+        let median = window.latencies.get(window.latencies.len() / 2).copied();
+        let failing = window.failures.saturating_mul(ADAPTIVE_FAILURE_RATIO) > samples;
+        let slow = median
+            .zip(window.baseline)
+            .is_some_and(|(current, best)| current.saturating_mul(2) > best.saturating_mul(3));
+        if let Some(current) = median {
+            window.baseline = Some(window.baseline.map_or(current, |best| best.min(current)));
+        }
+        let previous = window.limit;
+        if failing || slow {
+            window.limit = (previous / 2).max(self.min);
+            let removed = previous.saturating_sub(window.limit);
+            let forgotten = self.gate.forget_permits(removed);
+            window.excess = window
+                .excess
+                .saturating_add(removed.saturating_sub(forgotten));
+        } else if previous < self.max {
+            window.limit = previous.saturating_add(1);
+            if window.excess > 0 {
+                window.excess = window.excess.saturating_sub(1);
+            } else {
+                self.gate.add_permits(1);
+            }
+        }
+        if window.limit != previous {
+            info!(
+                "Adaptive concurrency: {previous} -> {} files at once (median latency {median:?}, {} of {samples} failed)",
+                window.limit, window.failures
+            );
+        }
+        window.latencies.clear();
+        window.failures = 0;
+    }
+}
+
+/// Analyzed files the rolling error rate of `--error-rate-threshold` is computed over.
+const ERROR_RATE_WINDOW: usize = 20;
+/// Files that must complete before the error rate is trusted.
+const ERROR_RATE_MIN_SAMPLES: usize = 10;
+
+/// Circuit breaker for `--error-rate-threshold`: halves the files in flight while too many
+/// recent files fail, and restores them once a `--throttle-cooldown` passes without the error
+/// rate exceeding the threshold again.
+struct ErrorThrottle {
+    gate: Arc<Semaphore>,
+    full: usize,
+    threshold: u8,
+    cooldown: Duration,
+    state: std::sync::Mutex<ThrottleState>,
+}
+
+#[derive(Default)]
+struct ThrottleState {
+    /// Latest outcomes, oldest first; `true` for a failed file
+    outcomes: VecDeque<bool>,
+    /// Set while throttled: when the error rate is checked again
+    recheck_at: Option<tokio::time::Instant>,
+    /// Slots still to remove after throttling, taken as files in flight return their permits
+    excess: usize,
+}
+
+impl ErrorThrottle {
+    fn new(max_concurrent: usize, threshold: u8, cooldown: Duration) -> Self {
+        Self {
+            gate: Arc::new(Semaphore::new(max_concurrent)),
+            full: max_concurrent,
+            threshold,
+            cooldown,
+            state: std::sync::Mutex::new(ThrottleState::default()),
+        }
+    }
+
+    /// Slots taken away while throttled, half of them rounded down.
+    const fn reduction(&self) -> usize {
+        self.full / 2
+    }
+
+    /// Add a finished file to the rolling window, engaging or releasing the throttle.
+    ///
+    /// Skipped files never reached the model and are left out.
+    fn record(&self, result: &Result<ImageAnalysisResult, ImageAnalysisError>) {
+        let failed = match result {
+            Ok(_) => false,
+            Err(err) if err.is_skipped() => return,
+            Err(_) => true,
+        };
+        let mut state = self
+            .state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        state.outcomes.push_back(failed);
+        if state.outcomes.len() > ERROR_RATE_WINDOW {
+            state.outcomes.pop_front();
+        }
+        let samples = state.outcomes.len();
+        let failures = state.outcomes.iter().filter(|failed| **failed).count();
+        let over_threshold =
+            failures.saturating_mul(100) > usize::from(self.threshold).saturating_mul(samples);
+        let now = tokio::time::Instant::now();
+        let next_check = now.checked_add(self.cooldown).unwrap_or(now);
+        match state.recheck_at {
+            None if over_threshold && samples >= ERROR_RATE_MIN_SAMPLES => {
+                let removed = self.reduction();
+                let forgotten = self.gate.forget_permits(removed);
+                state.excess = removed.saturating_sub(forgotten);
+                state.recheck_at = Some(next_check);
+                // The recovery is judged on files started after throttling
+                state.outcomes.clear();
+                outln!(
+                    "{}",
+                    rust_i18n::t!(
+                        "main.error_throttle_engaged",
+                        failures = failures.to_string(),
+                        samples = samples.to_string(),
+                        count = self.full.saturating_sub(removed).to_string(),
+                        seconds = self.cooldown.as_secs().to_string()
+                    )
+                );
+            }
+            Some(recheck_at) if now >= recheck_at => {
+                if over_threshold {
+                    state.recheck_at = Some(next_check);
+                } else {
+                    self.gate
+                        .add_permits(self.reduction().saturating_sub(state.excess));
+                    state.excess = 0;
+                    state.recheck_at = None;
+                    outln!(
+                        "{}",
+                        rust_i18n::t!(
+                            "main.error_throttle_released",
+                            count = self.full.to_string()
+                        )
+                    );
                 }
             }
-            (filename, result)
+            None | Some(_) => {}
+        }
+        if state.excess > 0 {
+            let forgotten = self.gate.forget_permits(state.excess);
+            state.excess = state.excess.saturating_sub(forgotten);
         }
-    }))
-    .buffer_unordered(args.max_concurrent)
-    .collect::<Vec<_>>()
-    .await
+    }
+}
+
+/// Recent descriptions for `--reject-duplicate-threshold`, to catch a model that is stuck
+/// returning the same text for every image.
+struct DuplicateGuard {
+    threshold: usize,
+    /// The last `threshold` descriptions, oldest first
+    recent: std::sync::Mutex<VecDeque<String>>,
+}
+
+impl DuplicateGuard {
+    fn new(threshold: usize) -> Self {
+        Self {
+            threshold,
+            recent: std::sync::Mutex::new(VecDeque::with_capacity(threshold)),
+        }
+    }
+
+    /// Fails once the same description comes back more than `threshold` times in a row.
+    fn check(&self, description: &str, filename: &str) -> Result<(), ImageAnalysisError> {
+        let trimmed = description.trim();
+        let mut recent = self
+            .recent
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let repeated =
+            recent.len() == self.threshold && recent.iter().all(|prior| prior == trimmed);
+        if recent.len() == self.threshold {
+            recent.pop_front();
+        }
+        recent.push_back(trimmed.to_owned());
+        drop(recent);
+        if repeated {
+            return Err(ImageAnalysisError::SuspiciousDuplicate {
+                filename: filename.to_owned(),
+                threshold: self.threshold,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Outcome counts for a finished batch, used for notifications and exit status.
+#[derive(Debug, Default)]
+pub struct BatchSummary {
+    pub successful: u32,
+    pub failed: u32,
+    pub skipped: u32,
+    pub failed_files: Vec<String>,
+}
+
+impl BatchSummary {
+    /// Failed files as a percentage of the files that were not skipped.
+    #[must_use]
+    pub fn failed_percent(&self) -> f64 {
+        let attempted = self.successful.saturating_add(self.failed);
+        if attempted == 0 {
+            return 0.0;
+        }
+        f64::from(self.failed) * 100.0 / f64::from(attempted)
+    }
+}
+
+#[must_use]
+pub fn summarize_results(
+    results: &[(String, Result<ImageAnalysisResult, ImageAnalysisError>)],
+) -> BatchSummary {
+    let mut summary = BatchSummary::default();
+    for (filename, result) in results {
+        match result {
+            Ok(_) => summary.successful = summary.successful.saturating_add(1),
+            Err(err) if err.is_skipped() => summary.skipped = summary.skipped.saturating_add(1),
+            Err(_) => {
+                summary.failed = summary.failed.saturating_add(1);
+                summary.failed_files.push(filename.clone());
+            }
+        }
+    }
+    summary
 }
 
 pub fn display_results(
     results: &[(String, Result<ImageAnalysisResult, ImageAnalysisError>)],
     use_sorting: bool,
 ) {
-    println!("{}", rust_i18n::t!("main.analysis_results"));
-    println!("{}", "-".repeat(31));
+    outln!("{}", rust_i18n::t!("main.analysis_results"));
+    outln!("{}", "-".repeat(31));
     let mut successful = 0_u32;
     let mut failed = 0_u32;
     let mut skipped = 0_u32;
+    let mut breakdown = SkipBreakdown::default();
+    let mut failure_kinds: HashMap<&'static str, u32> = HashMap::new();
     let mut output_lines = Vec::new();
     for (filename, result) in results {
         match result {
@@ -245,7 +951,7 @@ pub fn display_results(
                 successful = successful.saturating_add(1);
                 output_lines.push(format!(
                     "{} [{}] {}\n{}",
-                    rust_i18n::t!("status.success"),
+                    Status::Success.marker(),
                     filename,
                     analysis.description,
                     "-".repeat(80)
@@ -255,8 +961,28 @@ pub fn display_results(
                 let (count_increment, line) = handle_error_result(filename, err);
                 match count_increment {
                     "success" => successful = successful.saturating_add(1),
-                    "failed" => failed = failed.saturating_add(1),
+                    "failed" => {
+                        failed = failed.saturating_add(1);
+                        let kind_count = failure_kinds.entry(err.variant_name()).or_default();
+                        *kind_count = kind_count.saturating_add(1);
+                    }
                     "skipped" => skipped = skipped.saturating_add(1),
+                    "too_large" => {
+                        skipped = skipped.saturating_add(1);
+                        breakdown.too_large = breakdown.too_large.saturating_add(1);
+                    }
+                    "too_small" => {
+                        skipped = skipped.saturating_add(1);
+                        breakdown.too_small = breakdown.too_small.saturating_add(1);
+                    }
+                    "corrupt" => {
+                        skipped = skipped.saturating_add(1);
+                        breakdown.corrupt = breakdown.corrupt.saturating_add(1);
+                    }
+                    "repeated" => {
+                        skipped = skipped.saturating_add(1);
+                        breakdown.repeated = breakdown.repeated.saturating_add(1);
+                    }
                     _ => {}
                 }
                 output_lines.push(line);
@@ -267,9 +993,48 @@ pub fn display_results(
         output_lines.sort();
     }
     for line in output_lines {
-        println!("{line}");
+        outln!("{line}");
     }
-    print_statistics(successful, failed, skipped);
+    print_statistics(successful, failed, skipped, &breakdown, &failure_kinds);
+}
+
+/// Skipped files counted by reason for the final statistics.
+#[derive(Debug, Default)]
+struct SkipBreakdown {
+    too_large: u32,
+    too_small: u32,
+    corrupt: u32,
+    repeated: u32,
+}
+
+/// Writes successful results as `asset_id,filename,description` rows with a header.
+pub async fn write_results_csv(
+    results: &[(String, Result<ImageAnalysisResult, ImageAnalysisError>)],
+    path: &str,
+) -> Result<(), ImageAnalysisError> {
+    let io_error = |error: &dyn std::fmt::Display| ImageAnalysisError::IoError {
+        path: path.to_owned(),
+        error: error.to_string(),
+    };
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer
+        .write_record(["asset_id", "filename", "description"])
+        .map_err(|err| io_error(&err))?;
+    for (filename, result) in results {
+        if let Ok(analysis) = result {
+            writer
+                .write_record([
+                    analysis.asset_id.to_string().as_str(),
+                    filename,
+                    analysis.description.as_str(),
+                ])
+                .map_err(|err| io_error(&err))?;
+        }
+    }
+    let data = writer.into_inner().map_err(|err| io_error(&err))?;
+    tokio::fs::write(path, data)
+        .await
+        .map_err(|err| io_error(&err))
 }
 
 fn handle_error_result(filename: &str, error: &ImageAnalysisError) -> (&'static str, String) {
@@ -278,7 +1043,7 @@ fn handle_error_result(filename: &str, error: &ImageAnalysisError) -> (&'static
             "skipped",
             format!(
                 "{} [{}] {}\n{}",
-                rust_i18n::t!("status.skipped"),
+                Status::Skipped.marker(),
                 filename,
                 rust_i18n::t!("main.file_already_in_database", filename = filename),
                 "-".repeat(80)
@@ -288,7 +1053,7 @@ fn handle_error_result(filename: &str, error: &ImageAnalysisError) -> (&'static
             "skipped",
             format!(
                 "{} [{}] {}\n{}",
-                rust_i18n::t!("status.skipped"),
+                Status::Skipped.marker(),
                 filename,
                 rust_i18n::t!("error.invalid_uuid_filename", filename = filename),
                 "-".repeat(80)
@@ -298,17 +1063,92 @@ fn handle_error_result(filename: &str, error: &ImageAnalysisError) -> (&'static
             "skipped",
             format!(
                 "{} [{}] {}\n{}",
-                rust_i18n::t!("status.skipped"),
+                Status::Skipped.marker(),
                 filename,
                 rust_i18n::t!("database.asset_not_in_table", asset_id = asset_id),
                 "-".repeat(80)
             ),
         ),
+        ImageAnalysisError::FileTooLarge { .. } => (
+            "too_large",
+            format!(
+                "{} [{}] {}\n{}",
+                Status::Skipped.marker(),
+                filename,
+                error.user_message(),
+                "-".repeat(80)
+            ),
+        ),
+        ImageAnalysisError::TooSmall { .. } => (
+            "too_small",
+            format!(
+                "{} [{}] {}\n{}",
+                Status::Skipped.marker(),
+                filename,
+                error.user_message(),
+                "-".repeat(80)
+            ),
+        ),
+        ImageAnalysisError::CorruptImage { .. } => (
+            "corrupt",
+            format!(
+                "{} [{}] {}\n{}",
+                Status::Skipped.marker(),
+                filename,
+                error.user_message(),
+                "-".repeat(80)
+            ),
+        ),
+        ImageAnalysisError::UnsupportedFormat { .. }
+        | ImageAnalysisError::UnsupportedOriginal { .. }
+        | ImageAnalysisError::SuspiciousDuplicate { .. }
+        | ImageAnalysisError::Previewed { .. } => (
+            "skipped",
+            format!(
+                "{} [{}] {}\n{}",
+                Status::Skipped.marker(),
+                filename,
+                error.user_message(),
+                "-".repeat(80)
+            ),
+        ),
+        ImageAnalysisError::ContextLengthExceeded { .. } => (
+            "failed",
+            format!(
+                "{} [{}] {}\n   {}\n{}",
+                Status::Error.marker(),
+                filename,
+                error.user_message(),
+                rust_i18n::t!("recommendation.reduce_context"),
+                "-".repeat(80)
+            ),
+        ),
+        ImageAnalysisError::PayloadTooLarge { .. } => (
+            "failed",
+            format!(
+                "{} [{}] {}\n   {}\n{}",
+                Status::Error.marker(),
+                filename,
+                error.user_message(),
+                rust_i18n::t!("recommendation.reduce_image_size"),
+                "-".repeat(80)
+            ),
+        ),
+        ImageAnalysisError::RepeatedFailure { .. } => (
+            "repeated",
+            format!(
+                "{} [{}] {}\n{}",
+                Status::Skipped.marker(),
+                filename,
+                error.user_message(),
+                "-".repeat(80)
+            ),
+        ),
         ImageAnalysisError::InvalidImmichStructure { error } => (
             "failed",
             format!(
                 "{} [{}] {}\n{}",
-                rust_i18n::t!("status.error"),
+                Status::Error.marker(),
                 filename,
                 rust_i18n::t!("error.invalid_immich_structure", error = error),
                 "-".repeat(80)
@@ -318,7 +1158,7 @@ fn handle_error_result(filename: &str, error: &ImageAnalysisError) -> (&'static
             "failed",
             format!(
                 "{} [{}] {}\n{}",
-                rust_i18n::t!("status.error"),
+                Status::Error.marker(),
                 filename,
                 error.user_message(),
                 "-".repeat(80)
@@ -327,47 +1167,244 @@ fn handle_error_result(filename: &str, error: &ImageAnalysisError) -> (&'static
     }
 }
 
-fn print_statistics(successful: u32, failed: u32, skipped: u32) {
+fn print_statistics(
+    successful: u32,
+    failed: u32,
+    skipped: u32,
+    breakdown: &SkipBreakdown,
+    failure_kinds: &HashMap<&'static str, u32>,
+) {
     #[expect(clippy::arithmetic_side_effects)]
     let total = u64::from(successful) + u64::from(failed) + u64::from(skipped);
-    println!("{}", rust_i18n::t!("main.statistics"));
-    println!(
+    outln!("{}", rust_i18n::t!("main.statistics"));
+    outln!(
         "{}",
         rust_i18n::t!("main.successful", count = successful.to_string())
     );
-    println!(
+    outln!(
         "{}",
         rust_i18n::t!("main.failed", count = failed.to_string())
     );
+    print_failure_breakdown(failure_kinds);
     if skipped > 0 {
-        println!(
+        outln!(
             "{}",
             rust_i18n::t!("main.skipped", count = skipped.to_string())
         );
     }
-    println!(
+    if breakdown.too_large > 0 {
+        outln!(
+            "{}",
+            rust_i18n::t!(
+                "main.skipped_too_large",
+                count = breakdown.too_large.to_string()
+            )
+        );
+    }
+    if breakdown.too_small > 0 {
+        outln!(
+            "{}",
+            rust_i18n::t!(
+                "main.skipped_too_small",
+                count = breakdown.too_small.to_string()
+            )
+        );
+    }
+    if breakdown.corrupt > 0 {
+        outln!(
+            "{}",
+            rust_i18n::t!(
+                "main.skipped_corrupt",
+                count = breakdown.corrupt.to_string()
+            )
+        );
+    }
+    if breakdown.repeated > 0 {
+        outln!(
+            "{}",
+            rust_i18n::t!(
+                "main.skipped_repeated",
+                count = breakdown.repeated.to_string()
+            )
+        );
+    }
+    outln!(
         "{}",
         rust_i18n::t!("main.total_processed", count = total.to_string())
     );
-    println!("{}", rust_i18n::t!("main.database_updates_complete"));
+    outln!("{}", rust_i18n::t!("main.database_updates_complete"));
     if failed > 0 {
         print_error_recommendations();
     }
 }
 
+/// Print failure counts per error variant, most frequent first.
+fn print_failure_breakdown(failure_kinds: &HashMap<&'static str, u32>) {
+    if failure_kinds.is_empty() {
+        return;
+    }
+    let mut breakdown: Vec<(&str, u32)> = failure_kinds
+        .iter()
+        .map(|(kind, count)| (*kind, *count))
+        .collect();
+    breakdown.sort_by(|left, right| right.1.cmp(&left.1).then_with(|| left.0.cmp(right.0)));
+    outln!("{}", rust_i18n::t!("main.failure_breakdown"));
+    for (kind, count) in breakdown {
+        outln!(
+            "{}",
+            rust_i18n::t!(
+                "main.failure_breakdown_entry",
+                kind = kind,
+                count = count.to_string()
+            )
+        );
+    }
+}
+
 fn print_error_recommendations() {
-    println!("{}", rust_i18n::t!("main.error_recommendations"));
-    println!("• {}", rust_i18n::t!("recommendation.check_service_status"));
-    println!("• {}", rust_i18n::t!("recommendation.check_file_sizes"));
-    println!("• {}", rust_i18n::t!("recommendation.reduce_concurrency"));
-    println!("• {}", rust_i18n::t!("recommendation.use_monitor_mode"));
-    println!(
+    outln!("{}", rust_i18n::t!("main.error_recommendations"));
+    outln!("• {}", rust_i18n::t!("recommendation.check_service_status"));
+    outln!("• {}", rust_i18n::t!("recommendation.check_file_sizes"));
+    outln!("• {}", rust_i18n::t!("recommendation.reduce_concurrency"));
+    outln!("• {}", rust_i18n::t!("recommendation.use_monitor_mode"));
+    outln!(
         "• {}",
         rust_i18n::t!("recommendation.check_database_connection")
     );
-    println!(
+    outln!(
         "• {}",
         rust_i18n::t!("recommendation.check_immich_structure")
     );
-    println!("• {}", rust_i18n::t!("recommendation.check_ai_servers"));
+    outln!("• {}", rust_i18n::t!("recommendation.check_ai_servers"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        args::OverwritePolicy, config::AnalysisOptions, data_access::MemoryStore,
+        host_manager::MockAnalyzer,
+    };
+    use std::str::FromStr as _;
+
+    const ASSET: &str = "3f2c9e1a-5b7d-4c8e-9a1f-2d6b8e4c7a90";
+
+    fn asset_id() -> Uuid {
+        Uuid::from_str(ASSET).unwrap_or_default()
+    }
+
+    /// Runs one preview file through `analyzer`, returning the result and the stored descriptions.
+    async fn process_with(
+        reply: Result<String, ImageAnalysisError>,
+        options: AnalysisOptions,
+    ) -> (
+        Result<ImageAnalysisResult, ImageAnalysisError>,
+        HashMap<Uuid, String>,
+    ) {
+        let store = Arc::new(MemoryStore::with_assets(&[asset_id()]));
+        let data_access = DataAccess::Memory(Arc::clone(&store));
+        let analyzer = MockAnalyzer { options, reply };
+        let ctx = ProcessingContext::new(
+            &data_access,
+            "Describe the image",
+            &analyzer,
+            OverwritePolicy::None,
+            false,
+            false,
+            false,
+        );
+        let path = PathBuf::from(format!("{ASSET}_preview.jpeg"));
+        let result = process_file_with_existing_check(&ctx, &path, None).await;
+        let descriptions = store.descriptions().clone();
+        (result, descriptions)
+    }
+
+    #[tokio::test]
+    async fn stores_description_from_analyzer() {
+        let (result, descriptions) =
+            process_with(Ok("A red bicycle".to_owned()), AnalysisOptions::default()).await;
+        assert_eq!(
+            result.ok().map(|analysis| analysis.asset_id),
+            Some(asset_id())
+        );
+        assert!(
+            descriptions
+                .get(&asset_id())
+                .is_some_and(|desc| desc.contains("A red bicycle"))
+        );
+    }
+
+    #[tokio::test]
+    async fn analyzer_error_fails_file_without_writing() {
+        let (result, descriptions) = process_with(
+            Err(ImageAnalysisError::EmptyResponse {
+                filename: format!("{ASSET}_preview.jpeg"),
+            }),
+            AnalysisOptions::default(),
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Err(ImageAnalysisError::EmptyResponse { .. })
+        ));
+        assert!(descriptions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn diff_preview_is_skipped_without_writing() {
+        let options = AnalysisOptions {
+            show_diff: true,
+            diff_only: true,
+            ..AnalysisOptions::default()
+        };
+        let (result, descriptions) = process_with(Ok("A red bicycle".to_owned()), options).await;
+        assert!(matches!(result, Err(ImageAnalysisError::Previewed { .. })));
+        assert!(result.is_err_and(|err| err.is_skipped()));
+        assert!(descriptions.is_empty());
+    }
+
+    #[test]
+    fn duplicate_guard_rejects_only_beyond_threshold() {
+        let guard = DuplicateGuard::new(3);
+        let check = |description: &str| guard.check(description, "file.jpeg").is_ok();
+        // The first three identical descriptions are within the threshold
+        assert!((0_u8..3).all(|_| check("A cat")));
+        assert!(!check("A cat"));
+        assert!(!check(" A cat "));
+        // A differing description resets the run
+        assert!(check("A dog"));
+        assert!((0_u8..2).all(|_| check("A cat")));
+        assert!(check("A cat"));
+        assert!(!check("A cat"));
+    }
+
+    #[tokio::test]
+    async fn resume_asset_is_found_before_described_assets_are_dropped() {
+        let ids: Vec<Uuid> = (1..=4)
+            .map(|index| Uuid::from_u128(0x0000_0000_0000_4000_8000_0000_0000_0000 | index))
+            .collect();
+        let store = MemoryStore::with_assets(&ids);
+        // The resume asset and the one after it were described by hand
+        for described in ids.iter().skip(1).take(2) {
+            store
+                .descriptions()
+                .insert(*described, "Checked by hand".to_owned());
+        }
+        let progress = Arc::new(Mutex::new(SimpleProgress::new_discovering("")));
+        let (receiver, handle) = spawn_asset_discovery(
+            DataAccess::Memory(Arc::new(store)),
+            progress,
+            None,
+            Some(SortBy::Name),
+            ids.get(1).copied(),
+            Some(CoverageTarget::Description),
+        );
+        let queued: Vec<Uuid> = receiver.map(|asset| asset.id).collect().await;
+        assert_eq!(queued, ids.get(3..).unwrap_or_default());
+        let stats = handle.await.ok().and_then(Result::ok);
+        assert_eq!(
+            stats.map(|found| (found.queued, found.already_described)),
+            Some((1, 2))
+        );
+    }
 }
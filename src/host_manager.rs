@@ -1,18 +1,49 @@
 use crate::{
-    args::Interface,
+    anthropic,
+    args::{Args, Interface},
+    config::{AnalysisOptions, BYTES_PER_MB},
+    database::{AnalysisOutput, ImageAnalysisResult},
     error::ImageAnalysisError,
-    utils::{extract_uuid_from_preview_filename, filename_from_path, read_image_as_base64},
+    events::outln,
+    rate_limit::RateLimiter,
+    utils::{
+        EncodedImage, ensure_min_dimension, extract_asset_id_from_preview_path, filename_from_path,
+        read_image_as_base64, redact, redact_url, response_snippet, split_format_line,
+        strip_thinking_blocks,
+    },
+    webhook::WebhookNotifier,
 };
+use futures::future::BoxFuture;
 use log::{debug, error, info, warn};
-use reqwest::Client;
+use reqwest::{Client, RequestBuilder, Response};
 use serde_json::Value;
 use std::{
+    borrow::Cow,
     collections::HashMap,
+    hash::{BuildHasher as _, RandomState},
     num::NonZeroU32,
-    path::Path,
-    sync::{Arc, Mutex},
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
     time::{Duration, Instant},
 };
+use tokio::{sync::Semaphore, time::MissedTickBehavior};
+use uuid::Uuid;
+
+/// Header carrying the per-request UUID that also prefixes the log lines of that request.
+const REQUEST_ID_HEADER: &str = "X-Request-Id";
+/// Prepended to the prompt when related images are sent along with the main one.
+const RELATED_IMAGES_NOTE: &str = "Describe the first image. The other images are related \
+    frames from the same burst or stack and are only context.\n";
+/// Description returned by `--interface mock` for every image.
+const MOCK_DESCRIPTION: &str = "Mock description generated without a model.";
+/// Set once the first request body has been logged for `--dump-request`.
+static REQUEST_DUMPED: AtomicBool = AtomicBool::new(false);
+
+/// Ollama endpoint that unloads the model when called with `keep_alive: 0`.
+const OLLAMA_UNLOAD_ENDPOINT: &str = "/api/generate";
 
 impl Interface {
     /// Returns the API endpoint path for the given interface.
@@ -21,18 +52,71 @@ impl Interface {
         match self {
             Self::Ollama => "/api/chat",
             Self::Llamacpp => "/v1/chat/completions",
+            Self::Anthropic => "/v1/messages",
+            // Never sends requests
+            Self::Mock => "",
+        }
+    }
+
+    /// Part of a custom `--endpoint-path` in front of the default endpoint, e.g. `/proxy` for
+    /// `/proxy/v1/chat/completions`, so the model list and Ollama unload requests go through
+    /// the same mount. Empty when the custom path does not end with the default endpoint.
+    #[must_use]
+    pub fn endpoint_prefix(self, endpoint_path: Option<&str>) -> &str {
+        endpoint_path
+            .and_then(|path| path.strip_suffix(self.endpoint()))
+            .filter(|_| !self.endpoint().is_empty())
+            .unwrap_or_default()
+    }
+
+    /// Returns `true` if the interface accepts an API key.
+    #[inline]
+    pub const fn supports_api_key(self) -> bool {
+        match self {
+            Self::Ollama | Self::Mock => false,
+            Self::Llamacpp | Self::Anthropic => true,
         }
     }
 
-    /// Returns `true` if the interface supports Bearer token authentication.
+    /// Adds the authentication headers (and API version, if any) required by the interface.
+    pub fn authorize(self, request: RequestBuilder, api_key: Option<&str>) -> RequestBuilder {
+        match (self, api_key) {
+            (Self::Llamacpp, Some(key)) => request.header("Authorization", format!("Bearer {key}")),
+            (Self::Anthropic, _) => anthropic::authorize(request, api_key),
+            (Self::Ollama | Self::Llamacpp | Self::Mock, _) => request,
+        }
+    }
+
+    /// Returns the API endpoint path listing the models served by a host.
     #[inline]
-    pub const fn supports_bearer_auth(self) -> bool {
+    pub const fn models_endpoint(self) -> &'static str {
         match self {
-            Self::Ollama => false,
-            Self::Llamacpp => true,
+            Self::Ollama => "/api/tags",
+            Self::Llamacpp | Self::Anthropic => "/v1/models",
+            Self::Mock => "",
         }
     }
 
+    /// Parses a model list response and extracts the model names for the given interface.
+    pub fn parse_model_list(self, json_value: &Value) -> Vec<String> {
+        let (list_key, name_key) = match self {
+            Self::Ollama => ("models", "name"),
+            Self::Llamacpp | Self::Anthropic => ("data", "id"),
+            Self::Mock => return Vec::new(),
+        };
+        json_value
+            .get(list_key)
+            .and_then(|models| models.as_array())
+            .map(|models| {
+                models
+                    .iter()
+                    .filter_map(|model| model.get(name_key).and_then(|name| name.as_str()))
+                    .map(str::to_owned)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
     /// Parses the response JSON and extracts the content string for the given interface.
     pub fn parse_response(self, json_value: &Value) -> Option<&str> {
         match self {
@@ -47,48 +131,365 @@ impl Interface {
                 .and_then(|choice| choice.get("message"))
                 .and_then(|msg| msg.get("content"))
                 .and_then(|content| content.as_str()),
+            Self::Anthropic => anthropic::parse_response(json_value),
+            Self::Mock => None,
         }
     }
 
     /// Builds the JSON request body specific to the AI service interface.
-    pub fn build_request_body(self, model_name: &str, prompt: &str, base64_image: &str) -> Value {
+    ///
+    /// `images` are attached in order, the image to describe first.
+    /// `keep_alive` and the model `options` are only sent to Ollama and `image_detail` only to
+    /// llama.cpp; the other interfaces have no equivalent.
+    pub fn build_request_body(
+        self,
+        model_name: &str,
+        prompt: &str,
+        images: &[EncodedImage],
+        options: &AnalysisOptions,
+    ) -> Value {
         match self {
-            Self::Ollama => serde_json::json!({
+            Self::Ollama => {
+                let mut body = serde_json::json!({
                 "model": model_name,
                 "messages": [
                     {
                         "role": "user",
                         "content": prompt,
-                        "images": [base64_image]
-                    }
-                ],
-                "stream": false,
-            }),
-            Self::Llamacpp => serde_json::json!({
-                "model": model_name,
-                "messages": [
-                    {
-                        "role": "user",
-                        "content": [
-                            {
-                                "type": "text",
-                                "text": prompt
-                            },
-                            {
-                                "type": "image_url",
-                                "image_url": {
-                                    "url": format!("data:image/jpeg;base64,{}", base64_image)
-                                }
-                            }
-                        ]
+                        "images": images.iter().map(|image| image.base64.as_str()).collect::<Vec<_>>()
                     }
                 ],
                 "stream": false,
-            }),
+                });
+                if let (Some(duration), Some(fields)) =
+                    (options.keep_alive.as_ref(), body.as_object_mut())
+                {
+                    fields.insert("keep_alive".to_owned(), duration.clone());
+                }
+                if !options.ollama_options.is_empty()
+                    && let Some(fields) = body.as_object_mut()
+                {
+                    fields.insert(
+                        "options".to_owned(),
+                        Value::Object(options.ollama_options.clone()),
+                    );
+                }
+                body
+            }
+            Self::Llamacpp => {
+                let mut content = vec![serde_json::json!({
+                    "type": "text",
+                    "text": prompt
+                })];
+                content.extend(images.iter().map(|image| {
+                    serde_json::json!({
+                        "type": "image_url",
+                        "image_url": {
+                            "url": format!("data:{};base64,{}", image.mime_type, image.base64),
+                            "detail": options.image_detail.as_str()
+                        }
+                    })
+                }));
+                serde_json::json!({
+                    "model": model_name,
+                    "messages": [
+                        {
+                            "role": "user",
+                            "content": content
+                        }
+                    ],
+                    "stream": false,
+                })
+            }
+            Self::Anthropic => anthropic::build_request_body(model_name, prompt, images),
+            Self::Mock => Value::Null,
+        }
+    }
+}
+
+/// Verifies that `model_name` is served by every reachable host.
+///
+/// Hosts that cannot be queried are only logged, since availability is handled at request time.
+pub async fn verify_model_available(
+    client: &Client,
+    hosts: &[String],
+    interface: Interface,
+    model_name: &str,
+    api_key: Option<&str>,
+    endpoint_path: Option<&str>,
+) -> Result<(), ImageAnalysisError> {
+    for host in hosts {
+        let models = match fetch_model_list(client, host, interface, api_key, endpoint_path).await {
+            Ok(models) => models,
+            Err(reason) => {
+                warn!("Failed to list models on {host}: {reason}");
+                continue;
+            }
+        };
+
+        // Ollama reports untagged models with an implicit ":latest" suffix
+        let found = models
+            .iter()
+            .any(|model| model == model_name || model.strip_suffix(":latest") == Some(model_name));
+        if !found {
+            return Err(ImageAnalysisError::ModelNotFound {
+                model: model_name.to_owned(),
+                host: host.clone(),
+                available: models.join(", "),
+            });
+        }
+        info!("Model {model_name} is available on {host}");
+    }
+    Ok(())
+}
+
+/// Checks that every host answers its model-list endpoint (`--require-all-hosts`).
+///
+/// All hosts are queried at once; the error lists each unreachable host with the reason.
+pub async fn verify_hosts_reachable(
+    client: &Client,
+    hosts: &[String],
+    interface: Interface,
+    api_key: Option<&str>,
+    endpoint_path: Option<&str>,
+) -> Result<(), ImageAnalysisError> {
+    let results = futures::future::join_all(hosts.iter().map(|host| async move {
+        fetch_model_list(client, host, interface, api_key, endpoint_path)
+            .await
+            .err()
+            .map(|reason| format!("{host} ({reason})"))
+    }))
+    .await;
+    let unreachable: Vec<String> = results.into_iter().flatten().collect();
+    if unreachable.is_empty() {
+        info!("All {} hosts are reachable", hosts.len());
+        Ok(())
+    } else {
+        Err(ImageAnalysisError::HostsUnreachable {
+            hosts: unreachable.join(", "),
+        })
+    }
+}
+
+/// Fetches the models served by `host`, or why they could not be listed.
+async fn fetch_model_list(
+    client: &Client,
+    host: &str,
+    interface: Interface,
+    api_key: Option<&str>,
+    endpoint_path: Option<&str>,
+) -> Result<Vec<String>, String> {
+    let url = format!(
+        "{}{}{}",
+        host.trim_end_matches('/'),
+        interface.endpoint_prefix(endpoint_path),
+        interface.models_endpoint()
+    );
+    debug!("Fetching model list from {}", redact_url(&url));
+
+    let request = interface.authorize(client.get(&url), api_key);
+    let response = request.send().await.map_err(|err| err.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("HTTP {}", response.status().as_u16()));
+    }
+    let json_value = response
+        .json::<Value>()
+        .await
+        .map_err(|err| format!("invalid model list: {err}"))?;
+    Ok(interface.parse_model_list(&json_value))
+}
+
+/// Lowercase fragments of context-length errors reported by Ollama, llama.cpp and Anthropic.
+const CONTEXT_LENGTH_MARKERS: [&str; 7] = [
+    "context length",
+    "context_length",
+    "context size",
+    "exceeds the available context",
+    "maximum context",
+    "prompt is too long",
+    "too many tokens",
+];
+
+/// Whether an HTTP error response says the request did not fit into the model context.
+fn is_context_length_error(status: u16, response: &str) -> bool {
+    if !matches!(status, 400 | 413 | 500) {
+        return false;
+    }
+    let lowercase = response.to_lowercase();
+    CONTEXT_LENGTH_MARKERS
+        .iter()
+        .any(|marker| lowercase.contains(marker))
+}
+
+/// Reject descriptions that are shorter than `--min-description-length` or match a
+/// `--refusal-phrases` entry, so they are never stored.
+fn check_description(
+    description: &str,
+    filename: &str,
+    options: &AnalysisOptions,
+) -> Result<(), ImageAnalysisError> {
+    // Format tags are a single word by design
+    let length = description.chars().count();
+    if !matches!(options.output, AnalysisOutput::Tag { .. })
+        && length < options.min_description_length
+    {
+        return Err(ImageAnalysisError::ShortResponse {
+            filename: filename.to_owned(),
+            length,
+        });
+    }
+    let lowercase = description.to_lowercase();
+    if let Some(phrase) = options
+        .refusal_phrases
+        .iter()
+        .find(|phrase| lowercase.contains(phrase.as_str()))
+    {
+        return Err(ImageAnalysisError::RefusalResponse {
+            filename: filename.to_owned(),
+            phrase: phrase.clone(),
+        });
+    }
+    Ok(())
+}
+
+/// Copy of a request body with the base64 image data replaced by `<base64 N bytes>`,
+/// short enough to log (`--dump-request`).
+fn elide_images(body: &Value, images: &[EncodedImage]) -> Value {
+    match body {
+        Value::String(text) => Value::String(images.iter().fold(text.clone(), |elided, image| {
+            elided.replace(
+                &image.base64,
+                &format!("<base64 {} bytes>", image.base64.len()),
+            )
+        })),
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| elide_images(item, images))
+                .collect(),
+        ),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(key, value)| (key.clone(), elide_images(value, images)))
+                .collect(),
+        ),
+        Value::Null | Value::Bool(_) | Value::Number(_) => body.clone(),
+    }
+}
+
+/// Fraction of `--unavailable-duration` used as the upper bound for recovery jitter.
+const RECOVERY_JITTER_DIVISOR: u32 = 5;
+
+/// Random delay of up to a fifth of `duration`, added to a host's unavailable period.
+fn recovery_jitter(duration: Duration) -> Duration {
+    let max_millis = u64::try_from(
+        duration
+            .checked_div(RECOVERY_JITTER_DIVISOR)
+            .unwrap_or_default()
+            .as_millis(),
+    )
+    .unwrap_or(u64::MAX);
+    // RandomState is seeded randomly, which is enough for spreading out retries
+    let random = RandomState::new().hash_one(Instant::now());
+    Duration::from_millis(random.checked_rem(max_millis).unwrap_or(0))
+}
+
+/// Log the status of every host each `interval` for the rest of the run (`--host-status-interval`).
+pub fn spawn_host_status_log(host_manager: Arc<HostManager>, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        // The first tick fires immediately; nothing has happened yet at startup
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            host_manager.log_host_statuses();
         }
+    });
+}
+
+/// Something that turns a preview image into an analysis result.
+///
+/// File processing only talks to this trait, so tests can inject an analyzer that
+/// returns canned results instead of calling an AI host. Returns a boxed future to
+/// stay usable as `&dyn ImageAnalyzer`.
+pub trait ImageAnalyzer: Send + Sync {
+    /// Analyze `image_path`, sending `related_paths` along as extra context.
+    fn analyze<'a>(
+        &'a self,
+        image_path: &'a Path,
+        related_paths: &'a [PathBuf],
+        prompt: &'a str,
+    ) -> BoxFuture<'a, Result<ImageAnalysisResult, ImageAnalysisError>>;
+
+    /// Per-request options, including where results are stored.
+    fn options(&self) -> &AnalysisOptions;
+}
+
+impl ImageAnalyzer for HostManager {
+    fn analyze<'a>(
+        &'a self,
+        image_path: &'a Path,
+        related_paths: &'a [PathBuf],
+        prompt: &'a str,
+    ) -> BoxFuture<'a, Result<ImageAnalysisResult, ImageAnalysisError>> {
+        Box::pin(self.analyze_image(image_path, related_paths, prompt))
+    }
+
+    fn options(&self) -> &AnalysisOptions {
+        &self.options
+    }
+}
+
+/// Analyzer for tests that gives every image the same `reply` without calling a host.
+#[cfg(test)]
+pub struct MockAnalyzer {
+    pub options: AnalysisOptions,
+    /// Description returned for every image, or the error every analysis fails with
+    pub reply: Result<String, ImageAnalysisError>,
+}
+
+#[cfg(test)]
+impl ImageAnalyzer for MockAnalyzer {
+    fn analyze<'a>(
+        &'a self,
+        image_path: &'a Path,
+        _related_paths: &'a [PathBuf],
+        _prompt: &'a str,
+    ) -> BoxFuture<'a, Result<ImageAnalysisResult, ImageAnalysisError>> {
+        Box::pin(async move {
+            let description = self.reply.clone()?;
+            Ok(ImageAnalysisResult {
+                description,
+                asset_id: extract_asset_id_from_preview_path(image_path)?,
+                output: self.options.output.clone(),
+                format: None,
+            })
+        })
+    }
+
+    fn options(&self) -> &AnalysisOptions {
+        &self.options
     }
 }
 
+/// Availability of a single host, as reported by `HostManager::host_statuses`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HostAvailability {
+    Available,
+    /// Marked unavailable; it will be tried again after `retry_in`
+    Unavailable {
+        retry_in: Duration,
+    },
+    /// Unavailable period is over, waiting for a recovery probe to succeed
+    AwaitingProbe,
+}
+
+/// Picks AI hosts and tracks which of them are unavailable.
+///
+/// One instance is created per process and shared through an `Arc`, so in combined mode
+/// a host marked unavailable by batch processing is also avoided by the monitor.
 #[derive(Debug, Clone)]
 pub struct HostManager {
     hosts: Vec<String>,
@@ -98,12 +499,47 @@ pub struct HostManager {
     timeout: u64,
     max_retries: Option<NonZeroU32>,
     retry_delay: Duration,
+    /// Unavailable hosts mapped to the time they may be tried again
     unavailable_hosts: Arc<Mutex<HashMap<String, Instant>>>,
     unavailable_duration: Duration,
+    /// Let a single probe request through to a recovering host before re-enabling it
+    recovery_probe: bool,
     api_key: Option<String>,
+    options: AnalysisOptions,
+    host_semaphores: Arc<HashMap<String, Semaphore>>,
+    /// Process-wide `--max-concurrent` cap, shared by batch and monitor in combined mode
+    request_slots: Arc<Semaphore>,
+    /// Process-wide `--max-rpm` limit, shared like `request_slots`
+    rate_limiter: Option<Arc<RateLimiter>>,
+    /// Alerts about hosts failing repeatedly (`--webhook-url`)
+    webhook: Option<Arc<WebhookNotifier>>,
 }
 
 impl HostManager {
+    /// Builds the host manager from command line arguments.
+    ///
+    /// Created once per run and shared by batch and monitor processing.
+    #[must_use]
+    pub fn from_args(args: &Args, client: Client, webhook: Option<Arc<WebhookNotifier>>) -> Self {
+        Self::new(
+            args.hosts.clone(),
+            args.interface,
+            client,
+            args.model_name.clone(),
+            args.timeout,
+            NonZeroU32::new(args.max_retries),
+            Duration::from_secs(args.retry_delay_seconds),
+            Duration::from_secs(args.unavailable_duration),
+            args.api_key.clone(),
+            AnalysisOptions::from_args(args),
+            args.concurrency_per_host,
+            args.max_concurrent,
+            args.max_rpm,
+            args.host_recovery_probe,
+            webhook,
+        )
+    }
+
     #[expect(clippy::too_many_arguments)]
     pub fn new(
         hosts: Vec<String>,
@@ -115,7 +551,22 @@ impl HostManager {
         retry_delay: Duration,
         unavailable_duration: Duration,
         api_key: Option<String>,
+        options: AnalysisOptions,
+        concurrency_per_host: usize,
+        max_concurrent: usize,
+        max_rpm: u32,
+        recovery_probe: bool,
+        webhook: Option<Arc<WebhookNotifier>>,
     ) -> Self {
+        // Per-host limits are keyed by URL so each backend gets its own independent cap
+        let host_semaphores = if concurrency_per_host == 0 {
+            HashMap::new()
+        } else {
+            hosts
+                .iter()
+                .map(|host| (host.clone(), Semaphore::new(concurrency_per_host)))
+                .collect()
+        };
         Self {
             hosts,
             interface,
@@ -126,7 +577,13 @@ impl HostManager {
             retry_delay,
             unavailable_hosts: Arc::new(Mutex::new(HashMap::new())),
             unavailable_duration,
+            recovery_probe,
             api_key,
+            options,
+            host_semaphores: Arc::new(host_semaphores),
+            request_slots: Arc::new(Semaphore::new(max_concurrent.max(1))),
+            rate_limiter: RateLimiter::new(max_rpm).map(Arc::new),
+            webhook,
         }
     }
 
@@ -142,14 +599,16 @@ impl HostManager {
             .lock()
             .expect("unavailable_hosts mutex poisoned");
         let now = Instant::now();
-        let original_count = unavailable.len();
-        unavailable
-            .retain(|_, timestamp| now.duration_since(*timestamp) < self.unavailable_duration);
-
-        if let Some(removed_count) = original_count.checked_sub(unavailable.len())
-            && removed_count > 0
-        {
-            debug!("Cleaned up {removed_count} expired unavailable hosts");
+        // With recovery probes, expired hosts stay listed until a probe request succeeds
+        if !self.recovery_probe {
+            let original_count = unavailable.len();
+            unavailable.retain(|_, retry_at| now < *retry_at);
+
+            if let Some(removed_count) = original_count.checked_sub(unavailable.len())
+                && removed_count > 0
+            {
+                debug!("Cleaned up {removed_count} expired unavailable hosts");
+            }
         }
 
         debug!(
@@ -158,19 +617,34 @@ impl HostManager {
         );
 
         for host in &self.hosts {
-            if !unavailable.contains_key(host) {
-                info!("Selected available {:?} host: {}", self.interface, host);
-                return Ok(host.clone());
+            match unavailable.get_mut(host) {
+                None => {
+                    info!("Selected available {:?} host: {}", self.interface, host);
+                    return Ok(host.clone());
+                }
+                Some(retry_at) if now >= *retry_at => {
+                    // Hold the host back from other tasks until this probe finishes or times out
+                    *retry_at = now
+                        .checked_add(Duration::from_secs(
+                            self.request_timeout(u64::MAX).saturating_add(1),
+                        ))
+                        .unwrap_or(now);
+                    info!(
+                        "Sending recovery probe to {:?} host: {}",
+                        self.interface, host
+                    );
+                    return Ok(host.clone());
+                }
+                Some(_) => {}
             }
         }
 
-        if let Some((host, timestamp)) = unavailable.iter().min_by_key(|(_, timestamp)| *timestamp)
-        {
+        if let Some((host, retry_at)) = unavailable.iter().min_by_key(|(_, retry_at)| *retry_at) {
             warn!(
-                "All {:?} hosts unavailable. Using oldest unavailable host: {} (unavailable for {:?})",
+                "All {:?} hosts unavailable. Using the host closest to recovery: {} (available again in {:?})",
                 self.interface,
                 host,
-                now.duration_since(*timestamp)
+                retry_at.saturating_duration_since(now)
             );
             return Ok(host.clone());
         }
@@ -181,37 +655,249 @@ impl HostManager {
     }
 
     pub fn mark_host_unavailable(&self, host: &str) {
+        // Stagger recovery so hosts that failed together do not all come back at once
+        let retry_after = self
+            .unavailable_duration
+            .saturating_add(recovery_jitter(self.unavailable_duration));
+        let now = Instant::now();
         self.unavailable_hosts
             .lock()
             .expect("unavailable_hosts mutex poisoned")
-            .insert(host.to_owned(), Instant::now());
-        println!(
+            .insert(host.to_owned(), now.checked_add(retry_after).unwrap_or(now));
+        outln!(
             "{}",
             rust_i18n::t!("host_manager.host_marked_unavailable", host = host)
         );
+        if let Some(webhook) = &self.webhook {
+            webhook.record_host_failure(host);
+        }
+        self.log_available_hosts();
+    }
+
+    /// Fully re-enable a host after it answered successfully.
+    fn mark_host_recovered(&self, host: &str) {
+        let removed = self
+            .unavailable_hosts
+            .lock()
+            .expect("unavailable_hosts mutex poisoned")
+            .remove(host)
+            .is_some();
+        if removed {
+            outln!(
+                "{}",
+                rust_i18n::t!("host_manager.host_recovered", host = host)
+            );
+            self.log_available_hosts();
+        }
+    }
+
+    /// Number of hosts that are not currently marked unavailable.
+    ///
+    /// Hosts whose unavailable period has run out count as available, even while they
+    /// still wait for a recovery probe.
+    pub fn available_host_count(&self) -> usize {
+        let unavailable = self
+            .unavailable_hosts
+            .lock()
+            .expect("unavailable_hosts mutex poisoned");
+        let now = Instant::now();
+        self.hosts
+            .iter()
+            .filter(|host| {
+                unavailable
+                    .get(host.as_str())
+                    .is_none_or(|retry_at| now >= *retry_at)
+            })
+            .count()
     }
 
+    /// Snapshot of every configured host and its current availability.
+    pub fn host_statuses(&self) -> Vec<(String, HostAvailability)> {
+        let unavailable = self
+            .unavailable_hosts
+            .lock()
+            .expect("unavailable_hosts mutex poisoned");
+        let now = Instant::now();
+        self.hosts
+            .iter()
+            .map(|host| {
+                let availability = match unavailable.get(host.as_str()) {
+                    Some(retry_at) if now < *retry_at => HostAvailability::Unavailable {
+                        retry_in: retry_at.saturating_duration_since(now),
+                    },
+                    Some(_) if self.recovery_probe => HostAvailability::AwaitingProbe,
+                    _ => HostAvailability::Available,
+                };
+                (host.clone(), availability)
+            })
+            .collect()
+    }
+
+    /// Log one line per host with its availability.
+    fn log_host_statuses(&self) {
+        info!(
+            "{:?} host status ({}/{} available):",
+            self.interface,
+            self.available_host_count(),
+            self.hosts.len()
+        );
+        for (host, availability) in self.host_statuses() {
+            let shown = redact_url(&host);
+            match availability {
+                HostAvailability::Available => info!("  {shown}: available"),
+                HostAvailability::Unavailable { retry_in } => {
+                    info!("  {shown}: unavailable, retry in {}s", retry_in.as_secs());
+                }
+                HostAvailability::AwaitingProbe => {
+                    info!("  {shown}: awaiting recovery probe");
+                }
+            }
+        }
+    }
+
+    fn log_available_hosts(&self) {
+        info!(
+            "{}/{} {:?} hosts available",
+            self.available_host_count(),
+            self.hosts.len(),
+            self.interface
+        );
+    }
+
+    /// Request timeout in seconds for an image of `file_size` bytes.
+    ///
+    /// `--timeout` plus `--timeout-per-image-mb` for every megabyte, capped at `--max-timeout`.
+    fn request_timeout(&self, file_size: u64) -> u64 {
+        if self.options.timeout_per_mb == 0 {
+            return self.timeout;
+        }
+        let scaled = file_size
+            .saturating_mul(self.options.timeout_per_mb)
+            .checked_div(BYTES_PER_MB)
+            .unwrap_or(0);
+        self.timeout
+            .saturating_add(scaled)
+            .min(self.options.max_timeout.max(self.timeout))
+    }
+
+    /// Unload the model on `host` so the next image starts without any cached state.
+    ///
+    /// Sends Ollama a generate request with `keep_alive: 0` while the host slot is still held.
+    /// Failures are only logged, since the description has already been received.
+    async fn reset_context(&self, host: &str, request_id: Uuid) {
+        let url = format!(
+            "{}{}{OLLAMA_UNLOAD_ENDPOINT}",
+            host.trim_end_matches('/'),
+            self.interface
+                .endpoint_prefix(self.options.endpoint_path.as_deref())
+        );
+        let body = serde_json::json!({
+            "model": self.model_name,
+            "keep_alive": 0_u8,
+        });
+        let result = self
+            .client
+            .post(&url)
+            .timeout(Duration::from_secs(self.timeout))
+            .header(REQUEST_ID_HEADER, request_id.to_string())
+            .json(&body)
+            .send()
+            .await
+            .and_then(Response::error_for_status);
+        match result {
+            Ok(_) => debug!(
+                "[{request_id}] Unloaded model {} on {}",
+                self.model_name,
+                redact_url(host)
+            ),
+            Err(err) => warn!(
+                "[{request_id}] Failed to unload model {} on {}: {err}",
+                self.model_name,
+                redact_url(host)
+            ),
+        }
+    }
+
+    /// Describes the image at `image_path`, sending `related_paths` along as extra context.
+    ///
+    /// Related images (other frames of a stack, see `--prompt-max-images`) follow the main
+    /// image in the request; one that cannot be read is left out instead of failing.
     pub async fn analyze_image(
         &self,
         image_path: &Path,
+        related_paths: &[PathBuf],
         prompt: &str,
-    ) -> Result<crate::database::ImageAnalysisResult, ImageAnalysisError> {
+    ) -> Result<ImageAnalysisResult, ImageAnalysisError> {
         let filename = filename_from_path(image_path);
+        // Each mode limits its own files, this keeps combined mode under one global cap
+        let _request_slot = self.request_slots.acquire().await.ok();
 
         info!(
             "Starting {:?} analysis for image: {}",
             self.interface, filename
         );
-        debug!("Model: {}, Timeout: {}s", self.model_name, self.timeout);
-
-        let asset_id = extract_uuid_from_preview_filename(&filename)?;
-        let base64_image = read_image_as_base64(image_path, &filename).await?;
+        let asset_id = extract_asset_id_from_preview_path(image_path)?;
+        if let Some(min_size) = self.options.min_image_dimension {
+            ensure_min_dimension(image_path, &filename, min_size).await?;
+        }
+        let mut images = vec![read_image_as_base64(image_path, &filename, &self.options).await?];
+        let mut file_size = tokio::fs::metadata(image_path)
+            .await
+            .map_or(0, |metadata| metadata.len());
+        for related_path in related_paths {
+            let related_name = filename_from_path(related_path);
+            match read_image_as_base64(related_path, &related_name, &self.options).await {
+                Ok(related) => {
+                    images.push(related);
+                    file_size = file_size.saturating_add(
+                        tokio::fs::metadata(related_path)
+                            .await
+                            .map_or(0, |metadata| metadata.len()),
+                    );
+                }
+                Err(err) => warn!("Leaving related image {related_name} out of the request: {err}"),
+            }
+        }
+        if self.interface == Interface::Mock {
+            tokio::time::sleep(self.options.mock_delay).await;
+            return Ok(ImageAnalysisResult {
+                description: MOCK_DESCRIPTION.to_owned(),
+                asset_id,
+                output: self.options.output.clone(),
+                format: None,
+            });
+        }
+        let timeout = self.request_timeout(file_size);
+        debug!(
+            "Model: {}, Timeout: {}s, Images: {}",
+            self.model_name,
+            timeout,
+            images.len()
+        );
 
-        let request_body =
-            self.interface
-                .build_request_body(&self.model_name, prompt, &base64_image);
+        let request_prompt = if images.len() > 1 {
+            Cow::Owned(format!("{RELATED_IMAGES_NOTE}{prompt}"))
+        } else {
+            Cow::Borrowed(prompt)
+        };
+        let request_body = self.interface.build_request_body(
+            &self.model_name,
+            &request_prompt,
+            &images,
+            &self.options,
+        );
+        if self.options.dump_request && !REQUEST_DUMPED.swap(true, Ordering::Relaxed) {
+            debug!(
+                "Request body for {filename}: {}",
+                elide_images(&request_body, &images)
+            );
+        }
 
-        let endpoint = self.interface.endpoint();
+        let endpoint = self
+            .options
+            .endpoint_path
+            .as_deref()
+            .unwrap_or_else(|| self.interface.endpoint());
 
         let mut attempt: u32 = 0;
         let mut last_error = None;
@@ -241,89 +927,159 @@ impl HostManager {
                     }
                 };
 
-                let url = format!("{}{}", host.trim_end_matches('/'), endpoint);
-                info!("Making {:?} request to: {}", self.interface, url);
+                let _host_permit = match self.host_semaphores.get(&host) {
+                    Some(semaphore) => {
+                        debug!(
+                            "Waiting for a request slot on {host} ({} free)",
+                            semaphore.available_permits()
+                        );
+                        Some(semaphore.acquire().await.map_err(|err| {
+                            ImageAnalysisError::ProcessingError {
+                                filename: filename.clone(),
+                                error: err.to_string(),
+                            }
+                        })?)
+                    }
+                    None => None,
+                };
 
-                let mut request = self.client.post(&url).json(&request_body);
+                if let Some(rate_limiter) = &self.rate_limiter {
+                    rate_limiter.acquire().await;
+                }
+
+                let url = format!("{}{}", host.trim_end_matches('/'), endpoint);
+                // Sent as X-Request-Id so gateway and server logs can be matched to ours
+                let request_id = Uuid::new_v4();
+                info!(
+                    "[{request_id}] Making {:?} request to: {}",
+                    self.interface,
+                    redact_url(&url)
+                );
 
-                if self.interface.supports_bearer_auth() {
+                if self.interface.supports_api_key() {
                     if let Some(api_key) = &self.api_key {
-                        debug!("Adding Authorization header with API key");
-                        request = request.header("Authorization", format!("Bearer {api_key}"));
+                        debug!(
+                            "[{request_id}] Adding authentication header with API key {}",
+                            redact(api_key)
+                        );
                     } else {
-                        debug!("No API key provided for {:?} request", self.interface);
+                        debug!(
+                            "[{request_id}] No API key provided for {:?} request",
+                            self.interface
+                        );
                     }
                 }
+                let request = self
+                    .interface
+                    .authorize(self.client.post(&url), self.api_key.as_deref())
+                    .timeout(Duration::from_secs(timeout))
+                    .header(REQUEST_ID_HEADER, request_id.to_string())
+                    .json(&request_body);
 
-                match tokio::time::timeout(
-                    Duration::from_secs(self.timeout.saturating_add(1)),
-                    async {
-                        debug!("Sending {:?} request...", self.interface);
-                        request.send().await
-                    },
-                )
+                // `send` resolves with the headers, the body is read under the request timeout
+                let total_timeout = Duration::from_secs(timeout.saturating_add(1));
+                let start_timeout = self
+                    .options
+                    .response_start_timeout
+                    .filter(|start| *start < total_timeout);
+                match tokio::time::timeout(start_timeout.unwrap_or(total_timeout), async {
+                    debug!("[{request_id}] Sending {:?} request...", self.interface);
+                    request.send().await
+                })
                 .await
                 {
                     Ok(Ok(response)) => {
                         let status = response.status();
                         debug!(
-                            "Received {:?} response: {} {}",
+                            "[{request_id}] Received {:?} response: {} {}",
                             self.interface,
                             status.as_u16(),
                             status.canonical_reason().unwrap_or("")
                         );
 
                         if response.status().is_success() {
+                            self.mark_host_recovered(&host);
                             let response_text = response.text().await.map_err(|err| {
-                                error!("Failed to read response body: {err}");
+                                error!("[{request_id}] Failed to read response body: {err}");
                                 ImageAnalysisError::ProcessingError {
                                     filename: filename.clone(),
                                     error: err.to_string(),
                                 }
                             })?;
 
-                            debug!("Response body length: {} chars", response_text.len());
+                            debug!(
+                                "[{request_id}] Response body length: {} chars",
+                                response_text.len()
+                            );
 
                             match serde_json::from_str::<Value>(&response_text) {
                                 Ok(json_value) => {
                                     let content = self.interface.parse_response(&json_value);
 
                                     if let Some(raw_description) = content {
-                                        let description = raw_description.trim().to_owned();
+                                        let response_text = if self.options.strip_thinking {
+                                            strip_thinking_blocks(raw_description)
+                                        } else {
+                                            raw_description.trim().to_owned()
+                                        };
+                                        let (description, format) =
+                                            if self.options.format_tag_prefix.is_some() {
+                                                split_format_line(&response_text)
+                                            } else {
+                                                (response_text, None)
+                                            };
+                                        // The host answered, so a rejected answer says nothing about its health
                                         if description.is_empty() {
-                                            warn!("Empty response for image: {filename}");
-                                            last_error = Some(ImageAnalysisError::EmptyResponse {
+                                            warn!(
+                                                "[{request_id}] Empty response for image: {filename}"
+                                            );
+                                            return Err(ImageAnalysisError::EmptyResponse {
                                                 filename: filename.clone(),
                                             });
-                                        } else {
-                                            info!(
-                                                "{:?} analysis successful for {}, description length: {}",
-                                                self.interface,
-                                                filename,
-                                                description.len()
+                                        }
+                                        if let Err(err) = check_description(
+                                            &description,
+                                            &filename,
+                                            &self.options,
+                                        ) {
+                                            warn!(
+                                                "[{request_id}] Rejected response for image {filename}: {err}"
                                             );
-                                            return Ok(crate::database::ImageAnalysisResult {
-                                                description,
-                                                asset_id,
-                                            });
+                                            return Err(err);
                                         }
-                                    } else {
-                                        error!(
-                                            "Failed to extract content from response for {filename}"
+                                        info!(
+                                            "[{request_id}] {:?} analysis successful for {}, description length: {}",
+                                            self.interface,
+                                            filename,
+                                            description.len()
                                         );
-                                        last_error = Some(ImageAnalysisError::JsonParsing {
-                                            filename: filename.clone(),
-                                            error: "No content field found in response".to_owned(),
+                                        if self.options.reset_context {
+                                            self.reset_context(&host, request_id).await;
+                                        }
+                                        return Ok(ImageAnalysisResult {
+                                            description,
+                                            asset_id,
+                                            output: self.options.output.clone(),
+                                            format,
                                         });
                                     }
+                                    error!(
+                                        "[{request_id}] Failed to extract content from response for {filename}"
+                                    );
+                                    last_error = Some(ImageAnalysisError::JsonParsing {
+                                        filename: filename.clone(),
+                                        error: "No content field found in response".to_owned(),
+                                        snippet: response_snippet(&response_text),
+                                    });
                                 }
                                 Err(parse_error) => {
                                     error!(
-                                        "Failed to parse response as JSON for {filename}: {parse_error}"
+                                        "[{request_id}] Failed to parse response as JSON for {filename}: {parse_error}"
                                     );
                                     let error = ImageAnalysisError::JsonParsing {
                                         filename: filename.clone(),
                                         error: parse_error.to_string(),
+                                        snippet: response_snippet(&response_text),
                                     };
                                     if !error.is_retryable() {
                                         return Err(error);
@@ -335,13 +1091,27 @@ impl HostManager {
                             let status = response.status().as_u16();
                             let response_text = response.text().await.unwrap_or_default();
                             error!(
-                                "{:?} HTTP error {} for {}: {}",
+                                "[{request_id}] {:?} HTTP error {} for {}: {}",
                                 self.interface, status, filename, response_text
                             );
+                            // The request itself is too large for the model, so other hosts won't help
+                            if is_context_length_error(status, &response_text) {
+                                return Err(ImageAnalysisError::ContextLengthExceeded {
+                                    filename: filename.clone(),
+                                    response: response_text,
+                                });
+                            }
+                            // Usually a proxy body size limit in front of the host
+                            if status == 413 {
+                                return Err(ImageAnalysisError::PayloadTooLarge {
+                                    filename: filename.clone(),
+                                    response: response_snippet(&response_text),
+                                });
+                            }
                             let error = ImageAnalysisError::HttpError {
                                 status,
                                 filename: filename.clone(),
-                                response: response_text,
+                                response: format!("{response_text} (request {request_id})"),
                             };
                             if !error.is_retryable() {
                                 return Err(error);
@@ -351,21 +1121,37 @@ impl HostManager {
                     }
                     Ok(Err(err)) => {
                         error!(
-                            "{:?} request failed for {}: {}",
+                            "[{request_id}] {:?} request failed for {}: {}",
                             self.interface, filename, err
                         );
                         last_error = Some(ImageAnalysisError::HttpError {
                             status: 0,
                             filename: filename.clone(),
-                            response: err.to_string(),
+                            response: format!("{err} (request {request_id})"),
                         });
                     }
                     Err(_) => {
-                        last_error = Some(ImageAnalysisError::AiRequestTimeout);
+                        if let Some(start) = start_timeout {
+                            error!(
+                                "[{request_id}] {:?} host {} sent no response within {}s for {filename}",
+                                self.interface,
+                                redact_url(&host),
+                                start.as_secs()
+                            );
+                            last_error = Some(ImageAnalysisError::ResponseStartTimeout {
+                                timeout: start.as_secs(),
+                            });
+                        } else {
+                            error!(
+                                "[{request_id}] {:?} request timed out for {filename}",
+                                self.interface
+                            );
+                            last_error = Some(ImageAnalysisError::AiRequestTimeout);
+                        }
                     }
                 }
                 warn!(
-                    "Marking {:?} host as unavailable due to error: {}",
+                    "[{request_id}] Marking {:?} host as unavailable due to error: {}",
                     self.interface, host
                 );
                 self.mark_host_unavailable(&host);
@@ -391,3 +1177,173 @@ impl HostManager {
         Err(last_error.unwrap_or(ImageAnalysisError::AllHostsUnavailable))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::build_http_client;
+    use reqwest::header::HeaderMap;
+    use std::sync::atomic::AtomicUsize;
+    use tokio::{
+        io::{AsyncBufReadExt as _, AsyncReadExt as _, AsyncWriteExt as _, BufReader},
+        net::{TcpListener, TcpStream},
+    };
+
+    const REPLY: &str = r#"{"message":{"content":"A red square on a white background."}}"#;
+
+    /// Answers every request on `stream` with an Ollama chat reply, recording the body sizes.
+    async fn serve_connection(stream: TcpStream, body_sizes: Arc<Mutex<Vec<usize>>>) {
+        let mut reader = BufReader::new(stream);
+        loop {
+            let mut content_length = 0_usize;
+            let mut line = String::new();
+            loop {
+                line.clear();
+                if reader.read_line(&mut line).await.unwrap_or(0) == 0 {
+                    return;
+                }
+                if line == "\r\n" {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':')
+                    && name.eq_ignore_ascii_case("content-length")
+                {
+                    content_length = value.trim().parse().unwrap_or(0);
+                }
+            }
+            let mut body = vec![0_u8; content_length];
+            if reader.read_exact(&mut body).await.is_err() {
+                return;
+            }
+            if let Ok(mut sizes) = body_sizes.lock() {
+                sizes.push(body.len());
+            }
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{REPLY}",
+                REPLY.len()
+            );
+            if reader
+                .get_mut()
+                .write_all(response.as_bytes())
+                .await
+                .is_err()
+            {
+                return;
+            }
+        }
+    }
+
+    /// Writes a small preview image into a fresh temporary directory.
+    async fn write_test_image() -> Result<(PathBuf, PathBuf), Box<dyn std::error::Error>> {
+        let dir = std::env::temp_dir().join(format!("immich-analyze-test-{}", Uuid::new_v4()));
+        tokio::fs::create_dir_all(&dir).await?;
+        let image_path = dir.join(format!("{}-preview.jpeg", Uuid::new_v4()));
+        image::RgbImage::new(16, 16).save_with_format(&image_path, image::ImageFormat::Jpeg)?;
+        Ok((dir, image_path))
+    }
+
+    /// A host manager sending Ollama requests to `host`, trying each request once.
+    fn test_host_manager(
+        host: String,
+        options: AnalysisOptions,
+    ) -> Result<HostManager, Box<dyn std::error::Error>> {
+        let client = build_http_client(
+            Duration::from_secs(30),
+            Duration::from_secs(5),
+            None,
+            &[],
+            "immich-analyze-test",
+            HeaderMap::new(),
+        )?;
+        Ok(HostManager::new(
+            vec![host],
+            Interface::Ollama,
+            client,
+            "test-model".to_owned(),
+            30,
+            NonZeroU32::new(1),
+            Duration::from_millis(10),
+            Duration::from_mins(1),
+            None,
+            options,
+            0,
+            1,
+            0,
+            false,
+            None,
+        ))
+    }
+
+    /// Repeated analyses of the same image must neither open new connections nor send
+    /// growing request bodies, so per-request setup cost stays constant over a run.
+    #[tokio::test]
+    async fn repeated_requests_reuse_connection_and_body_size()
+    -> Result<(), Box<dyn std::error::Error>> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let host = format!("http://{}", listener.local_addr()?);
+        let connections = Arc::new(AtomicUsize::new(0));
+        let body_sizes = Arc::new(Mutex::new(Vec::new()));
+        let server_connections = Arc::clone(&connections);
+        let server_sizes = Arc::clone(&body_sizes);
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                server_connections.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(serve_connection(stream, Arc::clone(&server_sizes)));
+            }
+        });
+
+        let (dir, image_path) = write_test_image().await?;
+        let host_manager = test_host_manager(host, AnalysisOptions::default())?;
+        let runs = 5_usize;
+        for _ in 0..runs {
+            host_manager
+                .analyze_image(&image_path, &[], "Describe the image.")
+                .await?;
+        }
+        tokio::fs::remove_dir_all(&dir).await?;
+
+        let sizes = body_sizes
+            .lock()
+            .map(|sizes| sizes.clone())
+            .unwrap_or_default();
+        assert_eq!(sizes.len(), runs);
+        assert!(sizes.windows(2).all(|pair| pair.first() == pair.last()));
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+        Ok(())
+    }
+
+    /// A refused description fails the file but leaves the host that answered in rotation.
+    #[tokio::test]
+    async fn rejected_description_keeps_host_available() -> Result<(), Box<dyn std::error::Error>> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let host = format!("http://{}", listener.local_addr()?);
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve_connection(stream, Arc::new(Mutex::new(Vec::new()))));
+            }
+        });
+
+        let (dir, image_path) = write_test_image().await?;
+        let options = AnalysisOptions {
+            refusal_phrases: vec!["red square".to_owned()],
+            ..AnalysisOptions::default()
+        };
+        let host_manager = test_host_manager(host, options)?;
+        let result = host_manager
+            .analyze_image(&image_path, &[], "Describe the image.")
+            .await;
+        tokio::fs::remove_dir_all(&dir).await?;
+
+        assert!(matches!(
+            result,
+            Err(ImageAnalysisError::RefusalResponse { .. })
+        ));
+        assert!(
+            host_manager
+                .host_statuses()
+                .iter()
+                .all(|(_, status)| *status == HostAvailability::Available)
+        );
+        Ok(())
+    }
+}
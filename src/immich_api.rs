@@ -1,10 +1,15 @@
-use crate::error::ImageAnalysisError;
+use crate::{
+    data_access::{AssetFilter, ExcludedAssets},
+    database::METADATA_KEY,
+    error::ImageAnalysisError,
+    utils::response_snippet,
+};
 use log::{info, warn};
 use reqwest::{
-    Client,
+    Client, StatusCode,
     header::{HeaderMap, HeaderValue},
 };
-use serde::Deserialize;
+use serde::{Deserialize, de::DeserializeOwned};
 use std::path::PathBuf;
 use std::time::{Duration, Instant};
 use url::Url;
@@ -15,6 +20,8 @@ use uuid::Uuid;
 pub struct AssetRef {
     /// Unique identifier of the asset (UUID)
     pub id: Uuid,
+    /// Preview file the asset was discovered through (database mode), used by `--sort-by`
+    pub preview_path: Option<PathBuf>,
 }
 
 /// Internal response structure for asset metadata.
@@ -24,6 +31,38 @@ pub struct AssetResponse {
     pub id: String,
     #[serde(default)]
     pub exif_info: Option<ExifInfo>,
+    /// `timeline`, `archive`, `hidden` or `locked`
+    #[serde(default)]
+    pub visibility: Option<String>,
+    /// Reported by Immich versions before `visibility` was introduced
+    #[serde(default)]
+    pub is_archived: bool,
+    #[serde(default)]
+    pub is_trashed: bool,
+    /// `IMAGE`, `VIDEO`, `AUDIO` or `OTHER`
+    #[serde(default, rename = "type")]
+    pub asset_type: Option<String>,
+    /// Stack the asset belongs to, if any
+    #[serde(default)]
+    pub stack: Option<AssetStack>,
+}
+
+/// Stack summary embedded in an asset response.
+#[derive(Debug, Deserialize)]
+pub struct AssetStack {
+    pub id: String,
+}
+
+/// Response of `/api/stacks/{id}`.
+#[derive(Debug, Deserialize)]
+struct StackResponse {
+    assets: Vec<AssetResponse>,
+}
+
+impl AssetResponse {
+    fn archived(&self) -> bool {
+        self.is_archived || self.visibility.as_deref() == Some("archive")
+    }
 }
 
 /// Person info from Immich API (subset of `PersonWithFacesResponseDto`).
@@ -33,6 +72,8 @@ pub struct PersonInfo {
     pub name: String,
     #[serde(default)]
     pub birth_date: Option<String>,
+    #[serde(default)]
+    pub is_hidden: bool,
 }
 
 /// Tag info from Immich API (subset of `TagResponseDto`).
@@ -116,6 +157,27 @@ struct AssetSearchResult {
     next_page: Option<String>,
 }
 
+/// Album entry returned by `/api/albums`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AlbumResponse {
+    album_name: String,
+}
+
+/// Entry returned by `/api/assets/{id}/metadata`.
+#[derive(Debug, Deserialize)]
+struct AssetMetadataItem {
+    key: String,
+    value: serde_json::Value,
+}
+
+/// Response of `/api/users/me`, used to validate API keys.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UserResponse {
+    email: String,
+}
+
 /// Provider for accessing Immich data via the REST API.
 /// Supports multiple API keys for multi-user setups.
 #[derive(Clone)]
@@ -124,12 +186,15 @@ pub struct ImmichApiProvider {
     clients: Vec<Client>,
     /// Base URL of the Immich server (e.g., "<https://immich.example.com>")
     base_url: Url,
+    /// Archived and trashed assets left out of asset searches
+    asset_filter: AssetFilter,
 }
 
 impl std::fmt::Debug for ImmichApiProvider {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("ImmichApiProvider")
             .field("base_url", &self.base_url)
+            .field("asset_filter", &self.asset_filter)
             .field("clients", &format!("{} clients", self.clients.len()))
             .finish()
     }
@@ -143,10 +208,15 @@ impl ImmichApiProvider {
     /// # Arguments
     /// * `base_url` - Base URL of the Immich server
     /// * `api_keys` - List of API keys for authentication (created in Immich web UI)
+    /// * `asset_filter` - Archived and trashed assets to leave out of asset searches
     ///
     /// # Errors
     /// Returns an error if the URL is invalid or any API key contains invalid characters.
-    pub fn new(base_url_str: &str, api_keys: &[String]) -> Result<Self, ImageAnalysisError> {
+    pub fn new(
+        base_url_str: &str,
+        api_keys: &[String],
+        asset_filter: AssetFilter,
+    ) -> Result<Self, ImageAnalysisError> {
         let base_url =
             Url::parse(base_url_str).map_err(|err| ImageAnalysisError::InvalidConfig {
                 error: err.to_string(),
@@ -176,7 +246,11 @@ impl ImmichApiProvider {
             });
         }
 
-        Ok(Self { clients, base_url })
+        Ok(Self {
+            clients,
+            base_url,
+            asset_filter,
+        })
     }
 
     /// Waits for the Immich server to become reachable by pinging `/api/server/ping`.
@@ -260,14 +334,69 @@ impl ImmichApiProvider {
         }
     }
 
+    /// Checks every API key against `/api/users/me`.
+    ///
+    /// # Returns
+    /// The email of the user each key belongs to, in key order (`?` for keys without `user.read`).
+    ///
+    /// # Errors
+    /// Returns an error naming the first key (1-based) that Immich rejects or that could not be checked.
+    pub async fn validate_api_keys(&self) -> Result<Vec<String>, ImageAnalysisError> {
+        let me_url = self.base_url.join("/api/users/me").map_err(|err| {
+            ImageAnalysisError::InvalidConfig {
+                error: err.to_string(),
+            }
+        })?;
+        let mut users = Vec::with_capacity(self.clients.len());
+        for (index, client) in self.clients.iter().enumerate() {
+            let key_number = index.saturating_add(1).to_string();
+            let resp = client.get(me_url.clone()).send().await.map_err(|err| {
+                ImageAnalysisError::HttpClientError {
+                    error: err.to_string(),
+                }
+            })?;
+            let status = resp.status();
+            // Scoped keys without `user.read` are still valid for everything this tool needs
+            if status == StatusCode::FORBIDDEN {
+                warn!("API key #{key_number} lacks the user.read permission, skipping user lookup");
+                users.push("?".to_owned());
+                continue;
+            }
+            if status == StatusCode::UNAUTHORIZED {
+                return Err(ImageAnalysisError::InvalidConfig {
+                    error: rust_i18n::t!(
+                        "error.immich_api_key_rejected",
+                        key_number = key_number,
+                        status = status.to_string()
+                    )
+                    .to_string(),
+                });
+            }
+            if !status.is_success() {
+                return Err(ImageAnalysisError::HttpClientError {
+                    error: format!("GET {me_url} for API key #{key_number} returned HTTP {status}"),
+                });
+            }
+            let user: UserResponse =
+                resp.json()
+                    .await
+                    .map_err(|err| ImageAnalysisError::HttpClientError {
+                        error: err.to_string(),
+                    })?;
+            users.push(user.email);
+        }
+        Ok(users)
+    }
+
     /// Fetches all assets from the Immich library.
     ///
     /// Fully paginates each API key separately (multi-user support).
     /// Tries all keys for each page request on failure.
     ///
     /// # Returns
-    /// Vec<AssetRef> containing all assets with their ID and original path.
-    pub async fn get_assets(&self) -> Result<Vec<AssetRef>, ImageAnalysisError> {
+    /// Vec<AssetRef> containing all assets with their ID and original path, and how many
+    /// archived or trashed assets the asset filter left out.
+    pub async fn get_assets(&self) -> Result<(Vec<AssetRef>, ExcludedAssets), ImageAnalysisError> {
         self.search_assets_paginated(None).await
     }
 
@@ -294,15 +423,20 @@ impl ImmichApiProvider {
         &self,
         since: impl Into<String>,
     ) -> Result<Vec<AssetRef>, ImageAnalysisError> {
-        self.search_assets_paginated(Some(since.into())).await
+        let (assets, _) = self.search_assets_paginated(Some(since.into())).await?;
+        Ok(assets)
     }
 
     /// Shared paginated search across all clients.
+    ///
+    /// Trashed assets are always requested (`withDeleted`) and filtered here, together with
+    /// archived ones, so the assets left out can be counted.
     async fn search_assets_paginated(
         &self,
         since: Option<String>,
-    ) -> Result<Vec<AssetRef>, ImageAnalysisError> {
+    ) -> Result<(Vec<AssetRef>, ExcludedAssets), ImageAnalysisError> {
         let mut all_assets = Vec::new();
+        let mut excluded = ExcludedAssets::default();
 
         let search_url = self.base_url.join("/api/search/metadata").map_err(|err| {
             ImageAnalysisError::InvalidConfig {
@@ -317,6 +451,7 @@ impl ImmichApiProvider {
                     "page": page,
                     "size": Self::PAGE_SIZE,
                     "withExif": true,
+                    "withDeleted": true,
                 });
                 if let Some(since_val) = &since
                     && let Some(obj) = body.as_object_mut()
@@ -355,25 +490,29 @@ impl ImmichApiProvider {
                 }
 
                 let search_result: AssetSearchResponse =
-                    response
-                        .json()
-                        .await
-                        .map_err(|err| ImageAnalysisError::JsonParsing {
-                            filename: "assets_list".to_owned(),
-                            error: err.to_string(),
-                        })?;
+                    Self::parse_json("assets_list", response).await?;
 
                 if search_result.assets.items.is_empty() {
                     break;
                 }
 
                 for item in search_result.assets.items {
+                    if let Some(exclusion) = self
+                        .asset_filter
+                        .exclusion(item.archived(), item.is_trashed)
+                    {
+                        excluded.count(exclusion);
+                        continue;
+                    }
                     let asset_id =
                         Uuid::parse_str(&item.id).map_err(|_| ImageAnalysisError::InvalidUuid {
                             filename: item.id.clone(),
                         })?;
 
-                    all_assets.push(AssetRef { id: asset_id });
+                    all_assets.push(AssetRef {
+                        id: asset_id,
+                        preview_path: None,
+                    });
                 }
 
                 if search_result.assets.next_page.is_none() {
@@ -383,7 +522,7 @@ impl ImmichApiProvider {
             }
         }
 
-        Ok(all_assets)
+        Ok((all_assets, excluded))
     }
 
     /// Gets the filesystem path to the preview image for an asset.
@@ -522,6 +661,141 @@ impl ImmichApiProvider {
         }))
     }
 
+    /// Creates the tag (Immich creates missing parent tags) and links it to an asset.
+    /// Tries all API keys until one succeeds.
+    ///
+    /// # Arguments
+    /// * `asset_id` - UUID of the asset
+    /// * `tag_value` - Full tag path, e.g. `Format/Screenshot`
+    pub async fn tag_asset(
+        &self,
+        asset_id: &Uuid,
+        tag_value: &str,
+    ) -> Result<(), ImageAnalysisError> {
+        let mut last_error = None;
+        for client in &self.clients {
+            match self
+                .tag_asset_with_client(client, asset_id, tag_value)
+                .await
+            {
+                Ok(()) => return Ok(()),
+                Err(err) => last_error = Some(err),
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| ImageAnalysisError::HttpError {
+            status: 0,
+            filename: asset_id.to_string(),
+            response: "No API keys available".to_owned(),
+        }))
+    }
+
+    async fn tag_asset_with_client(
+        &self,
+        client: &Client,
+        asset_id: &Uuid,
+        tag_value: &str,
+    ) -> Result<(), ImageAnalysisError> {
+        #[derive(serde::Serialize)]
+        struct UpsertTagsRequest<'a> {
+            tags: [&'a str; 1],
+        }
+        #[derive(serde::Serialize)]
+        struct TagAssetsRequest {
+            ids: [Uuid; 1],
+        }
+        #[derive(Deserialize)]
+        struct TagResponse {
+            id: String,
+        }
+
+        let tags_url =
+            self.base_url
+                .join("/api/tags")
+                .map_err(|err| ImageAnalysisError::InvalidConfig {
+                    error: err.to_string(),
+                })?;
+        let response = client
+            .put(tags_url)
+            .json(&UpsertTagsRequest { tags: [tag_value] })
+            .send()
+            .await
+            .map_err(|err| Self::request_error(asset_id, &err))?;
+        if !response.status().is_success() {
+            return Err(Self::status_error(asset_id, response).await);
+        }
+        let tags: Vec<TagResponse> = Self::parse_json(&asset_id.to_string(), response).await?;
+        let Some(tag) = tags.first() else {
+            return Err(ImageAnalysisError::JsonParsing {
+                filename: asset_id.to_string(),
+                error: "Tag upsert returned no tags".to_owned(),
+                snippet: "[]".to_owned(),
+            });
+        };
+
+        let assets_url = self
+            .base_url
+            .join(&format!("/api/tags/{}/assets", tag.id))
+            .map_err(|err| ImageAnalysisError::InvalidConfig {
+                error: err.to_string(),
+            })?;
+        let response = client
+            .put(assets_url)
+            .json(&TagAssetsRequest { ids: [*asset_id] })
+            .send()
+            .await
+            .map_err(|err| Self::request_error(asset_id, &err))?;
+        if !response.status().is_success() {
+            return Err(Self::status_error(asset_id, response).await);
+        }
+        Ok(())
+    }
+
+    fn request_error(asset_id: &Uuid, err: &reqwest::Error) -> ImageAnalysisError {
+        ImageAnalysisError::HttpError {
+            status: 0,
+            filename: asset_id.to_string(),
+            response: err.to_string(),
+        }
+    }
+
+    /// Reads a successful response and deserializes its JSON body.
+    ///
+    /// On failure the error carries the start of the body, so a proxy or login page
+    /// answering in place of Immich is easy to recognize.
+    async fn parse_json<T: DeserializeOwned>(
+        filename: &str,
+        resp: reqwest::Response,
+    ) -> Result<T, ImageAnalysisError> {
+        let body = resp
+            .text()
+            .await
+            .map_err(|err| ImageAnalysisError::JsonParsing {
+                filename: filename.to_owned(),
+                error: err.to_string(),
+                snippet: String::new(),
+            })?;
+        serde_json::from_str(&body).map_err(|err| ImageAnalysisError::JsonParsing {
+            filename: filename.to_owned(),
+            error: err.to_string(),
+            snippet: response_snippet(&body),
+        })
+    }
+
+    async fn status_error(asset_id: &Uuid, resp: reqwest::Response) -> ImageAnalysisError {
+        ImageAnalysisError::HttpError {
+            status: resp.status().as_u16(),
+            filename: asset_id.to_string(),
+            response: match resp.text().await {
+                Ok(text) => text,
+                Err(err) => {
+                    warn!("Failed to read response body: {err}");
+                    String::new()
+                }
+            },
+        }
+    }
+
     /// Checks if an asset already has a description via API.
     /// Tries all API keys until one succeeds.
     ///
@@ -545,12 +819,7 @@ impl ImmichApiProvider {
             match response {
                 Ok(resp) if resp.status().is_success() => {
                     let asset: AssetResponse =
-                        resp.json()
-                            .await
-                            .map_err(|err| ImageAnalysisError::JsonParsing {
-                                filename: asset_id.to_string(),
-                                error: err.to_string(),
-                            })?;
+                        Self::parse_json(&asset_id.to_string(), resp).await?;
 
                     return Ok(asset
                         .exif_info
@@ -655,12 +924,7 @@ impl ImmichApiProvider {
             match response {
                 Ok(resp) if resp.status().is_success() => {
                     let metadata: AssetMetadata =
-                        resp.json()
-                            .await
-                            .map_err(|err| ImageAnalysisError::JsonParsing {
-                                filename: asset_id.to_string(),
-                                error: err.to_string(),
-                            })?;
+                        Self::parse_json(&asset_id.to_string(), resp).await?;
 
                     return Ok(metadata);
                 }
@@ -693,4 +957,190 @@ impl ImmichApiProvider {
             response: "No API keys available".to_owned(),
         }))
     }
+
+    /// Gets the IDs of up to `limit` other images stacked with an asset.
+    ///
+    /// Reads the asset's stack from `/api/assets/{id}` and lists its members through
+    /// `/api/stacks/{id}`. Trashed members are left out.
+    pub async fn get_stack_member_ids(
+        &self,
+        asset_id: &Uuid,
+        limit: usize,
+    ) -> Result<Vec<Uuid>, ImageAnalysisError> {
+        let asset: AssetResponse = self
+            .get_json(asset_id, &format!("/api/assets/{asset_id}"))
+            .await?;
+        let Some(stack) = asset.stack else {
+            return Ok(Vec::new());
+        };
+        let members: StackResponse = self
+            .get_json(asset_id, &format!("/api/stacks/{}", stack.id))
+            .await?;
+        Ok(members
+            .assets
+            .into_iter()
+            .filter(|member| !member.is_trashed && member.asset_type.as_deref() == Some("IMAGE"))
+            .filter_map(|member| Uuid::parse_str(&member.id).ok())
+            .filter(|member_id| member_id != asset_id)
+            .take(limit)
+            .collect())
+    }
+
+    /// GETs `path` and deserializes the JSON response, trying each API key until one succeeds.
+    async fn get_json<T: DeserializeOwned>(
+        &self,
+        asset_id: &Uuid,
+        path: &str,
+    ) -> Result<T, ImageAnalysisError> {
+        let url = self
+            .base_url
+            .join(path)
+            .map_err(|err| ImageAnalysisError::InvalidConfig {
+                error: err.to_string(),
+            })?;
+
+        let mut last_error = None;
+        for client in &self.clients {
+            match client.get(url.clone()).send().await {
+                Ok(resp) if resp.status().is_success() => {
+                    return Self::parse_json(&asset_id.to_string(), resp).await;
+                }
+                Ok(resp) => {
+                    last_error = Some(Self::status_error(asset_id, resp).await);
+                }
+                Err(err) => {
+                    last_error = Some(Self::request_error(asset_id, &err));
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| ImageAnalysisError::HttpError {
+            status: 0,
+            filename: asset_id.to_string(),
+            response: "No API keys available".to_owned(),
+        }))
+    }
+
+    /// Gets the names of albums containing an asset.
+    ///
+    /// Tries each API key until one succeeds, since albums are visible per user.
+    pub async fn get_asset_album_names(
+        &self,
+        asset_id: &Uuid,
+    ) -> Result<Vec<String>, ImageAnalysisError> {
+        let mut url =
+            self.base_url
+                .join("/api/albums")
+                .map_err(|err| ImageAnalysisError::InvalidConfig {
+                    error: err.to_string(),
+                })?;
+        url.query_pairs_mut()
+            .append_pair("assetId", &asset_id.to_string());
+
+        let mut last_error = None;
+        for client in &self.clients {
+            match client.get(url.clone()).send().await {
+                Ok(resp) if resp.status().is_success() => {
+                    let albums: Vec<AlbumResponse> =
+                        Self::parse_json(&asset_id.to_string(), resp).await?;
+                    return Ok(albums.into_iter().map(|album| album.album_name).collect());
+                }
+                Ok(resp) => {
+                    last_error = Some(Self::status_error(asset_id, resp).await);
+                }
+                Err(err) => {
+                    last_error = Some(Self::request_error(asset_id, &err));
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| ImageAnalysisError::HttpError {
+            status: 0,
+            filename: asset_id.to_string(),
+            response: "No API keys available".to_owned(),
+        }))
+    }
+
+    /// Gets the description stored under the `immich-analyze` asset metadata key.
+    /// Tries all API keys until one succeeds.
+    pub async fn get_metadata_description(
+        &self,
+        asset_id: &Uuid,
+    ) -> Result<Option<String>, ImageAnalysisError> {
+        let url = self
+            .base_url
+            .join(&format!("/api/assets/{asset_id}/metadata"))
+            .map_err(|err| ImageAnalysisError::InvalidConfig {
+                error: err.to_string(),
+            })?;
+
+        let mut last_error = None;
+        for client in &self.clients {
+            match client.get(url.clone()).send().await {
+                Ok(resp) if resp.status().is_success() => {
+                    let items: Vec<AssetMetadataItem> =
+                        Self::parse_json(&asset_id.to_string(), resp).await?;
+                    return Ok(items
+                        .into_iter()
+                        .find(|item| item.key == METADATA_KEY)
+                        .and_then(|item| {
+                            item.value
+                                .get("description")
+                                .and_then(serde_json::Value::as_str)
+                                .map(str::to_owned)
+                        })
+                        .filter(|desc| !desc.is_empty()));
+                }
+                Ok(resp) => {
+                    last_error = Some(Self::status_error(asset_id, resp).await);
+                }
+                Err(err) => {
+                    last_error = Some(Self::request_error(asset_id, &err));
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| ImageAnalysisError::HttpError {
+            status: 0,
+            filename: asset_id.to_string(),
+            response: "No API keys available".to_owned(),
+        }))
+    }
+
+    /// Stores the description under the `immich-analyze` asset metadata key.
+    /// Tries all API keys until one succeeds.
+    pub async fn update_metadata_description(
+        &self,
+        asset_id: &Uuid,
+        description: &str,
+    ) -> Result<(), ImageAnalysisError> {
+        let url = self
+            .base_url
+            .join(&format!("/api/assets/{asset_id}/metadata"))
+            .map_err(|err| ImageAnalysisError::InvalidConfig {
+                error: err.to_string(),
+            })?;
+        let body = serde_json::json!({
+            "items": [{ "key": METADATA_KEY, "value": { "description": description } }]
+        });
+
+        let mut last_error = None;
+        for client in &self.clients {
+            match client.put(url.clone()).json(&body).send().await {
+                Ok(resp) if resp.status().is_success() => return Ok(()),
+                Ok(resp) => {
+                    last_error = Some(Self::status_error(asset_id, resp).await);
+                }
+                Err(err) => {
+                    last_error = Some(Self::request_error(asset_id, &err));
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| ImageAnalysisError::HttpError {
+            status: 0,
+            filename: asset_id.to_string(),
+            response: "No API keys available".to_owned(),
+        }))
+    }
 }
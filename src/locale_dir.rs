@@ -0,0 +1,57 @@
+use std::{borrow::Cow, collections::BTreeMap, sync::OnceLock};
+
+/// Locale code to flattened translation key to text.
+type Translations = BTreeMap<String, BTreeMap<String, String>>;
+
+static EXTERNAL: OnceLock<Translations> = OnceLock::new();
+
+/// Translations loaded from `--locale-dir`, layered over the compiled-in locales.
+///
+/// Registered as the `i18n!` backend extension: external entries take precedence,
+/// and keys missing from an external file fall back to the built-in text.
+pub struct ExternalLocales;
+
+/// Load every `*.yml`, `*.yaml`, `*.json` and `*.toml` locale file below `dir`.
+///
+/// Files use the same formats as the compiled-in `locales/` directory: one file per locale
+/// named after it (e.g. `de.yml`) or `_version: 2` files with per-key translations.
+/// Returns the locales found.
+pub fn load(dir: &str) -> Result<Vec<String>, String> {
+    let translations = rust_i18n_support::try_load_locales(dir, |_| false, true)?;
+    let locales = translations.keys().cloned().collect();
+    EXTERNAL
+        .set(translations)
+        .map_err(|_| "locale directory already loaded".to_owned())?;
+    Ok(locales)
+}
+
+impl rust_i18n::Backend for ExternalLocales {
+    fn available_locales(&self) -> Vec<Cow<'_, str>> {
+        EXTERNAL
+            .get()
+            .map(|translations| {
+                translations
+                    .keys()
+                    .map(|locale| Cow::Borrowed(locale.as_str()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn translate(&self, locale: &str, key: &str) -> Option<Cow<'_, str>> {
+        EXTERNAL
+            .get()?
+            .get(locale)?
+            .get(key)
+            .map(|text| Cow::Borrowed(text.as_str()))
+    }
+
+    fn messages_for_locale(&self, locale: &str) -> Option<Vec<(Cow<'_, str>, Cow<'_, str>)>> {
+        EXTERNAL.get()?.get(locale).map(|messages| {
+            messages
+                .iter()
+                .map(|(key, text)| (Cow::Borrowed(key.as_str()), Cow::Borrowed(text.as_str())))
+                .collect()
+        })
+    }
+}
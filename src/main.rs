@@ -1,50 +1,226 @@
 #![warn(non_ascii_idents)]
 
-use clap::Parser as _;
-use std::{path::Path, sync::Arc};
+use clap::{
+    ArgMatches, CommandFactory as _, FromArgMatches as _, ValueEnum as _, parser::ValueSource,
+};
+use futures::StreamExt as _;
+use std::{
+    io::IsTerminal as _,
+    path::Path,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
 use tokio_postgres::NoTls;
 
+mod anthropic;
 mod args;
 mod config;
 mod data_access;
 mod database;
 mod error;
+mod events;
+mod failure_cache;
 mod file_processing;
 mod health;
 mod host_manager;
 mod immich_api;
+mod locale_dir;
 mod monitor;
+mod pause;
+mod post_hook;
 mod progress;
 mod prompt_enricher;
+mod rate_limit;
+mod results_db;
 mod utils;
+mod webhook;
 
-use args::{Args, OverwritePolicy};
-use config::MonitorConfig;
+use args::{Args, CoverageTarget, DescriptionColumn, Interface, OutputFormat, OverwritePolicy};
+use config::{AnalysisOptions, MonitorConfig, RunState};
 use data_access::{DataAccess, DataAccessMode};
-use file_processing::process_files_concurrently;
+use error::ImageAnalysisError;
+use events::{Event, outln};
+use failure_cache::FailureCache;
+use file_processing::{DiscoveryStats, process_files_concurrently};
+use host_manager::HostManager;
 use monitor::monitor_folder;
+use pause::Pause;
+use post_hook::PostHook;
 use progress::SimpleProgress;
-use utils::{determine_locale, get_system_locale, validate_args, validate_immich_directory};
+use rand::{SeedableRng as _, rngs::StdRng};
+use results_db::ResultsDb;
+use utils::{
+    build_http_client, determine_locale, expand_env_vars, get_system_locale, load_immich_env,
+    normalize_hosts, postgres_address, read_hosts_file, read_secret_file, redact_postgres_url,
+    redact_url, validate_args, validate_immich_directory,
+};
+use webhook::WebhookNotifier;
 
-rust_i18n::i18n!("locales", fallback = "en");
+rust_i18n::i18n!(
+    "locales",
+    fallback = "en",
+    backend = locale_dir::ExternalLocales
+);
+
+const MODEL_CHECK_TIMEOUT_SECS: u64 = 10;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Initialize logger to enable debug logging
-    env_logger::init();
+    let matches = Args::command().get_matches();
+    let mut args = Args::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    init_logger(args.verbose);
+    progress::set_plain_output(args.plain || !std::io::stdout().is_terminal());
+    events::set_json_output(args.output_format == OutputFormat::Json);
 
+    // External locales must be loaded before the available locales are listed
+    let external_locales = args.locale_dir.as_deref().map(locale_dir::load);
     let system_locale = get_system_locale();
     let available_locales = rust_i18n::available_locales!();
-    let args = Args::parse();
 
     let final_locale = determine_locale(&args.lang, &system_locale, &available_locales);
     rust_i18n::set_locale(&final_locale);
-    println!(
+    outln!(
         "{}",
         rust_i18n::t!("autodetect.locale_selected", locale = final_locale)
     );
+    if let (Some(dir), Some(loaded)) = (&args.locale_dir, external_locales) {
+        match loaded {
+            Ok(locales) => outln!(
+                "{}",
+                rust_i18n::t!(
+                    "autodetect.locale_dir_loaded",
+                    path = dir,
+                    locales = locales.join(", ")
+                )
+            ),
+            Err(err) => {
+                eprintln!(
+                    "{}",
+                    rust_i18n::t!("error.locale_dir_invalid", path = dir, error = err)
+                );
+                std::process::exit(1);
+            }
+        }
+    }
 
+    if let Some(env_path) = args.immich_env.clone() {
+        apply_immich_env(&mut args, &matches, &env_path).await?;
+    }
+    if let Some(hosts_file) = &args.hosts_file {
+        let file_hosts = read_hosts_file(hosts_file).await?;
+        let count = file_hosts.len();
+        if matches.value_source("hosts") == Some(ValueSource::DefaultValue) {
+            args.hosts = file_hosts;
+        } else {
+            args.hosts.extend(file_hosts);
+        }
+        outln!(
+            "{}",
+            rust_i18n::t!(
+                "main.hosts_file_loaded",
+                path = hosts_file,
+                count = count.to_string()
+            )
+        );
+    }
+    args.prompt = expand_env_vars(&args.prompt);
+    args.hosts = args
+        .hosts
+        .iter()
+        .map(|host| expand_env_vars(host))
+        .collect();
     validate_args(&args)?;
+    match normalize_hosts(&args.hosts) {
+        Ok(hosts) => args.hosts = hosts,
+        Err(host) => {
+            eprintln!("{}", rust_i18n::t!("error.invalid_host_url", host = host));
+            std::process::exit(1);
+        }
+    }
+
+    let webhook = args
+        .webhook_url
+        .as_deref()
+        .map(|url| {
+            WebhookNotifier::new(
+                url,
+                args.webhook_host_failures,
+                std::time::Duration::from_secs(args.webhook_host_failure_window),
+            )
+            .map(Arc::new)
+        })
+        .transpose()?;
+
+    if args.diff {
+        outln!(
+            "{}",
+            if args.apply {
+                rust_i18n::t!("main.diff_applying")
+            } else {
+                rust_i18n::t!("main.diff_preview_only")
+            }
+        );
+    }
+
+    let mut run_state = RunState {
+        pause: Some(Pause::start(args.pause_file.clone())),
+        // Nothing is stored when only previewing changes, so there is nothing to hook into
+        post_hook: args
+            .post_hook
+            .as_ref()
+            .filter(|_| !args.diff || args.apply)
+            .map(|command| {
+                Arc::new(PostHook::new(
+                    command,
+                    args.post_hook_concurrency,
+                    std::time::Duration::from_secs(args.post_hook_timeout),
+                ))
+            }),
+        ..RunState::default()
+    };
+
+    let results_db = if let Some(results_path) = &args.results_db {
+        let results_db = ResultsDb::open(results_path, args.write_target, &args.model_name).await?;
+        outln!(
+            "{}",
+            rust_i18n::t!(
+                "main.results_db_opened",
+                path = results_path.display().to_string(),
+                target = args
+                    .write_target
+                    .to_possible_value()
+                    .map(|value| value.get_name().to_owned())
+                    .unwrap_or_default()
+            )
+        );
+        Some(results_db)
+    } else {
+        None
+    };
+
+    // Single-asset runs are for debugging, so earlier failures must not skip the asset
+    if let Some(cache_path) = &args.failure_cache
+        && args.only_asset.is_empty()
+    {
+        let failure_cache = FailureCache::load(
+            cache_path,
+            args.max_attempts,
+            std::time::Duration::from_hours(args.failure_cooldown_hours),
+            args.retry_failed,
+        )
+        .await?;
+        outln!(
+            "{}",
+            rust_i18n::t!(
+                "main.failure_cache_loaded",
+                path = cache_path.as_str(),
+                count = failure_cache.len().await.to_string()
+            )
+        );
+        run_state.failure_cache = Some(Arc::new(failure_cache));
+    }
 
     // Start health check HTTP server for Docker HEALTHCHECK
     let health_port = args.health_port;
@@ -55,8 +231,35 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Create data access based on mode
     let data_access = match args.data_access_mode {
         DataAccessMode::Database => {
+            let mut pg_config: tokio_postgres::Config = args.postgres_url.parse()?;
+            if let Some(password_file) = &args.db_password_file {
+                pg_config.password(read_secret_file(password_file).await?);
+            }
+            let connect_timeout = std::time::Duration::from_secs(args.db_connect_timeout);
             let (pg_client, connection) =
-                tokio_postgres::connect(&args.postgres_url, NoTls).await?;
+                match tokio::time::timeout(connect_timeout, pg_config.connect(NoTls)).await {
+                    Ok(Ok(connected)) => connected,
+                    Ok(Err(err)) => {
+                        let error = format!("{} ({err})", postgres_address(&pg_config));
+                        eprintln!(
+                            "{}",
+                            rust_i18n::t!("error.postgres_connection_error", error = error)
+                        );
+                        std::process::exit(1);
+                    }
+                    Err(_elapsed) => {
+                        let error = rust_i18n::t!(
+                            "error.postgres_connect_timeout",
+                            address = postgres_address(&pg_config),
+                            seconds = args.db_connect_timeout.to_string()
+                        );
+                        eprintln!(
+                            "{}",
+                            rust_i18n::t!("error.postgres_connection_error", error = error)
+                        );
+                        std::process::exit(1);
+                    }
+                };
             tokio::spawn(async move {
                 if let Err(err) = connection.await {
                     eprintln!(
@@ -66,9 +269,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 }
             });
             let pg_client_arc = Arc::new(pg_client);
-            println!(
+            outln!(
                 "{}",
-                rust_i18n::t!("main.postgres_connected", url = args.postgres_url)
+                rust_i18n::t!(
+                    "main.postgres_connected",
+                    url = redact_postgres_url(&args.postgres_url)
+                )
             );
             if let Err(err) = database::check_database_connection(&pg_client_arc).await {
                 eprintln!(
@@ -77,9 +283,36 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 );
                 std::process::exit(1);
             }
+            let options = AnalysisOptions::from_args(&args);
+            // With --write-target results-db, Immich's database is only read
+            if results_db.as_ref().is_none_or(ResultsDb::writes_immich)
+                && let Err(err) = database::check_write_permissions(
+                    &pg_client_arc,
+                    &options.output,
+                    options.format_tag_prefix.is_some(),
+                )
+                .await
+            {
+                eprintln!("{}", err.user_message());
+                std::process::exit(1);
+            }
+            if args.write_run_log
+                && let Err(err) = database::ensure_run_log_table(&pg_client_arc).await
+            {
+                eprintln!("{}", err.user_message());
+                std::process::exit(1);
+            }
             let immich_root = Path::new(&args.immich_root);
-            validate_immich_directory(immich_root)?;
-            DataAccess::new_database(pg_client_arc, immich_root.to_path_buf())
+            validate_immich_directory(immich_root, &args.thumbs_subdir).await?;
+            DataAccess::new_database(
+                pg_client_arc,
+                immich_root.to_path_buf(),
+                &args.thumbs_subdir,
+                &args.preview_extensions,
+                args.use_original_fallback,
+                args.asset_filter(),
+                results_db,
+            )
         }
         DataAccessMode::ImmichApi => {
             let api_url = args.immich_api_url.as_ref().ok_or(
@@ -88,71 +321,281 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             if args.immich_api_keys.is_empty() {
                 return Err("IMMICH_API_KEY required for API mode. Set via --immich-api-keys or IMMICH_API_KEY env var (comma-separated for multiple keys)".into());
             }
-            let provider = immich_api::ImmichApiProvider::new(api_url, &args.immich_api_keys)?;
+            let provider = immich_api::ImmichApiProvider::new(
+                api_url,
+                &args.immich_api_keys,
+                args.asset_filter(),
+            )?;
             if !args.no_wait_for_immich {
                 let timeout_display = if args.wait_timeout == 0 {
                     "∞".to_owned()
                 } else {
                     args.wait_timeout.to_string()
                 };
-                println!(
+                outln!(
                     "{}",
                     rust_i18n::t!("main.waiting_for_immich", timeout = timeout_display)
                 );
                 provider
                     .wait_until_ready(args.wait_timeout, args.wait_retry_interval)
                     .await?;
-                println!("{}", rust_i18n::t!("main.immich_ready"));
+                outln!("{}", rust_i18n::t!("main.immich_ready"));
+            }
+            match provider.validate_api_keys().await {
+                Ok(users) => {
+                    for (index, email) in users.iter().enumerate() {
+                        outln!(
+                            "{}",
+                            rust_i18n::t!(
+                                "main.immich_api_key_valid",
+                                key_number = index.saturating_add(1).to_string(),
+                                user = email
+                            )
+                        );
+                    }
+                }
+                Err(err) => {
+                    eprintln!("{}", err.user_message());
+                    std::process::exit(1);
+                }
             }
-            println!(
+            outln!(
                 "{}",
                 rust_i18n::t!(
                     "main.immich_api_connected",
-                    api_url = api_url,
+                    api_url = redact_url(api_url),
                     key_count = args.immich_api_keys.len().to_string()
                 )
             );
-            DataAccess::new_api(Arc::new(provider))
+            DataAccess::new_api(Arc::new(provider), results_db)
         }
     };
 
-    if args.combined {
-        run_combined_mode(args.clone(), &data_access, &final_locale).await?;
+    // Batch and monitor modes both dispatch through HostManager with this interface
+    outln!(
+        "{}",
+        rust_i18n::t!(
+            "main.ai_interface",
+            interface = format!("{:?}", args.interface).to_lowercase(),
+            hosts = args
+                .hosts
+                .iter()
+                .map(|host| redact_url(host))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    );
+    if let Some(proxy) = &args.proxy {
+        outln!(
+            "{}",
+            rust_i18n::t!("main.using_proxy", url = redact_url(proxy))
+        );
+    }
+    // Shared by batch and monitor processing so both honor the same proxy settings
+    let http_client = build_http_client(
+        std::time::Duration::from_secs(args.timeout),
+        std::time::Duration::from_secs(args.connect_timeout),
+        args.proxy.as_deref(),
+        &args.no_proxy_for,
+        &args.user_agent,
+        args.host_headers(),
+    )?;
+
+    let check_client = build_http_client(
+        std::time::Duration::from_secs(MODEL_CHECK_TIMEOUT_SECS),
+        std::time::Duration::from_secs(args.connect_timeout),
+        args.proxy.as_deref(),
+        &args.no_proxy_for,
+        &args.user_agent,
+        args.host_headers(),
+    )?;
+    if args.require_all_hosts && args.interface != Interface::Mock {
+        outln!(
+            "{}",
+            rust_i18n::t!("main.checking_hosts", count = args.hosts.len().to_string())
+        );
+        if let Err(err) = host_manager::verify_hosts_reachable(
+            &check_client,
+            &args.hosts,
+            args.interface,
+            args.api_key.as_deref(),
+            args.endpoint_path.as_deref(),
+        )
+        .await
+        {
+            eprintln!("{}", err.user_message());
+            std::process::exit(1);
+        }
+    }
+    if !args.skip_model_check && args.interface != Interface::Mock {
+        outln!(
+            "{}",
+            rust_i18n::t!("main.checking_model", name = args.model_name)
+        );
+        if let Err(err) = host_manager::verify_model_available(
+            &check_client,
+            &args.hosts,
+            args.interface,
+            &args.model_name,
+            args.api_key.as_deref(),
+            args.endpoint_path.as_deref(),
+        )
+        .await
+        {
+            eprintln!("{}", err.user_message());
+            std::process::exit(1);
+        }
+    }
+
+    if args.print_prompt {
+        outln!(
+            "{}",
+            rust_i18n::t!("main.resolved_prompt", prompt = args.effective_prompt())
+        );
+    }
+
+    // One host manager for every mode, so host availability is shared between batch and monitor
+    let host_manager = Arc::new(HostManager::from_args(&args, http_client, webhook.clone()));
+    if args.host_status_interval > 0 {
+        host_manager::spawn_host_status_log(
+            Arc::clone(&host_manager),
+            std::time::Duration::from_secs(args.host_status_interval),
+        );
+    }
+    let result = if args.combined {
+        run_combined_mode(
+            args.clone(),
+            &data_access,
+            &host_manager,
+            &run_state,
+            webhook.as_ref(),
+            &final_locale,
+        )
+        .await
     } else if args.monitor {
-        run_monitor_mode(&args, &data_access, &final_locale).await?;
+        run_monitor_mode(
+            &args,
+            &data_access,
+            &host_manager,
+            &run_state,
+            &final_locale,
+        )
+        .await
     } else {
-        run_batch_mode(&args, &data_access, &final_locale).await?;
+        run_batch_mode(
+            &args,
+            &data_access,
+            &host_manager,
+            &run_state,
+            webhook.as_deref(),
+            &final_locale,
+        )
+        .await
+    };
+    // Hooks still running for the last files would be killed by exiting on an error
+    if let Some(post_hook) = &run_state.post_hook {
+        post_hook.wait_idle().await;
+    }
+    if let Err(err) = result {
+        // Print the localized message once instead of letting main debug-format the error
+        let message = err
+            .downcast_ref::<ImageAnalysisError>()
+            .map_or_else(|| err.to_string(), ImageAnalysisError::user_message);
+        eprintln!("{message}");
+        std::process::exit(1);
     }
 
     Ok(())
 }
 
+/// Fill in --postgres-url and --immich-root from Immich's `.env`, unless given on the command line.
+async fn apply_immich_env(
+    args: &mut Args,
+    matches: &ArgMatches,
+    env_path: &str,
+) -> Result<(), ImageAnalysisError> {
+    let immich_env = load_immich_env(env_path).await?;
+    let from_default = |id: &str| matches.value_source(id) == Some(ValueSource::DefaultValue);
+    if from_default("postgres_url") {
+        args.postgres_url = immich_env.postgres_url;
+    }
+    if let Some(upload_location) = immich_env.upload_location
+        && from_default("immich_root")
+    {
+        args.immich_root = upload_location;
+    }
+    outln!(
+        "{}",
+        rust_i18n::t!(
+            "main.immich_env_loaded",
+            path = env_path,
+            url = redact_postgres_url(&args.postgres_url),
+            root = args.immich_root
+        )
+    );
+    Ok(())
+}
+
+/// Initializes logging from `-v` flags, letting an explicit `RUST_LOG` override them.
+fn init_logger(verbosity: u8) {
+    let level = match verbosity {
+        0 => log::LevelFilter::Error,
+        1 => log::LevelFilter::Info,
+        2 => log::LevelFilter::Debug,
+        _ => log::LevelFilter::Trace,
+    };
+    env_logger::Builder::new()
+        .filter_level(level)
+        .parse_default_env()
+        .init();
+}
+
 async fn run_combined_mode(
     args: Args,
     data_access: &DataAccess,
+    host_manager: &Arc<HostManager>,
+    run_state: &RunState,
+    webhook: Option<&Arc<WebhookNotifier>>,
     locale: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    println!("{}", rust_i18n::t!("main.combined_mode_activated"));
+    outln!("{}", rust_i18n::t!("main.combined_mode_activated"));
     let batch_handle = {
         let args_clone = args.clone();
         let data_access_clone = data_access.clone();
+        let host_manager_clone = Arc::clone(host_manager);
+        let run_state_clone = run_state.clone();
+        let webhook_clone = webhook.cloned();
         let locale_clone = locale.to_owned();
         tokio::spawn(async move {
-            println!("{}", rust_i18n::t!("main.processing_existing_images"));
-            if let Err(err) = run_batch_mode(&args_clone, &data_access_clone, &locale_clone).await {
+            outln!("{}", rust_i18n::t!("main.processing_existing_images"));
+            if let Err(err) = run_batch_mode(
+                &args_clone,
+                &data_access_clone,
+                &host_manager_clone,
+                &run_state_clone,
+                webhook_clone.as_deref(),
+                &locale_clone,
+            )
+            .await
+            {
                 eprintln!(
                     "{}",
                     rust_i18n::t!("error.batch_mode_failed", error = err.to_string())
                 );
             }
-            println!("{}", rust_i18n::t!("main.batch_mode_completed"));
+            outln!("{}", rust_i18n::t!("main.batch_mode_completed"));
         })
     };
-    println!(
+    outln!(
         "{}",
         rust_i18n::t!("main.monitor_mode_started_in_background")
     );
-    run_monitor_mode(&args, data_access, locale).await?;
+    // Both halves share one host manager, so host availability is tracked process-wide
+    log::info!(
+        "Combined mode sharing {} available AI hosts between batch and monitor",
+        host_manager.available_host_count()
+    );
+    run_monitor_mode(&args, data_access, host_manager, run_state, locale).await?;
     let _: Result<(), tokio::task::JoinError> = batch_handle.await;
     Ok(())
 }
@@ -160,78 +603,285 @@ async fn run_combined_mode(
 async fn run_monitor_mode(
     args: &Args,
     data_access: &DataAccess,
+    host_manager: &Arc<HostManager>,
+    run_state: &RunState,
     locale: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    println!("{}", rust_i18n::t!("main.monitor_mode_activated"));
+    outln!("{}", rust_i18n::t!("main.monitor_mode_activated"));
     let overwrite_policy = args.effective_overwrite_policy();
     match overwrite_policy {
-        OverwritePolicy::All => println!("{}", rust_i18n::t!("main.ignore_existing_enabled")),
-        OverwritePolicy::MissingAi => println!("{}", rust_i18n::t!("main.missing_ai_enabled")),
+        OverwritePolicy::All => outln!("{}", rust_i18n::t!("main.ignore_existing_enabled")),
+        OverwritePolicy::MissingAi => outln!("{}", rust_i18n::t!("main.missing_ai_enabled")),
         OverwritePolicy::None => {}
     }
+    if args.format_only {
+        outln!(
+            "{}",
+            rust_i18n::t!("main.format_only_enabled", prefix = args.tag_prefix)
+        );
+    } else if args.description_column == DescriptionColumn::AssetMetadata {
+        outln!(
+            "{}",
+            rust_i18n::t!("main.asset_metadata_enabled", key = database::METADATA_KEY)
+        );
+    }
     let monitor_config = MonitorConfig::from_args(args, locale);
     monitor_folder(
-        &args.model_name,
         data_access.clone(),
-        &args.prompt,
+        Arc::clone(host_manager),
+        run_state.clone(),
+        &args.effective_prompt(),
         &monitor_config,
     )
     .await?;
     Ok(())
 }
 
+/// Print what discovery left out and how many images it queued for processing.
+fn report_discovery(discovered: &DiscoveryStats) {
+    if discovered.excluded.archived > 0 {
+        outln!(
+            "{}",
+            rust_i18n::t!(
+                "main.skipped_archived",
+                count = discovered.excluded.archived.to_string()
+            )
+        );
+    }
+    if discovered.excluded.trashed > 0 {
+        outln!(
+            "{}",
+            rust_i18n::t!(
+                "main.skipped_trashed",
+                count = discovered.excluded.trashed.to_string()
+            )
+        );
+    }
+    if discovered.already_described > 0 {
+        outln!(
+            "{}",
+            rust_i18n::t!(
+                "main.skipped_already_described",
+                count = discovered.already_described.to_string()
+            )
+        );
+    }
+    if discovered.already_indexed > 0 {
+        outln!(
+            "{}",
+            rust_i18n::t!(
+                "main.skipped_already_indexed",
+                count = discovered.already_indexed.to_string()
+            )
+        );
+    }
+    outln!(
+        "{}",
+        rust_i18n::t!(
+            "main.images_to_process",
+            count = discovered.queued.to_string()
+        )
+    );
+}
+
 async fn run_batch_mode(
     args: &Args,
     data_access: &DataAccess,
+    host_manager: &Arc<HostManager>,
+    run_state: &RunState,
+    webhook: Option<&WebhookNotifier>,
     locale: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    println!(
+    Event::Started { mode: "batch" }.emit();
+    let started_at = std::time::SystemTime::now();
+    outln!(
         "{}",
         rust_i18n::t!("main.database_connected", path = "Immich data source")
     );
 
-    let assets = data_access.get_assets_to_process().await?;
-
-    println!(
-        "{}",
-        rust_i18n::t!("main.images_to_process", count = assets.len().to_string())
-    );
-    println!(
+    outln!(
         "{}",
         rust_i18n::t!("main.model_name", name = args.model_name)
     );
-    println!(
+    outln!(
         "{}",
         rust_i18n::t!(
             "main.max_concurrent",
             count = args.max_concurrent.to_string()
         )
     );
-    println!(
+    outln!(
         "{}",
         rust_i18n::t!("main.timeout", seconds = args.timeout.to_string())
     );
     let overwrite_policy = args.effective_overwrite_policy();
     match overwrite_policy {
-        OverwritePolicy::All => println!("{}", rust_i18n::t!("main.ignore_existing_enabled")),
-        OverwritePolicy::MissingAi => println!("{}", rust_i18n::t!("main.missing_ai_enabled")),
+        OverwritePolicy::All => outln!("{}", rust_i18n::t!("main.ignore_existing_enabled")),
+        OverwritePolicy::MissingAi => outln!("{}", rust_i18n::t!("main.missing_ai_enabled")),
         OverwritePolicy::None => {}
     }
+    if args.format_only {
+        outln!(
+            "{}",
+            rust_i18n::t!("main.format_only_enabled", prefix = args.tag_prefix)
+        );
+    } else if args.description_column == DescriptionColumn::AssetMetadata {
+        outln!(
+            "{}",
+            rust_i18n::t!("main.asset_metadata_enabled", key = database::METADATA_KEY)
+        );
+    }
 
-    let http_client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(args.timeout))
-        .build()?;
-
-    let progress = Arc::new(tokio::sync::Mutex::new(SimpleProgress::new(
-        assets.len() as u64,
+    let progress = Arc::new(tokio::sync::Mutex::new(SimpleProgress::new_discovering(
         &rust_i18n::t!("progress.processing_complete"),
     )));
 
-    let results =
-        process_files_concurrently(assets, &http_client, data_access, args, locale, progress).await;
+    let shuffle_rng = args.shuffle.then(|| {
+        outln!("{}", rust_i18n::t!("main.shuffle_enabled"));
+        args.shuffle_seed
+            .map_or_else(StdRng::from_os_rng, StdRng::seed_from_u64)
+    });
+    if let Some(sort_by) = args.sort_by {
+        outln!(
+            "{}",
+            rust_i18n::t!("main.sort_enabled", order = format!("{sort_by:?}"))
+        );
+    }
+    // With the default policy, described assets are filtered in batches instead of one query each
+    let skip_described = overwrite_policy == OverwritePolicy::None
+        && !args.format_only
+        && args.description_column == DescriptionColumn::Exif
+        && matches!(data_access, DataAccess::Database { .. });
+    let covered_by = match args.coverage_target {
+        CoverageTarget::SmartSearch => {
+            outln!("{}", rust_i18n::t!("main.coverage_smart_search"));
+            Some(CoverageTarget::SmartSearch)
+        }
+        CoverageTarget::Description => skip_described.then_some(CoverageTarget::Description),
+    };
+    // Discovery runs alongside processing so work starts with the first found asset
+    let (assets, discovery) = if args.only_asset.is_empty() {
+        file_processing::spawn_asset_discovery(
+            data_access.clone(),
+            Arc::clone(&progress),
+            shuffle_rng,
+            args.sort_by,
+            args.resume_from_uuid,
+            covered_by,
+        )
+    } else {
+        file_processing::spawn_listed_assets(&args.only_asset, Arc::clone(&progress))
+    };
+    // Past --max-runtime no new assets are handed out, files already started still finish
+    let runtime_exceeded = Arc::new(AtomicBool::new(false));
+    let deadline = args
+        .max_runtime
+        .and_then(|max_runtime| tokio::time::Instant::now().checked_add(max_runtime));
+    let stop = {
+        let exceeded = Arc::clone(&runtime_exceeded);
+        async move {
+            match deadline {
+                Some(deadline_at) => {
+                    tokio::time::sleep_until(deadline_at).await;
+                    exceeded.store(true, Ordering::Relaxed);
+                }
+                None => std::future::pending().await,
+            }
+        }
+    };
+    // Report discovery as soon as it finishes, while the remaining files are still processed
+    let report = async {
+        let finished = discovery.await;
+        if let Ok(Ok(discovered)) = &finished {
+            report_discovery(discovered);
+        }
+        finished
+    };
+    let (results, discovery_result) = tokio::join!(
+        process_files_concurrently(
+            assets.take_until(stop),
+            host_manager,
+            data_access,
+            run_state,
+            args,
+            locale,
+            progress,
+        ),
+        report
+    );
+    discovery_result??;
+    if let Some(max_runtime) = args.max_runtime
+        && runtime_exceeded.load(Ordering::Relaxed)
+    {
+        outln!(
+            "{}",
+            rust_i18n::t!(
+                "main.max_runtime_reached",
+                seconds = max_runtime.as_secs().to_string()
+            )
+        );
+    }
 
-    if !args.no_final_output {
+    let summary = file_processing::summarize_results(&results);
+    if events::json_output() {
+        Event::Summary {
+            successful: summary.successful,
+            failed: summary.failed,
+            skipped: summary.skipped,
+        }
+        .emit();
+    } else if !args.no_final_output || !args.only_asset.is_empty() {
         file_processing::display_results(&results, args.max_concurrent > 1);
     }
+    if let Some(csv_path) = &args.output_csv {
+        // The batch itself finished, so a failed export must not skip the steps below
+        match file_processing::write_results_csv(&results, csv_path).await {
+            Ok(()) => outln!(
+                "{}",
+                rust_i18n::t!("main.csv_written", path = csv_path.as_str())
+            ),
+            Err(err) => eprintln!("{}", err.user_message()),
+        }
+    }
+    if let Some(notifier) = webhook {
+        notifier.notify_batch_complete(&summary).await;
+    }
+    if args.write_run_log {
+        let run = database::RunLogEntry {
+            started_at,
+            finished_at: std::time::SystemTime::now(),
+            processed: summary.successful,
+            failed: summary.failed,
+            skipped: summary.skipped,
+            model: args.model_name.clone(),
+            hosts: args.hosts.iter().map(|host| redact_url(host)).collect(),
+        };
+        if let Err(err) = data_access.record_run(&run).await {
+            eprintln!("{}", err.user_message());
+        }
+    }
+    if let Some(threshold) = args.fail_exit_threshold
+        && summary.failed_percent() > f64::from(threshold)
+    {
+        return Err(rust_i18n::t!(
+            "error.fail_exit_threshold_exceeded",
+            failed = summary.failed.to_string(),
+            total = summary
+                .successful
+                .saturating_add(summary.failed)
+                .to_string(),
+            percent = format!("{:.1}", summary.failed_percent()),
+            threshold = threshold.to_string()
+        )
+        .into());
+    }
+    // A hard error under --fail-fast makes the run exit with a failure status
+    if args.fail_fast
+        && let Some(err) = results
+            .iter()
+            .find_map(|(_, result)| result.as_ref().err().filter(|err| err.is_fatal()))
+    {
+        return Err(err.clone().into());
+    }
     Ok(())
 }
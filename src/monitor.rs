@@ -1,69 +1,138 @@
 use crate::{
-    config::{MonitorConfig, ProcessingContext},
+    args::StabilityMode,
+    config::{MonitorConfig, ProcessingContext, RunState},
     data_access::DataAccess,
     error::ImageAnalysisError,
+    events::{self, Event, outln},
     health::mark_activity,
     host_manager::HostManager,
-    immich_api::ImmichApiProvider,
-    prompt_enricher::enrich_prompt_if_needed,
+    immich_api::{AssetRef, ImmichApiProvider},
+    prompt_enricher::prepare_prompt,
     utils::{
-        OverwriteDecision, build_final_description, check_overwrite_policy,
-        extract_uuid_from_preview_filename, filename_from_path, is_preview_filename,
+        OverwriteDecision, check_overwrite_policy, cleanup_related_previews,
+        extract_asset_id_from_preview_path, filename_from_path, is_preview_filename,
+        related_previews, store_analysis_result,
     },
 };
-use log::{error, warn};
+use futures::stream::{self, StreamExt as _};
+use log::{debug, error, info, warn};
 use notify::{
-    event::ModifyKind,
+    event::{ModifyKind, RenameMode},
     {Config, EventKind, RecommendedWatcher, RecursiveMode, Watcher as _},
 };
-use reqwest::Client;
 use std::{
     collections::{HashMap, HashSet},
-    path::Path,
+    io::SeekFrom,
+    os::unix::fs::MetadataExt as _,
+    path::{Path, PathBuf},
     sync::{
         Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
         mpsc::{self, Receiver, Sender},
     },
     time::{Duration, Instant},
 };
 use tokio::{
+    io::{AsyncReadExt as _, AsyncSeekExt as _},
     signal::unix::{SignalKind, signal},
-    sync::mpsc as tokio_mpsc,
+    sync::{OwnedSemaphorePermit, Semaphore, mpsc as tokio_mpsc},
+    task::JoinSet,
     time::MissedTickBehavior,
 };
 use uuid::Uuid;
 
+/// How often the watched thumbs directory is checked for a remount.
+const WATCHER_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// How often a "watcher alive" line is printed in database monitor mode.
+const WATCHER_HEARTBEAT_INTERVAL: Duration = Duration::from_mins(15);
+/// First and longest delay between attempts to re-watch the thumbs directory.
+const WATCHER_RETRY_MIN: Duration = Duration::from_secs(1);
+const WATCHER_RETRY_MAX: Duration = Duration::from_mins(5);
+
+/// Bytes at the end of a JPEG searched for the end-of-image marker, to allow for padding.
+const JPEG_TAIL_BYTES: u64 = 16;
+
+/// Whether the file at `path` is a complete JPEG, i.e. ends with the `FFD9` end-of-image
+/// marker, ignoring zero padding. Files that are not JPEGs always count as complete.
+async fn jpeg_complete(path: &Path) -> bool {
+    let Ok(mut file) = tokio::fs::File::open(path).await else {
+        return false;
+    };
+    let mut header = [0_u8; 2];
+    if file.read_exact(&mut header).await.is_err() {
+        return false;
+    }
+    if header != [0xFF, 0xD8] {
+        return true;
+    }
+    let Ok(len) = file.seek(SeekFrom::End(0)).await else {
+        return false;
+    };
+    let tail_start = len.saturating_sub(JPEG_TAIL_BYTES);
+    let mut tail = Vec::new();
+    if file.seek(SeekFrom::Start(tail_start)).await.is_err()
+        || file.read_to_end(&mut tail).await.is_err()
+    {
+        return false;
+    }
+    let end = tail
+        .iter()
+        .rposition(|byte| *byte != 0)
+        .map_or(0, |last| last.saturating_add(1));
+    tail.get(..end)
+        .is_some_and(|data| data.ends_with(&[0xFF, 0xD9]))
+}
+
 /// Process new file with stability checking using `data_access` abstraction.
+///
+/// `complete` skips the stability checks for a file already known to be fully written.
 pub async fn process_new_file(
     ctx: &ProcessingContext<'_>,
     preview_path: &Path,
-    file_write_timeout: u64,
-    file_check_interval: u64,
+    config: &MonitorConfig,
+    complete: bool,
 ) -> Result<(), ImageAnalysisError> {
-    let data_access = ctx.data_access;
-
     let filename = filename_from_path(preview_path);
-    println!(
-        "{}",
-        rust_i18n::t!("monitor.file_detected", filename = filename)
-    );
+    Event::FileProcessing {
+        filename: &filename,
+    }
+    .emit();
+    let result = analyze_new_file(ctx, preview_path, &filename, config, complete).await;
+    if let Err(err) = &result {
+        events::emit_failure(&filename, err);
+    }
+    result
+}
+
+/// Wait until the size and modification time of `preview_path` stop changing.
+async fn wait_until_stable(
+    preview_path: &Path,
+    filename: &str,
+    config: &MonitorConfig,
+) -> Result<(), ImageAnalysisError> {
+    let file_write_timeout = config.file_write_timeout;
     let start_time = Instant::now();
-    let mut last_size = 0;
+    let mut last_state = None;
     let mut stable_count = 0_u8;
     let timeout_duration = Duration::from_secs(file_write_timeout);
-    let check_interval = Duration::from_millis(file_check_interval);
-    // Wait for file to be stable
+    let check_interval = Duration::from_millis(config.file_check_interval);
+    // Wait for file to be stable; mtime catches rewrites that keep the same size
     while start_time.elapsed() < timeout_duration {
         if let Ok(metadata) = tokio::fs::metadata(preview_path).await {
-            let current_size = metadata.len();
-            if current_size == last_size && current_size > 0 {
+            let current_state = (metadata.len(), metadata.modified().ok());
+            if last_state == Some(current_state) && current_state.0 > 0 {
                 stable_count = stable_count.saturating_add(1);
-                if stable_count >= 3 {
-                    break;
+                if stable_count >= config.file_stable_checks {
+                    // A stalled writer leaves the size unchanged too, so also wait for the end marker
+                    if !config.verify_jpeg_eof || jpeg_complete(preview_path).await {
+                        break;
+                    }
+                    debug!("{filename} is stable but has no JPEG end marker yet, still waiting");
+                    stable_count = 0;
                 }
             } else {
                 stable_count = 0;
-                last_size = current_size;
+                last_state = Some(current_state);
             }
         }
         tokio::time::sleep(check_interval).await;
@@ -71,61 +140,98 @@ pub async fn process_new_file(
     if start_time.elapsed() >= timeout_duration {
         return Err(ImageAnalysisError::FileWriteTimeout {
             timeout: file_write_timeout,
-            filename: filename.clone(),
+            filename: filename.to_owned(),
         });
     }
-    println!(
+    Ok(())
+}
+
+async fn analyze_new_file(
+    ctx: &ProcessingContext<'_>,
+    preview_path: &Path,
+    filename: &str,
+    config: &MonitorConfig,
+    complete: bool,
+) -> Result<(), ImageAnalysisError> {
+    outln!(
+        "{}",
+        rust_i18n::t!("monitor.file_detected", filename = filename)
+    );
+    if !complete {
+        wait_until_stable(preview_path, filename, config).await?;
+    }
+    outln!(
         "{}",
         rust_i18n::t!("monitor.file_stable", filename = filename)
     );
-    let asset_id = extract_uuid_from_preview_filename(&filename)?;
+    let asset_id = extract_asset_id_from_preview_path(preview_path)?;
+    if let Some(failure_cache) = ctx.failure_cache
+        && let Err(err) = failure_cache.check(&asset_id, filename).await
+    {
+        outln!("{}", err.user_message());
+        events::emit_failure(filename, &err);
+        return Ok(());
+    }
 
-    let existing_description =
-        match check_overwrite_policy(ctx.data_access, &asset_id, ctx.overwrite_policy).await {
-            Ok(OverwriteDecision::Skip) => {
-                println!(
-                    "{}",
-                    rust_i18n::t!("monitor.file_already_in_db", filename = filename)
-                );
-                return Ok(());
-            }
-            Ok(OverwriteDecision::AnalyzeFresh) => None,
-            Ok(OverwriteDecision::PreserveExisting(desc)) => Some(desc),
-            Err(err) => return Err(err),
-        };
+    let existing_description = match check_overwrite_policy(
+        ctx.data_access,
+        &asset_id,
+        ctx.overwrite_policy,
+        ctx.analyzer.options(),
+    )
+    .await
+    {
+        Ok(OverwriteDecision::Skip) => {
+            outln!(
+                "{}",
+                rust_i18n::t!("monitor.file_already_in_db", filename = filename)
+            );
+            events::emit_failure(
+                filename,
+                &ImageAnalysisError::AlreadyProcessed {
+                    filename: filename.to_owned(),
+                },
+            );
+            return Ok(());
+        }
+        Ok(OverwriteDecision::AnalyzeFresh) => None,
+        Ok(OverwriteDecision::PreserveExisting(desc)) => Some(desc),
+        Err(err) => return Err(err),
+    };
 
-    let final_prompt = enrich_prompt_if_needed(ctx, &asset_id)
-        .await
-        .unwrap_or_else(|| ctx.prompt.to_owned());
+    let final_prompt = prepare_prompt(ctx, &asset_id).await;
+    let related_paths = related_previews(ctx, &asset_id).await;
 
     let result = ctx
-        .host_manager
-        .analyze_image(preview_path, &final_prompt)
+        .analyzer
+        .analyze(preview_path, &related_paths, &final_prompt)
         .await;
+    cleanup_related_previews(ctx.data_access, &related_paths).await;
+    if let Some(failure_cache) = ctx.failure_cache {
+        failure_cache.record(asset_id, result.as_ref().err()).await;
+    }
 
     match result {
         Ok(analysis) => {
-            println!(
+            outln!(
                 "{}",
                 rust_i18n::t!("monitor.processing_success", filename = filename)
             );
 
-            let final_description = build_final_description(
-                &analysis,
-                data_access,
-                ctx.preserve_human,
-                existing_description,
-                ctx.disable_ai_wrapper,
-            )
-            .await?;
-
-            data_access
-                .update_description(&analysis.asset_id, &final_description)
-                .await?;
-            println!(
+            let stored = store_analysis_result(ctx, &analysis, existing_description).await?;
+            if let Some(post_hook) = ctx.post_hook {
+                post_hook.run(analysis.asset_id, filename, &stored);
+            }
+            outln!(
                 "{}",
                 rust_i18n::t!("monitor.database_updated", filename = filename)
             );
+            Event::FileDone {
+                filename,
+                asset_id: analysis.asset_id,
+                description: &analysis.description,
+            }
+            .emit();
             Ok(())
         }
         Err(err) => {
@@ -141,17 +247,44 @@ pub async fn process_new_file(
 /// Uses filesystem watcher on thumbs/ directory.
 ///
 /// # `ImmichApi` mode
-/// Uses polling via `get_assets_to_process()` to detect new assets.
+/// Uses polling via `ImmichApiProvider::get_assets()` to detect new assets.
 pub async fn monitor_folder(
-    model_name: &str,
     data_access: DataAccess,
+    host_manager: Arc<HostManager>,
+    run_state: RunState,
     prompt: &str,
     config: &MonitorConfig,
 ) -> Result<(), Box<dyn std::error::Error>> {
     rust_i18n::set_locale(&config.lang);
-    let http_client = Client::builder()
-        .timeout(Duration::from_secs(config.timeout))
-        .build()?;
+    Event::Started { mode: "monitor" }.emit();
+
+    let max_concurrent = config.max_concurrent.max(1);
+    let bg_ctx = BackgroundCtx {
+        data_access: data_access.clone(),
+        prompt: prompt.to_owned(),
+        host_manager,
+        run_state,
+        slots: Arc::new(Semaphore::new(max_concurrent)),
+        max_concurrent,
+        waiting: Arc::new(AtomicUsize::new(0)),
+        tasks: Arc::new(Mutex::new(JoinSet::new())),
+    };
+
+    if config.once {
+        process_backlog(&bg_ctx, config).await?;
+        return Ok(());
+    }
+    if config.scan_on_start {
+        // Existing previews are processed in the background while new ones are watched
+        let bg_ctx_clone = bg_ctx.clone();
+        let config_clone = config.clone();
+        bg_ctx.spawn(async move {
+            rust_i18n::set_locale(&config_clone.lang);
+            if let Err(err) = process_backlog(&bg_ctx_clone, &config_clone).await {
+                error!("Initial scan failed: {}", err.user_message());
+            }
+        });
+    }
 
     let (stop_tx, mut stop_rx) = tokio_mpsc::channel(1);
     // Handle CTRL-C signal
@@ -165,39 +298,23 @@ pub async fn monitor_folder(
                 signal(SignalKind::interrupt()).expect("Failed to set up SIGINT handler");
             tokio::select! {
                 _ = sigterm.recv() => {
-                    println!("{}", rust_i18n::t!("monitor.stop_signal_received", signal = "SIGTERM"));
+                    outln!("{}", rust_i18n::t!("monitor.stop_signal_received", signal = "SIGTERM"));
                 }
                 _ = sigint.recv() => {
-                    println!("{}", rust_i18n::t!("monitor.stop_signal_received", signal = "SIGINT"));
+                    outln!("{}", rust_i18n::t!("monitor.stop_signal_received", signal = "SIGINT"));
                 }
             }
             let _: Result<(), tokio_mpsc::error::SendError<()>> = stop_tx.send(()).await;
         }
     });
 
-    let unavailable_duration = Duration::from_secs(config.unavailable_duration);
-    let host_manager = Arc::new(HostManager::new(
-        config.hosts.clone(),
-        config.interface,
-        http_client.clone(),
-        model_name.to_owned(),
-        config.timeout,
-        config.max_retries,
-        Duration::from_secs(config.retry_delay_seconds),
-        unavailable_duration,
-        config.api_key.clone(),
-    ));
-
-    let bg_ctx = BackgroundCtx {
-        data_access: data_access.clone(),
-        prompt: prompt.to_owned(),
-        host_manager,
-    };
-
     match &data_access {
         // ========== DATABASE MODE: filesystem monitoring ==========
-        DataAccess::Database { immich_root, .. } => {
-            let thumbs_dir = immich_root.join("thumbs");
+        DataAccess::Database {
+            thumbs_dir,
+            preview_extensions,
+            ..
+        } => {
             if !thumbs_dir.exists() {
                 return Err(Box::new(ImageAnalysisError::InvalidImmichStructure {
                     error: rust_i18n::t!(
@@ -208,22 +325,16 @@ pub async fn monitor_folder(
                 }));
             }
 
-            println!(
+            outln!(
                 "{}",
                 rust_i18n::t!(
                     "monitor.folder_monitoring_started",
                     path = thumbs_dir.display().to_string()
                 )
             );
-            println!("{}", rust_i18n::t!("monitor.stop_instructions"));
-
-            let (event_tx, event_rx): (
-                Sender<notify::Result<notify::Event>>,
-                Receiver<notify::Result<notify::Event>>,
-            ) = mpsc::channel();
+            outln!("{}", rust_i18n::t!("monitor.stop_instructions"));
 
-            let mut watcher = RecommendedWatcher::new(event_tx, Config::default())?;
-            watcher.watch(&thumbs_dir, RecursiveMode::Recursive)?;
+            let mut watcher = ThumbsWatcher::start(thumbs_dir.clone()).await?;
 
             let processing_files = Arc::new(Mutex::new(HashSet::<String>::new()));
             let mut last_events: HashMap<String, Instant> = HashMap::new();
@@ -233,28 +344,32 @@ pub async fn monitor_folder(
             loop {
                 tokio::select! {
                     Some(()) = stop_rx.recv() => {
-                        println!("{}", rust_i18n::t!("monitor.stopping_monitoring"));
+                        outln!("{}", rust_i18n::t!("monitor.stopping_monitoring"));
                         drop(watcher);
+                        bg_ctx.drain_tasks(config.shutdown_grace).await;
+                        log_unfinished(&processing_files);
                         return Ok(());
                     }
                     _ = interval.tick() => {
                         mark_activity();
-                        handle_fs_events(
-                            &event_rx,
+                        let outcome = handle_fs_events(
+                            &watcher.event_rx,
                             &mut last_events,
                             &processing_files,
+                            preview_extensions,
                             config,
                             &bg_ctx,
                         );
+                        watcher.maintain(outcome).await;
                     }
                 }
             }
         }
 
         // ========== IMMICH API MODE: polling-based monitoring ==========
-        DataAccess::ImmichApi { provider } => {
-            println!("{}", rust_i18n::t!("monitor.api_monitoring_started"));
-            println!("{}", rust_i18n::t!("monitor.stop_instructions"));
+        DataAccess::ImmichApi { provider, .. } => {
+            outln!("{}", rust_i18n::t!("monitor.api_monitoring_started"));
+            outln!("{}", rust_i18n::t!("monitor.stop_instructions"));
 
             let processing_assets = Arc::new(Mutex::new(HashSet::<Uuid>::new()));
             let mut known_assets: HashSet<Uuid> = HashSet::with_capacity(1 << 16);
@@ -268,7 +383,9 @@ pub async fn monitor_folder(
             loop {
                 tokio::select! {
                     Some(()) = stop_rx.recv() => {
-                        println!("{}", rust_i18n::t!("monitor.stopping_monitoring"));
+                        outln!("{}", rust_i18n::t!("monitor.stopping_monitoring"));
+                        bg_ctx.drain_tasks(config.shutdown_grace).await;
+                        log_unfinished(&processing_assets);
                         return Ok(());
                     }
                     _ = poll_interval.tick() => {
@@ -287,7 +404,96 @@ pub async fn monitor_folder(
                 }
             }
         }
+
+        #[cfg(test)]
+        DataAccess::Memory(_) => Err(Box::new(ImageAnalysisError::InvalidConfig {
+            error: "monitoring needs database or API access".to_owned(),
+        })),
+    }
+}
+
+/// Fetch the preview for an asset, process it with stability checks and clean up afterwards.
+async fn process_asset(
+    bg_ctx: &BackgroundCtx,
+    config: &MonitorConfig,
+    asset_id: Uuid,
+) -> Result<(), ImageAnalysisError> {
+    let preview_path = bg_ctx.data_access.get_preview_path(&asset_id).await?;
+
+    let ctx = ProcessingContext::new(
+        &bg_ctx.data_access,
+        &bg_ctx.prompt,
+        &*bg_ctx.host_manager,
+        config.overwrite_policy,
+        config.enrich_prompt,
+        config.preserve_human,
+        config.disable_ai_wrapper,
+    )
+    .with_run_state(&bg_ctx.run_state);
+
+    // A preview that already exists under its final name was moved into place complete
+    let complete = config.stability_mode != StabilityMode::Size;
+    let result = process_new_file(&ctx, &preview_path, config, complete).await;
+
+    if let Err(err) = bg_ctx.data_access.cleanup_preview(&preview_path).await {
+        warn!("Failed to cleanup preview: {err}");
     }
+    result
+}
+
+/// Process every existing asset once (`--once` and `--scan-on-start`).
+///
+/// Uses the same stability checks and processing slots as the watch loop, so previews
+/// still being written are waited for instead of being read half-finished.
+async fn process_backlog(
+    bg_ctx: &BackgroundCtx,
+    config: &MonitorConfig,
+) -> Result<(), ImageAnalysisError> {
+    let (sender, receiver) = futures::channel::mpsc::unbounded();
+    let excluded = bg_ctx.data_access.discover_assets(sender).await?;
+    debug!(
+        "Left out {} archived and {} trashed assets from the backlog",
+        excluded.archived, excluded.trashed
+    );
+    let assets: Vec<AssetRef> = receiver.collect().await;
+    outln!(
+        "{}",
+        rust_i18n::t!("monitor.backlog_started", count = assets.len().to_string())
+    );
+
+    let results: Vec<Result<(), ImageAnalysisError>> = stream::iter(assets)
+        .map(|asset| async move {
+            let _slot = bg_ctx.acquire_slot().await;
+            mark_activity();
+            process_asset(bg_ctx, config, asset.id)
+                .await
+                .inspect_err(|err| {
+                    if !err.is_skipped() {
+                        error!(
+                            "Background processing error for: {}: {}",
+                            asset.id,
+                            err.user_message()
+                        );
+                    }
+                })
+        })
+        .buffer_unordered(bg_ctx.max_concurrent)
+        .collect()
+        .await;
+
+    let failed = results
+        .iter()
+        .filter(|result| result.as_ref().is_err_and(|err| !err.is_skipped()))
+        .count();
+    outln!(
+        "{}",
+        rust_i18n::t!(
+            "monitor.backlog_complete",
+            total = results.len().to_string(),
+            failed = failed.to_string()
+        )
+    );
+    Ok(())
 }
 
 #[derive(Clone)]
@@ -295,28 +501,298 @@ struct BackgroundCtx {
     data_access: DataAccess,
     prompt: String,
     host_manager: Arc<HostManager>,
+    run_state: RunState,
+    /// Limits concurrently processed files to `--max-concurrent`
+    slots: Arc<Semaphore>,
+    max_concurrent: usize,
+    /// Number of queued files waiting for a free slot
+    waiting: Arc<AtomicUsize>,
+    /// Spawned background tasks, awaited on shutdown so in-flight writes can complete
+    tasks: Arc<Mutex<JoinSet<()>>>,
+}
+
+impl BackgroundCtx {
+    /// Waits for a free processing slot and for processing not to be paused, logging the
+    /// queue depth when all slots are busy.
+    async fn acquire_slot(&self) -> OwnedSemaphorePermit {
+        let waiting = self
+            .waiting
+            .fetch_add(1, Ordering::Relaxed)
+            .saturating_add(1);
+        if self.slots.available_permits() == 0 {
+            info!(
+                "All {} processing slots busy, {waiting} file(s) waiting",
+                self.max_concurrent
+            );
+        }
+        let permit = Arc::clone(&self.slots)
+            .acquire_owned()
+            .await
+            .expect("Processing semaphore closed");
+        self.run_state.wait_while_paused().await;
+        self.waiting.fetch_sub(1, Ordering::Relaxed);
+        permit
+    }
+
+    /// Spawn a background task tracked for shutdown, reaping tasks that already finished.
+    fn spawn(&self, task: impl Future<Output = ()> + Send + 'static) {
+        let mut tasks = self.tasks.lock().expect("Failed to lock background tasks");
+        while tasks.try_join_next().is_some() {}
+        tasks.spawn(task);
+    }
+
+    /// Wait up to `grace_secs` for the spawned tasks to finish, then abort the rest.
+    async fn drain_tasks(&self, grace_secs: u64) {
+        let mut tasks =
+            std::mem::take(&mut *self.tasks.lock().expect("Failed to lock background tasks"));
+        while tasks.try_join_next().is_some() {}
+        if tasks.is_empty() {
+            return;
+        }
+        outln!(
+            "{}",
+            rust_i18n::t!(
+                "monitor.shutdown_draining",
+                count = tasks.len().to_string(),
+                grace = grace_secs.to_string()
+            )
+        );
+        let mut drained: usize = 0;
+        let _: Result<(), tokio::time::error::Elapsed> =
+            tokio::time::timeout(Duration::from_secs(grace_secs), async {
+                while tasks.join_next().await.is_some() {
+                    drained = drained.saturating_add(1);
+                }
+            })
+            .await;
+        let abandoned = tasks.len();
+        tasks.abort_all();
+        outln!(
+            "{}",
+            rust_i18n::t!(
+                "monitor.shutdown_drained",
+                drained = drained.to_string(),
+                abandoned = abandoned.to_string()
+            )
+        );
+    }
+}
+
+/// Warn about files or assets that were still queued or processing when monitoring stopped.
+fn log_unfinished<T: std::fmt::Display>(processing: &Mutex<HashSet<T>>) {
+    let names: Vec<String> = processing
+        .lock()
+        .expect("Failed to lock processing set")
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    if !names.is_empty() {
+        warn!("Not described before shutdown: {}", names.join(", "));
+    }
+}
+
+/// Events drained from the watcher channel in one tick.
+#[derive(Debug, Default, Clone, Copy)]
+struct FsEventsOutcome {
+    events: u64,
+    /// The watcher reported an error and may have stopped delivering events
+    watcher_failed: bool,
+}
+
+/// Recursive watcher on the thumbs directory that is re-created when it stops working.
+///
+/// Network storage can be remounted under a running watcher, which then stays silent.
+/// Watcher errors and a changed device or inode of the directory trigger a re-watch,
+/// retried with backoff until it succeeds.
+struct ThumbsWatcher {
+    dir: PathBuf,
+    watcher: Option<RecommendedWatcher>,
+    event_rx: Receiver<notify::Result<notify::Event>>,
+    /// Device and inode of `dir` when it was watched
+    identity: Option<(u64, u64)>,
+    retry_delay: Duration,
+    next_retry: Option<Instant>,
+    last_check: Instant,
+    last_heartbeat: Instant,
+    events_since_heartbeat: u64,
+}
+
+impl ThumbsWatcher {
+    async fn start(dir: PathBuf) -> notify::Result<Self> {
+        let (watcher, event_rx) = Self::watch(&dir)?;
+        let identity = dir_identity(&dir).await;
+        let now = Instant::now();
+        Ok(Self {
+            dir,
+            watcher: Some(watcher),
+            event_rx,
+            identity,
+            retry_delay: WATCHER_RETRY_MIN,
+            next_retry: None,
+            last_check: now,
+            last_heartbeat: now,
+            events_since_heartbeat: 0,
+        })
+    }
+
+    fn watch(
+        dir: &Path,
+    ) -> notify::Result<(RecommendedWatcher, Receiver<notify::Result<notify::Event>>)> {
+        let (event_tx, event_rx): (
+            Sender<notify::Result<notify::Event>>,
+            Receiver<notify::Result<notify::Event>>,
+        ) = mpsc::channel();
+        let mut watcher = RecommendedWatcher::new(event_tx, Config::default())?;
+        watcher.watch(dir, RecursiveMode::Recursive)?;
+        Ok((watcher, event_rx))
+    }
+
+    /// Called every tick: re-watches a failed or remounted directory and logs a heartbeat.
+    async fn maintain(&mut self, outcome: FsEventsOutcome) {
+        self.events_since_heartbeat = self.events_since_heartbeat.saturating_add(outcome.events);
+        if outcome.watcher_failed && self.next_retry.is_none() {
+            self.invalidate();
+        }
+        if self.next_retry.is_none() && self.last_check.elapsed() >= WATCHER_CHECK_INTERVAL {
+            self.last_check = Instant::now();
+            let identity = dir_identity(&self.dir).await;
+            if identity.is_none() || identity != self.identity {
+                warn!(
+                    "Thumbs directory {} was removed or remounted",
+                    self.dir.display()
+                );
+                self.invalidate();
+            }
+        }
+        if self
+            .next_retry
+            .is_some_and(|retry_at| Instant::now() >= retry_at)
+        {
+            self.restart().await;
+        }
+        if self.last_heartbeat.elapsed() >= WATCHER_HEARTBEAT_INTERVAL {
+            let active = self.watcher.is_some();
+            outln!(
+                "{}",
+                if active {
+                    rust_i18n::t!(
+                        "monitor.watcher_heartbeat",
+                        events = self.events_since_heartbeat.to_string()
+                    )
+                } else {
+                    rust_i18n::t!(
+                        "monitor.watcher_down",
+                        path = self.dir.display().to_string()
+                    )
+                }
+            );
+            self.last_heartbeat = Instant::now();
+            self.events_since_heartbeat = 0;
+        }
+    }
+
+    /// Drop the current watcher and re-watch on the next tick.
+    fn invalidate(&mut self) {
+        outln!(
+            "{}",
+            rust_i18n::t!(
+                "monitor.watcher_restarting",
+                path = self.dir.display().to_string()
+            )
+        );
+        self.watcher = None;
+        self.next_retry = Some(Instant::now());
+    }
+
+    async fn restart(&mut self) {
+        match Self::watch(&self.dir) {
+            Ok((watcher, event_rx)) => {
+                self.watcher = Some(watcher);
+                self.event_rx = event_rx;
+                self.identity = dir_identity(&self.dir).await;
+                self.retry_delay = WATCHER_RETRY_MIN;
+                self.next_retry = None;
+                self.last_check = Instant::now();
+                outln!(
+                    "{}",
+                    rust_i18n::t!(
+                        "monitor.watcher_restarted",
+                        path = self.dir.display().to_string()
+                    )
+                );
+            }
+            Err(err) => {
+                warn!(
+                    "Failed to watch {} again, retrying in {:?}: {err}",
+                    self.dir.display(),
+                    self.retry_delay
+                );
+                self.next_retry = Instant::now().checked_add(self.retry_delay);
+                self.retry_delay = self.retry_delay.saturating_mul(2).min(WATCHER_RETRY_MAX);
+            }
+        }
+    }
+}
+
+/// Device and inode of a directory, `None` when it cannot be read.
+async fn dir_identity(dir: &Path) -> Option<(u64, u64)> {
+    tokio::fs::metadata(dir)
+        .await
+        .ok()
+        .filter(std::fs::Metadata::is_dir)
+        .map(|metadata| (metadata.dev(), metadata.ino()))
+}
+
+/// Whether `kind` may announce a new or rewritten preview. Files moved into place are only
+/// picked up when the stability mode does not rely on watching them being written.
+const fn is_new_file_event(mode: StabilityMode, kind: EventKind) -> bool {
+    match kind {
+        EventKind::Create(_) | EventKind::Modify(ModifyKind::Data(_)) => true,
+        EventKind::Modify(ModifyKind::Name(RenameMode::To | RenameMode::Both)) => {
+            !matches!(mode, StabilityMode::Size)
+        }
+        _ => false,
+    }
+}
+
+/// Whether the file behind an event of `kind` is already fully written, so the stability
+/// checks can be skipped (`--stability-mode`).
+const fn written_on_event(mode: StabilityMode, kind: EventKind) -> bool {
+    match mode {
+        StabilityMode::Size => false,
+        StabilityMode::Rename => matches!(
+            kind,
+            EventKind::Create(_)
+                | EventKind::Modify(ModifyKind::Name(RenameMode::To | RenameMode::Both))
+        ),
+        StabilityMode::None => true,
+    }
 }
 
 fn handle_fs_events(
     event_rx: &Receiver<notify::Result<notify::Event>>,
     last_events: &mut HashMap<String, Instant>,
     processing_files: &Arc<Mutex<HashSet<String>>>,
+    preview_extensions: &[String],
     config: &MonitorConfig,
     bg_ctx: &BackgroundCtx,
-) {
+) -> FsEventsOutcome {
+    let mut outcome = FsEventsOutcome::default();
     while let Ok(event) = event_rx.try_recv() {
+        outcome.events = outcome.events.saturating_add(1);
         match event {
             Ok(event_val) => {
-                if let EventKind::Create(_) | EventKind::Modify(ModifyKind::Data(_)) =
-                    event_val.kind
-                    && let Some(path_buf) = event_val.paths.first()
+                if is_new_file_event(config.stability_mode, event_val.kind)
+                    // A rename lists the old path first and the new one last
+                    && let Some(path_buf) = event_val.paths.last()
                 {
+                    let complete = written_on_event(config.stability_mode, event_val.kind);
                     let path = path_buf.as_path();
                     if path.is_file()
                         && let Some(filename_str) = path.file_name().and_then(|n| n.to_str())
                     {
                         let filename = filename_str.to_owned();
-                        if !is_preview_filename(&filename) {
+                        if !is_preview_filename(&filename, preview_extensions) {
                             continue;
                         }
 
@@ -325,7 +801,7 @@ fn handle_fs_events(
                         if let Some(last_time) = last_events.get(&filename)
                             && now.duration_since(*last_time) < cooldown_duration
                         {
-                            println!(
+                            outln!(
                                 "{}",
                                 rust_i18n::t!(
                                     "monitor.skipping_duplicate_event",
@@ -342,7 +818,7 @@ fn handle_fs_events(
                                 .lock()
                                 .expect("Failed to lock processing files");
                             if files.contains(&filename) {
-                                println!(
+                                outln!(
                                     "{}",
                                     rust_i18n::t!(
                                         "monitor.file_already_processing",
@@ -353,7 +829,7 @@ fn handle_fs_events(
                             }
                         }
 
-                        println!(
+                        outln!(
                             "{}",
                             rust_i18n::t!("monitor.file_queued", filename = filename)
                         );
@@ -370,24 +846,21 @@ fn handle_fs_events(
                         let processing_files_clone = Arc::clone(processing_files);
                         let config_clone = config.clone();
 
-                        tokio::spawn(async move {
+                        bg_ctx.spawn(async move {
                             rust_i18n::set_locale(&config_clone.lang);
+                            let _slot = bg_ctx_clone.acquire_slot().await;
                             let ctx = ProcessingContext::new(
                                 &bg_ctx_clone.data_access,
                                 &bg_ctx_clone.prompt,
-                                &bg_ctx_clone.host_manager,
+                                &*bg_ctx_clone.host_manager,
                                 config_clone.overwrite_policy,
                                 config_clone.enrich_prompt,
                                 config_clone.preserve_human,
                                 config_clone.disable_ai_wrapper,
-                            );
-                            let result = process_new_file(
-                                &ctx,
-                                &path_clone,
-                                config_clone.file_write_timeout,
-                                config_clone.file_check_interval,
                             )
-                            .await;
+                            .with_run_state(&bg_ctx_clone.run_state);
+                            let result =
+                                process_new_file(&ctx, &path_clone, &config_clone, complete).await;
                             {
                                 let mut files = processing_files_clone
                                     .lock()
@@ -397,6 +870,7 @@ fn handle_fs_events(
                             if let Err(err) = result {
                                 match err {
                                     ImageAnalysisError::AlreadyProcessed { .. }
+                                    | ImageAnalysisError::Previewed { .. }
                                     | ImageAnalysisError::AssetNotFound { .. } => {}
                                     err => error!(
                                         "Background processing error for: {filename_clone}: {}",
@@ -410,9 +884,11 @@ fn handle_fs_events(
             }
             Err(err) => {
                 error!("Filesystem monitoring error: {err}");
+                outcome.watcher_failed = true;
             }
         }
     }
+    outcome
 }
 
 async fn handle_api_poll(
@@ -425,7 +901,7 @@ async fn handle_api_poll(
     bg_ctx: &BackgroundCtx,
 ) {
     let assets_result = if *is_first_poll {
-        provider.get_assets().await
+        provider.get_assets().await.map(|(assets, _)| assets)
     } else {
         #[expect(clippy::arithmetic_side_effects)]
         let buffer_secs = i64::from(config.api_poll_interval) * 2;
@@ -471,7 +947,7 @@ async fn handle_api_poll(
                         processing.insert(asset.id);
                     }
 
-                    println!(
+                    outln!(
                         "{}",
                         rust_i18n::t!("monitor.api_asset_queued", asset_id = asset.id.to_string())
                     );
@@ -481,47 +957,11 @@ async fn handle_api_poll(
                     let processing_assets_clone = Arc::clone(processing_assets);
                     let config_clone = config.clone();
 
-                    tokio::spawn(async move {
+                    bg_ctx.spawn(async move {
                         rust_i18n::set_locale(&config_clone.lang);
+                        let _slot = bg_ctx_clone.acquire_slot().await;
 
-                        let preview_path =
-                            match bg_ctx_clone.data_access.get_preview_path(&asset_id).await {
-                                Ok(path) => path,
-                                Err(err) => {
-                                    error!("Failed to get preview for asset {asset_id}: {err}");
-                                    processing_assets_clone
-                                        .lock()
-                                        .expect("Failed to lock processing assets")
-                                        .remove(&asset_id);
-                                    return;
-                                }
-                            };
-
-                        let ctx = ProcessingContext::new(
-                            &bg_ctx_clone.data_access,
-                            &bg_ctx_clone.prompt,
-                            &bg_ctx_clone.host_manager,
-                            config_clone.overwrite_policy,
-                            config_clone.enrich_prompt,
-                            config_clone.preserve_human,
-                            config_clone.disable_ai_wrapper,
-                        );
-
-                        let result = process_new_file(
-                            &ctx,
-                            &preview_path,
-                            config_clone.file_write_timeout,
-                            config_clone.file_check_interval,
-                        )
-                        .await;
-
-                        if let Err(err) = bg_ctx_clone
-                            .data_access
-                            .cleanup_preview(&preview_path)
-                            .await
-                        {
-                            warn!("Failed to cleanup preview: {err}");
-                        }
+                        let result = process_asset(&bg_ctx_clone, &config_clone, asset_id).await;
 
                         {
                             let mut processing = processing_assets_clone
@@ -533,6 +973,7 @@ async fn handle_api_poll(
                         if let Err(err) = result {
                             match err {
                                 ImageAnalysisError::AlreadyProcessed { .. }
+                                | ImageAnalysisError::Previewed { .. }
                                 | ImageAnalysisError::AssetNotFound { .. } => {}
                                 err => error!(
                                     "Background processing error for: {asset_id}: {}",
@@ -0,0 +1,91 @@
+use crate::{events::outln, health::mark_activity};
+use log::warn;
+use std::{
+    path::PathBuf,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+use tokio::{
+    signal::unix::{SignalKind, signal},
+    sync::watch,
+    time::MissedTickBehavior,
+};
+
+/// How often `--pause-file` is checked.
+const PAUSE_FILE_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Paused state, toggled by SIGUSR1 and held while `--pause-file` exists.
+#[derive(Debug)]
+pub struct Pause {
+    /// Flipped by every SIGUSR1
+    toggled: AtomicBool,
+    file: Option<PathBuf>,
+    paused: watch::Sender<bool>,
+}
+
+impl Pause {
+    /// Start listening for SIGUSR1 and watching `pause_file`.
+    ///
+    /// Must be called from within the runtime.
+    #[must_use]
+    pub fn start(pause_file: Option<PathBuf>) -> Arc<Self> {
+        let pause = Arc::new(Self {
+            toggled: AtomicBool::new(false),
+            file: pause_file,
+            paused: watch::Sender::new(false),
+        });
+        tokio::spawn(listen(Arc::clone(&pause)));
+        pause
+    }
+
+    /// Wait while processing is paused; returns at once when it is not.
+    ///
+    /// Files already being analyzed are not interrupted, callers check before starting one.
+    pub async fn wait_while_paused(&self) {
+        let mut paused = self.paused.subscribe();
+        let _: Result<watch::Ref<'_, bool>, watch::error::RecvError> =
+            paused.wait_for(|is_paused| !is_paused).await;
+    }
+}
+
+async fn listen(pause: Arc<Pause>) {
+    let mut sigusr1 = match signal(SignalKind::user_defined1()) {
+        Ok(sigusr1) => Some(sigusr1),
+        Err(err) => {
+            warn!("Failed to set up SIGUSR1 handler, pausing by signal is unavailable: {err}");
+            None
+        }
+    };
+    let mut poll = tokio::time::interval(PAUSE_FILE_POLL_INTERVAL);
+    poll.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    loop {
+        tokio::select! {
+            Some(()) = async { sigusr1.as_mut()?.recv().await } => {
+                pause.toggled.fetch_xor(true, Ordering::Relaxed);
+            }
+            _ = poll.tick() => {}
+        }
+        let file_present = match &pause.file {
+            Some(path) => tokio::fs::try_exists(path).await.unwrap_or(false),
+            None => false,
+        };
+        let paused = pause.toggled.load(Ordering::Relaxed) || file_present;
+        if paused {
+            // A paused run is idle on purpose, not hung
+            mark_activity();
+        }
+        if pause.paused.send_replace(paused) != paused {
+            outln!(
+                "{}",
+                if paused {
+                    rust_i18n::t!("main.processing_paused")
+                } else {
+                    rust_i18n::t!("main.processing_resumed")
+                }
+            );
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use log::{debug, warn};
+use std::{
+    process::Stdio,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+use tokio::{
+    process::Command,
+    sync::{Notify, Semaphore},
+};
+use uuid::Uuid;
+
+/// Characters of hook stderr kept in the warning for a failed hook.
+const STDERR_SNIPPET_CHARS: usize = 200;
+
+/// Shell command run after every stored description (`--post-hook`).
+#[derive(Debug)]
+pub struct PostHook {
+    command: String,
+    timeout: Duration,
+    slots: Arc<Semaphore>,
+    /// Hooks started or waiting for a slot, so exiting can wait for them
+    pending: AtomicUsize,
+    idle: Notify,
+}
+
+impl PostHook {
+    /// Creates the hook running `command` after each successful description.
+    #[must_use]
+    pub fn new(command: &str, max_concurrent: usize, timeout: Duration) -> Self {
+        Self {
+            command: command.to_owned(),
+            timeout,
+            slots: Arc::new(Semaphore::new(max_concurrent.max(1))),
+            pending: AtomicUsize::new(0),
+            idle: Notify::new(),
+        }
+    }
+
+    /// Run the hook for a stored description in the background.
+    ///
+    /// `{asset_id}` is substituted directly. `{filename}` and `{description}` become references
+    /// to the `IMMICH_ANALYZE_FILENAME` and `IMMICH_ANALYZE_DESCRIPTION` environment variables,
+    /// so neither their length nor shell characters in them reach the command line.
+    /// At most `--post-hook-concurrency` hooks run at once; one running past
+    /// `--post-hook-timeout` is killed.
+    // The `{...}` literals are hook placeholders, not format arguments
+    #[expect(clippy::literal_string_with_formatting_args)]
+    pub fn run(self: &Arc<Self>, asset_id: Uuid, filename: &str, description: &str) {
+        let script = self
+            .command
+            .replace("{asset_id}", &asset_id.to_string())
+            .replace("{filename}", "\"$IMMICH_ANALYZE_FILENAME\"")
+            .replace("{description}", "\"$IMMICH_ANALYZE_DESCRIPTION\"");
+        let mut command = Command::new("sh");
+        command
+            .arg("-c")
+            .arg(script)
+            .env("IMMICH_ANALYZE_ASSET_ID", asset_id.to_string())
+            .env("IMMICH_ANALYZE_FILENAME", filename)
+            .env("IMMICH_ANALYZE_DESCRIPTION", description)
+            .stdin(Stdio::null())
+            .kill_on_drop(true);
+        self.pending.fetch_add(1, Ordering::SeqCst);
+        let hook = Arc::clone(self);
+        tokio::spawn(async move {
+            execute(&hook, asset_id, command).await;
+            if hook.pending.fetch_sub(1, Ordering::SeqCst) == 1 {
+                hook.idle.notify_waiters();
+            }
+        });
+    }
+
+    /// Wait until every hook started so far has finished, so none is cut off on exit.
+    pub async fn wait_idle(&self) {
+        loop {
+            let idle = self.idle.notified();
+            if self.pending.load(Ordering::SeqCst) == 0 {
+                return;
+            }
+            idle.await;
+        }
+    }
+}
+
+async fn execute(hook: &PostHook, asset_id: Uuid, mut command: Command) {
+    let timeout = hook.timeout;
+    let Ok(_slot) = hook.slots.acquire().await else {
+        return;
+    };
+    match tokio::time::timeout(timeout, command.output()).await {
+        Ok(Ok(output)) if output.status.success() => {
+            debug!("Post hook for asset {asset_id} finished");
+        }
+        Ok(Ok(output)) => {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let snippet: String = stderr.trim().chars().take(STDERR_SNIPPET_CHARS).collect();
+            warn!(
+                "Post hook for asset {asset_id} exited with {}: {snippet}",
+                output.status
+            );
+        }
+        Ok(Err(err)) => warn!("Failed to start post hook for asset {asset_id}: {err}"),
+        Err(_elapsed) => warn!(
+            "Post hook for asset {asset_id} timed out after {}s and was killed",
+            timeout.as_secs()
+        ),
+    }
+}
@@ -1,4 +1,44 @@
-use std::time::Instant;
+use std::{
+    borrow::Cow,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
+static PLAIN_OUTPUT: AtomicBool = AtomicBool::new(false);
+
+/// Minimum time between progress lines in plain output mode
+const PLAIN_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Switch console output to plain text (set by `--plain` or when stdout is not a terminal).
+pub fn set_plain_output(plain: bool) {
+    PLAIN_OUTPUT.store(plain, Ordering::Relaxed);
+}
+
+pub fn plain_output() -> bool {
+    PLAIN_OUTPUT.load(Ordering::Relaxed)
+}
+
+/// Result status shown in front of each processed file
+#[derive(Debug, Clone, Copy)]
+pub enum Status {
+    Success,
+    Error,
+    Skipped,
+}
+
+impl Status {
+    /// Nerd Font icon, or an ASCII tag in plain output mode.
+    pub fn marker(self) -> Cow<'static, str> {
+        match (self, plain_output()) {
+            (Self::Success, true) => Cow::Borrowed("[OK]"),
+            (Self::Error, true) => Cow::Borrowed("[ERROR]"),
+            (Self::Skipped, true) => Cow::Borrowed("[SKIP]"),
+            (Self::Success, false) => rust_i18n::t!("status.success"),
+            (Self::Error, false) => rust_i18n::t!("status.error"),
+            (Self::Skipped, false) => rust_i18n::t!("status.skipped"),
+        }
+    }
+}
 
 /// Simple progress display without external dependencies
 pub struct SimpleProgress {
@@ -7,6 +47,12 @@ pub struct SimpleProgress {
     pub start_time: Instant,
     pub current_message: String,
     pub finish_message: String,
+    /// Whether items are still being discovered, so `total` may keep growing
+    pub discovering: bool,
+    /// Print only periodic summary lines instead of one line per update
+    pub plain: bool,
+    /// When the last plain-mode progress line was printed
+    pub last_printed: Option<Instant>,
 }
 
 impl SimpleProgress {
@@ -17,6 +63,25 @@ impl SimpleProgress {
             start_time: Instant::now(),
             current_message: String::new(),
             finish_message: finish_message.to_owned(),
+            discovering: false,
+            plain: plain_output(),
+            last_printed: None,
+        }
+    }
+    /// Create a progress display whose total grows while items are discovered.
+    pub fn new_discovering(finish_message: &str) -> Self {
+        Self {
+            discovering: true,
+            ..Self::new(0, finish_message)
+        }
+    }
+    pub const fn inc_total(&mut self) {
+        self.total = self.total.saturating_add(1);
+    }
+    pub fn finish_discovery(&mut self) {
+        self.discovering = false;
+        if self.total > 0 {
+            self.display();
         }
     }
     pub fn set_message(&mut self, message: &str) {
@@ -31,15 +96,25 @@ impl SimpleProgress {
         message.clone_into(&mut self.current_message);
         self.inc();
     }
+    /// Take a skipped item out of the total, so the percentage and ETA only count
+    /// items that are actually processed.
     pub fn dec_total(&mut self) {
         self.total = self.total.saturating_sub(1);
         self.display();
     }
+    /// Take `count` queued items out of the total at once, without redrawing.
+    pub const fn sub_total(&mut self, count: u64) {
+        self.total = self.total.saturating_sub(count);
+    }
     pub fn set_message_and_dec_total(&mut self, message: &str) {
         message.clone_into(&mut self.current_message);
         self.dec_total();
     }
-    pub fn display(&self) {
+    pub fn display(&mut self) {
+        // Lifecycle events replace progress lines in JSON output mode
+        if crate::events::json_output() {
+            return;
+        }
         let progress: u8 = self
             .current
             .saturating_mul(100)
@@ -53,7 +128,32 @@ impl SimpleProgress {
             .saturating_mul(self.total.saturating_sub(self.current))
             .checked_div(self.current)
             .unwrap_or(0);
-        if progress >= 100 {
+        if self.plain {
+            let finished = !self.discovering && progress >= 100;
+            let due = self
+                .last_printed
+                .is_none_or(|last| last.elapsed() >= PLAIN_INTERVAL);
+            if !finished && !due {
+                return;
+            }
+            self.last_printed = Some(Instant::now());
+            if finished {
+                println!("[100%] {}/{} ({}s)", self.total, self.total, elapsed);
+                println!("   {}", self.finish_message);
+            } else if self.discovering {
+                println!("[  ?%] {}/{}+ ({}s)", self.current, self.total, elapsed);
+            } else {
+                println!(
+                    "[{:3}%] {}/{} ({}s, ETA: {}s)",
+                    progress, self.current, self.total, elapsed, eta
+                );
+            }
+        } else if self.discovering {
+            println!("[  ?%] {}/{}+ ({}s)", self.current, self.total, elapsed);
+            if !self.current_message.is_empty() {
+                println!("   {}", self.current_message);
+            }
+        } else if progress >= 100 {
             println!(
                 "[{:3}%] {}/{} ({}s)",
                 progress, self.total, self.total, elapsed
@@ -1,8 +1,17 @@
-use crate::config::ProcessingContext;
+use crate::{config::ProcessingContext, immich_api::AssetMetadata, utils::is_generic_filename};
 use chrono::{Datelike as _, NaiveDate};
 use log::warn;
+use regex::{Captures, Regex};
+use std::{
+    collections::HashSet,
+    sync::{Mutex, OnceLock},
+};
 use uuid::Uuid;
 
+static PLACEHOLDER_PATTERN: OnceLock<Regex> = OnceLock::new();
+
+static WARNED_PLACEHOLDERS: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+
 pub struct PromptContext {
     pub base_prompt: String,
     pub created_at: Option<String>,
@@ -254,84 +263,219 @@ fn calculate_age(birth_date: Option<&str>, photo_date: Option<&str>) -> Option<u
     }
 }
 
-pub async fn enrich_prompt_if_needed(
+/// Prepares the prompt for an asset.
+///
+/// Fills in `{asset_date}`, `{album_names}` and `{original_filename}` placeholders and,
+/// with `--enrich-prompt`, appends asset metadata as additional context. With
+/// `--use-filename-hint`, a meaningful original filename is prepended, and with
+/// `--include-people`, the names of the recognized people.
+pub async fn prepare_prompt(ctx: &ProcessingContext<'_>, asset_id: &Uuid) -> String {
+    let mut prompt = prepare_base_prompt(ctx, asset_id).await;
+    // Enrichment already lists the original filename and people
+    if ctx.enrich_prompt {
+        return prompt;
+    }
+    let options = ctx.analyzer.options();
+    if options.use_filename_hint {
+        prompt = prepend_filename_hint(ctx, asset_id, prompt).await;
+    }
+    if let Some(limit) = options.include_people {
+        prompt = prepend_people(ctx, asset_id, limit, prompt).await;
+    }
+    prompt
+}
+
+async fn prepend_filename_hint(
     ctx: &ProcessingContext<'_>,
     asset_id: &Uuid,
-) -> Option<String> {
-    if !ctx.enrich_prompt {
-        return None;
+    prompt: String,
+) -> String {
+    match ctx.data_access.get_original_file_name(asset_id).await {
+        Ok(Some(name)) if !is_generic_filename(&name) => {
+            format!("The original filename is \"{name}\" and may hint at the content.\n{prompt}")
+        }
+        Ok(_) => prompt,
+        Err(err) => {
+            warn!("Failed to get original filename of {asset_id}: {err}");
+            prompt
+        }
     }
+}
 
-    match ctx.data_access.get_asset_metadata(asset_id).await {
-        Ok(metadata) => {
-            let photo_date = metadata
-                .local_date_time
-                .as_deref()
-                .or(metadata.file_created_at.as_deref())
-                .or_else(|| {
-                    metadata
-                        .exif_info
-                        .as_ref()
-                        .and_then(|exif| exif.date_time_original.as_deref())
-                });
-
-            let people_with_ages: Vec<(String, Option<u32>)> = metadata
-                .people
-                .iter()
-                .map(|person| {
-                    let age = calculate_age(person.birth_date.as_deref(), photo_date);
-                    (person.name.clone(), age)
-                })
-                .collect();
+async fn prepend_people(
+    ctx: &ProcessingContext<'_>,
+    asset_id: &Uuid,
+    limit: usize,
+    prompt: String,
+) -> String {
+    match ctx
+        .data_access
+        .get_asset_people_names(asset_id, limit)
+        .await
+    {
+        Ok(names) if !names.is_empty() => {
+            format!("This photo contains: {}.\n{prompt}", names.join(", "))
+        }
+        Ok(_) => prompt,
+        Err(err) => {
+            warn!("Failed to get people of {asset_id}: {err}");
+            prompt
+        }
+    }
+}
 
-            let tag_values: Vec<String> =
-                metadata.tags.iter().map(|tag| tag.value.clone()).collect();
-
-            let mut context = PromptContext::new(ctx.prompt)
-                .with_file_info(metadata.original_file_name, metadata.r#type)
-                .with_people(people_with_ages)
-                .with_tags(tag_values)
-                .with_resolution(metadata.width, metadata.height)
-                .with_mime_type(metadata.original_mime_type);
-
-            if let Some(exif) = metadata.exif_info {
-                let created_at = exif.date_time_original.or(metadata.file_created_at);
-                context = context.with_created_at(created_at);
-
-                let location_parts: Vec<String> = [exif.city, exif.state, exif.country]
-                    .into_iter()
-                    .flatten()
-                    .filter(|part| !part.is_empty())
-                    .collect();
-
-                let location = if location_parts.is_empty() {
-                    None
-                } else {
-                    Some(location_parts.join(", "))
-                };
-
-                context = context
-                    .with_location(location)
-                    .with_camera_info(exif.make, exif.model)
-                    .with_lens_model(exif.lens_model)
-                    .with_exposure_settings(
-                        exif.exposure_time,
-                        exif.f_number,
-                        exif.focal_length,
-                        exif.iso,
-                    )
-                    .with_rating(exif.rating)
-                    .with_time_zone(exif.time_zone)
-                    .with_exif_description(exif.description);
-            } else {
-                context = context.with_created_at(metadata.file_created_at);
-            }
+async fn prepare_base_prompt(ctx: &ProcessingContext<'_>, asset_id: &Uuid) -> String {
+    let has_placeholders = placeholder_pattern().is_match(ctx.prompt);
+    if !has_placeholders && !ctx.enrich_prompt {
+        return ctx.prompt.to_owned();
+    }
 
-            Some(context.build_enriched_prompt())
-        }
+    let metadata = match ctx.data_access.get_asset_metadata(asset_id).await {
+        Ok(metadata) => Some(metadata),
         Err(err) => {
-            warn!("Failed to get asset metadata for enrichment of {asset_id}: {err}");
+            warn!("Failed to get asset metadata for prompt of {asset_id}: {err}");
             None
         }
+    };
+
+    let base_prompt = if has_placeholders {
+        render_placeholders(ctx, asset_id, metadata.as_ref()).await
+    } else {
+        ctx.prompt.to_owned()
+    };
+
+    match metadata {
+        Some(asset_metadata) if ctx.enrich_prompt => enrich_prompt(&base_prompt, asset_metadata),
+        _ => base_prompt,
+    }
+}
+
+fn placeholder_pattern() -> &'static Regex {
+    PLACEHOLDER_PATTERN
+        .get_or_init(|| Regex::new(r"\{([a-z_]+)\}").expect("Invalid placeholder regex"))
+}
+
+/// Replaces prompt placeholders with values for the asset.
+///
+/// Unknown or unresolvable placeholders are left blank, warning once per placeholder name.
+async fn render_placeholders(
+    ctx: &ProcessingContext<'_>,
+    asset_id: &Uuid,
+    metadata: Option<&AssetMetadata>,
+) -> String {
+    let uses_albums = placeholder_pattern().captures_iter(ctx.prompt).any(|caps| {
+        caps.get(1)
+            .is_some_and(|name| name.as_str() == "album_names")
+    });
+    let album_names = if uses_albums {
+        match ctx.data_access.get_asset_album_names(asset_id).await {
+            Ok(names) => Some(names.join(", ")),
+            Err(err) => {
+                warn!("Failed to get albums of {asset_id}: {err}");
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    placeholder_pattern()
+        .replace_all(ctx.prompt, |caps: &Captures<'_>| {
+            let name = caps.get(1).map_or("", |name_match| name_match.as_str());
+            let value = match name {
+                "asset_date" => metadata
+                    .and_then(photo_date)
+                    .map(|date| date.get(..10).unwrap_or(date).to_owned()),
+                "original_filename" => metadata.and_then(|meta| meta.original_file_name.clone()),
+                "album_names" => album_names.clone(),
+                _ => None,
+            };
+            value.unwrap_or_else(|| {
+                warn_unresolved_once(name);
+                String::new()
+            })
+        })
+        .into_owned()
+}
+
+fn warn_unresolved_once(name: &str) {
+    let warned = WARNED_PLACEHOLDERS.get_or_init(|| Mutex::new(HashSet::new()));
+    let is_new = warned
+        .lock()
+        .expect("Failed to lock warned placeholders")
+        .insert(name.to_owned());
+    if is_new {
+        warn!("Prompt placeholder {{{name}}} could not be resolved and was left blank");
+    }
+}
+
+/// Date the photo was taken, falling back to the file creation date.
+fn photo_date(metadata: &AssetMetadata) -> Option<&str> {
+    metadata
+        .local_date_time
+        .as_deref()
+        .or(metadata.file_created_at.as_deref())
+        .or_else(|| {
+            metadata
+                .exif_info
+                .as_ref()
+                .and_then(|exif| exif.date_time_original.as_deref())
+        })
+}
+
+fn enrich_prompt(base_prompt: &str, metadata: AssetMetadata) -> String {
+    let photo_date = photo_date(&metadata);
+
+    let people_with_ages: Vec<(String, Option<u32>)> = metadata
+        .people
+        .iter()
+        .map(|person| {
+            let age = calculate_age(person.birth_date.as_deref(), photo_date);
+            (person.name.clone(), age)
+        })
+        .collect();
+
+    let tag_values: Vec<String> = metadata.tags.iter().map(|tag| tag.value.clone()).collect();
+
+    let mut context = PromptContext::new(base_prompt)
+        .with_file_info(metadata.original_file_name, metadata.r#type)
+        .with_people(people_with_ages)
+        .with_tags(tag_values)
+        .with_resolution(metadata.width, metadata.height)
+        .with_mime_type(metadata.original_mime_type);
+
+    if let Some(exif) = metadata.exif_info {
+        let created_at = exif.date_time_original.or(metadata.file_created_at);
+        context = context.with_created_at(created_at);
+
+        let location_parts: Vec<String> = [exif.city, exif.state, exif.country]
+            .into_iter()
+            .flatten()
+            .filter(|part| !part.is_empty())
+            .collect();
+
+        let location = if location_parts.is_empty() {
+            None
+        } else {
+            Some(location_parts.join(", "))
+        };
+
+        context = context
+            .with_location(location)
+            .with_camera_info(exif.make, exif.model)
+            .with_lens_model(exif.lens_model)
+            .with_exposure_settings(
+                exif.exposure_time,
+                exif.f_number,
+                exif.focal_length,
+                exif.iso,
+            )
+            .with_rating(exif.rating)
+            .with_time_zone(exif.time_zone)
+            .with_exif_description(exif.description);
+    } else {
+        context = context.with_created_at(metadata.file_created_at);
     }
+
+    context.build_enriched_prompt()
 }
@@ -0,0 +1,43 @@
+use log::debug;
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Spaces AI requests evenly so at most `max_rpm` start in any minute.
+#[derive(Debug)]
+pub struct RateLimiter {
+    interval: Duration,
+    next_slot: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    /// Limit AI requests to `max_rpm` per minute across all tasks, `None` when 0 (unlimited).
+    #[must_use]
+    pub fn new(max_rpm: u32) -> Option<Self> {
+        let interval = Duration::from_mins(1).checked_div(max_rpm)?;
+        Some(Self {
+            interval,
+            next_slot: Mutex::new(Instant::now()),
+        })
+    }
+
+    /// Wait until the next request slot is free.
+    ///
+    /// Slots are handed out in call order, so waiting tasks queue instead of bursting
+    /// into the provider's limit.
+    pub async fn acquire(&self) {
+        let slot = {
+            let mut next_slot = self.next_slot.lock().expect("next_slot mutex poisoned");
+            let slot = (*next_slot).max(Instant::now());
+            *next_slot = slot.checked_add(self.interval).unwrap_or(slot);
+            drop(next_slot);
+            slot
+        };
+        let wait = slot.saturating_duration_since(Instant::now());
+        if !wait.is_zero() {
+            debug!("Rate limit reached, waiting {wait:?} before sending request");
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
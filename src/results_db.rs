@@ -0,0 +1,151 @@
+use crate::error::ImageAnalysisError;
+use clap::ValueEnum;
+use log::debug;
+use rusqlite::{Connection, OptionalExtension as _, params};
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use uuid::Uuid;
+
+/// Where generated descriptions are written (`--write-target`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum WriteTarget {
+    /// Immich's database or API
+    #[default]
+    Immich,
+    /// Only the local `--results-db`; Immich is read but never written
+    ResultsDb,
+    /// Immich and the local `--results-db`
+    Both,
+}
+
+/// Local `SQLite` table receiving descriptions next to or instead of Immich (`--results-db`).
+///
+/// Cloning is cheap; clones share the connection.
+#[derive(Clone)]
+pub struct ResultsDb {
+    path: PathBuf,
+    target: WriteTarget,
+    /// Model name stored with every description
+    model: String,
+    connection: Arc<Mutex<Connection>>,
+}
+
+const CREATE_TABLE: &str = "
+    CREATE TABLE IF NOT EXISTS descriptions (
+        asset_id TEXT PRIMARY KEY,
+        description TEXT NOT NULL,
+        model TEXT NOT NULL,
+        updated_at INTEGER NOT NULL
+    )
+";
+
+impl ResultsDb {
+    /// Open the results database at `path`, creating it and its `descriptions` table if missing.
+    ///
+    /// # Arguments
+    /// * `target` - Whether Immich is written as well (`--write-target`)
+    /// * `model` - Model name stored with every description
+    pub async fn open(
+        path: &Path,
+        target: WriteTarget,
+        model: &str,
+    ) -> Result<Self, ImageAnalysisError> {
+        let db_path = path.to_path_buf();
+        let connection = tokio::task::spawn_blocking(move || {
+            let connection = Connection::open(&db_path)?;
+            connection.execute_batch(CREATE_TABLE)?;
+            Ok::<_, rusqlite::Error>(connection)
+        })
+        .await
+        .map_err(|err| results_db_error(path, &err))?
+        .map_err(|err| results_db_error(path, &err))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            target,
+            model: model.to_owned(),
+            connection: Arc::new(Mutex::new(connection)),
+        })
+    }
+
+    /// Whether descriptions are written to Immich too, i.e. the write target is not `results-db`.
+    #[must_use]
+    pub fn writes_immich(&self) -> bool {
+        self.target != WriteTarget::ResultsDb
+    }
+
+    /// Store `description` for an asset, replacing an earlier one.
+    pub async fn store(&self, asset_id: Uuid, description: &str) -> Result<(), ImageAnalysisError> {
+        let row = (
+            asset_id.to_string(),
+            description.to_owned(),
+            self.model.clone(),
+        );
+        self.run(move |connection| {
+            connection.execute(
+                "INSERT INTO descriptions (asset_id, description, model, updated_at)
+                 VALUES (?1, ?2, ?3, unixepoch())
+                 ON CONFLICT (asset_id) DO UPDATE
+                 SET description = excluded.description,
+                     model = excluded.model,
+                     updated_at = excluded.updated_at",
+                params![row.0, row.1, row.2],
+            )
+        })
+        .await?;
+        debug!("Stored description of asset {asset_id} in the results database");
+        Ok(())
+    }
+
+    /// When the description of an asset was stored, `None` if it has none.
+    pub async fn updated_at(
+        &self,
+        asset_id: &Uuid,
+    ) -> Result<Option<SystemTime>, ImageAnalysisError> {
+        let id = asset_id.to_string();
+        let seconds = self
+            .run(move |connection| {
+                connection
+                    .query_row(
+                        "SELECT updated_at FROM descriptions WHERE asset_id = ?1",
+                        params![id],
+                        |row| row.get::<_, i64>(0),
+                    )
+                    .optional()
+            })
+            .await?;
+        // A row always counts as described, even with a timestamp that does not fit
+        Ok(seconds.map(|secs| {
+            u64::try_from(secs)
+                .ok()
+                .and_then(|unix| UNIX_EPOCH.checked_add(Duration::from_secs(unix)))
+                .unwrap_or(UNIX_EPOCH)
+        }))
+    }
+
+    /// Run `query` on the connection without blocking the runtime.
+    async fn run<T, F>(&self, query: F) -> Result<T, ImageAnalysisError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> rusqlite::Result<T> + Send + 'static,
+    {
+        let connection = Arc::clone(&self.connection);
+        let result = tokio::task::spawn_blocking(move || {
+            let guard = connection
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner);
+            query(&guard)
+        })
+        .await
+        .map_err(|err| results_db_error(&self.path, &err))?;
+        result.map_err(|err| results_db_error(&self.path, &err))
+    }
+}
+
+fn results_db_error(path: &Path, err: &impl std::fmt::Display) -> ImageAnalysisError {
+    ImageAnalysisError::DatabaseError {
+        error: format!("results database {}: {err}", path.display()),
+    }
+}
@@ -0,0 +1,143 @@
+use crate::{error::ImageAnalysisError, file_processing::BatchSummary};
+use log::{info, warn};
+use reqwest::Client;
+use serde_json::Value;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+const WEBHOOK_TIMEOUT_SECS: u64 = 10;
+const FAILED_FILES_SAMPLE_SIZE: usize = 10;
+
+/// Sends batch summaries and host failure alerts to `--webhook-url`.
+#[derive(Debug)]
+pub struct WebhookNotifier {
+    client: Client,
+    url: String,
+    host_failure_threshold: usize,
+    host_failure_window: Duration,
+    host_failures: Mutex<HashMap<String, VecDeque<Instant>>>,
+}
+
+impl WebhookNotifier {
+    /// Creates a notifier posting to `url`.
+    ///
+    /// # Arguments
+    /// * `host_failure_threshold` - Failures of one host within the window that trigger an alert
+    /// * `host_failure_window` - Window in which host failures are counted
+    pub fn new(
+        url: &str,
+        host_failure_threshold: usize,
+        host_failure_window: Duration,
+    ) -> Result<Self, ImageAnalysisError> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(WEBHOOK_TIMEOUT_SECS))
+            .build()
+            .map_err(|err| ImageAnalysisError::HttpClientError {
+                error: err.to_string(),
+            })?;
+        Ok(Self {
+            client,
+            url: url.to_owned(),
+            host_failure_threshold,
+            host_failure_window,
+            host_failures: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Send the end-of-batch summary to the configured webhook.
+    pub async fn notify_batch_complete(&self, summary: &BatchSummary) {
+        let text = rust_i18n::t!(
+            "webhook.batch_complete",
+            successful = summary.successful.to_string(),
+            failed = summary.failed.to_string(),
+            skipped = summary.skipped.to_string()
+        )
+        .to_string();
+        let failed_sample: Vec<&String> = summary
+            .failed_files
+            .iter()
+            .take(FAILED_FILES_SAMPLE_SIZE)
+            .collect();
+        let payload = serde_json::json!({
+            "event": "batch_complete",
+            // Both keys are sent so Slack ("text") and Discord ("content") render the message
+            "text": text,
+            "content": text,
+            "successful": summary.successful,
+            "failed": summary.failed,
+            "skipped": summary.skipped,
+            "failed_files_sample": failed_sample,
+        });
+        self.send(&payload).await;
+    }
+
+    /// Record that a host was marked unavailable and alert once it has failed
+    /// more than the configured number of times within the failure window.
+    pub fn record_host_failure(self: &Arc<Self>, host: &str) {
+        let now = Instant::now();
+        let failure_count = {
+            let mut host_failures = self
+                .host_failures
+                .lock()
+                .expect("host_failures mutex poisoned");
+            let failures = host_failures.entry(host.to_owned()).or_default();
+            failures.push_back(now);
+            while failures
+                .front()
+                .is_some_and(|first| now.duration_since(*first) > self.host_failure_window)
+            {
+                failures.pop_front();
+            }
+            let count = failures.len();
+            if count > self.host_failure_threshold {
+                // Reset so the alert fires once per burst instead of on every further failure
+                failures.clear();
+            }
+            drop(host_failures);
+            count
+        };
+        if failure_count <= self.host_failure_threshold {
+            return;
+        }
+
+        let text = rust_i18n::t!(
+            "webhook.host_failures",
+            host = host,
+            count = failure_count.to_string(),
+            window = self.host_failure_window.as_secs().to_string()
+        )
+        .to_string();
+        let payload = serde_json::json!({
+            "event": "host_failures",
+            "text": text,
+            "content": text,
+            "host": host,
+            "failures": failure_count,
+            "window_seconds": self.host_failure_window.as_secs(),
+        });
+        let notifier = Arc::clone(self);
+        tokio::spawn(async move {
+            notifier.send(&payload).await;
+        });
+    }
+
+    async fn send(&self, payload: &Value) {
+        match self.client.post(&self.url).json(payload).send().await {
+            Ok(response) if response.status().is_success() => {
+                info!("Webhook notification delivered");
+            }
+            Ok(response) => {
+                warn!(
+                    "Webhook notification rejected with HTTP {}",
+                    response.status().as_u16()
+                );
+            }
+            Err(err) => {
+                warn!("Failed to send webhook notification: {err}");
+            }
+        }
+    }
+}